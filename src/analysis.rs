@@ -0,0 +1,377 @@
+//! Move hints and post-game review, from the view of a single seat.
+//!
+//! The hidden cards are dealt out at random in ways consistent with what
+//! the seat knows, and every legal action is scored in each of these
+//! sampled worlds: the heuristic bot plays the hand on until `depth` rounds
+//! are left, and the solver finishes it exactly. An action's value is the
+//! mean final points of the declarer team over the worlds.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::Card;
+use crate::common::{Contract, Hand, PartnerCondition, PlayAction, PlayerIndex};
+use crate::estimate::Estimator;
+use crate::friend::FriendInference;
+use crate::heuristic::{HeuristicPlayer, Weights};
+use crate::inference::Knowledge;
+use crate::play_phase::{EarlyEnd, ExposedGameState, PlayPhase};
+use crate::player::Player;
+use crate::record::GameRecord;
+use crate::solver::Solver;
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+
+#[derive(Clone, Debug)]
+pub struct Advisor {
+    /// Sampled deals of the hidden cards per position.
+    pub worlds: usize,
+    /// Rounds left from which a world is solved exactly instead of played
+    /// on by the heuristic bot.
+    pub depth: u8,
+    pub seed: u64,
+    /// Points lost from which a move is an inaccuracy.
+    pub inaccuracy: f64,
+    /// Points lost from which a move is a blunder.
+    pub blunder: f64,
+}
+
+impl Default for Advisor {
+    fn default() -> Self {
+        Advisor {
+            worlds: 32,
+            depth: 5,
+            seed: 0,
+            inaccuracy: 1.0,
+            blunder: 3.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Hint {
+    pub action: PlayAction,
+    /// Expected final points of the declarer team after the action.
+    pub points: f64,
+    /// Expected points the action is better by than the next best one,
+    /// for the side of the player to move.
+    pub delta: f64,
+    /// Chance of each seat holding the called friend card, while the mover
+    /// cannot see who does.
+    pub friend: Option<[f64; 5]>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Mistake {
+    Inaccuracy,
+    Blunder,
+}
+
+impl fmt::Display for Mistake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mistake::Inaccuracy => write!(f, "inaccuracy"),
+            Mistake::Blunder => write!(f, "blunder"),
+        }
+    }
+}
+
+/// A reviewed move, with the engine's choice in the same position.
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    /// Index of the move in `GameRecord::actions`.
+    pub action: usize,
+    pub player: PlayerIndex,
+    pub played: PlayAction,
+    pub best: PlayAction,
+    /// Expected points the move gave away against the best one.
+    pub loss: f64,
+    pub mistake: Option<Mistake>,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(mistake) = self.mistake {
+            write!(f, "{}: ", mistake)?;
+        }
+        if self.played == self.best {
+            return write!(f, "{} is the best move", self.played);
+        }
+        write!(
+            f,
+            "{} loses {:.1} points against {}",
+            self.played, self.loss, self.best
+        )
+    }
+}
+
+/// Whether `seat` plays for the declarer, as far as it knows. A friend
+/// chosen by round plays for the opposition until that round is over.
+pub fn on_declarer_side(seat: PlayerIndex, state: &ExposedGameState) -> bool {
+    seat == state.declarer
+        || state.partner_revealed == Some(seat)
+        || match state.partner_condition {
+            PartnerCondition::CardCondition(card) => state.hand.contains(&card),
+            PartnerCondition::Player(partner) => partner == seat,
+            PartnerCondition::Round(_) | PartnerCondition::None => false,
+        }
+}
+
+/// Who `seat` reckons holds the called friend card, or `None` when the
+/// friend is not chosen by card or the seat already knows them.
+fn friend_odds(seat: PlayerIndex, state: &ExposedGameState) -> Option<[f64; 5]> {
+    let PartnerCondition::CardCondition(card) = state.partner_condition else {
+        return None;
+    };
+    if state.partner_revealed.is_some() || state.hand.contains(&card) {
+        return None;
+    }
+    let inference = FriendInference::new(seat, state);
+    inference.probabilities().try_into().ok()
+}
+
+/// The cards a seat cannot see, with the seats that may hold them. The
+/// discards are a sixth holder when the seat has not seen them.
+pub(crate) struct Hidden {
+    knowledge: Knowledge,
+    pub cards: Vec<Card>,
+    /// How many hidden cards each holder has.
+    pub capacity: Vec<usize>,
+}
+
+impl Hidden {
+    /// `None` when the cards seen do not add up, which a real game rules out.
+    pub fn new(seat: PlayerIndex, state: &ExposedGameState) -> Option<Hidden> {
+        let knowledge = Knowledge::new(seat, state);
+        let cards = Card::deck()
+            .into_iter()
+            .filter(|c| {
+                !knowledge.played.contains_key(c)
+                    && !state.hand.contains(c)
+                    && !state.discarded.as_ref().is_some_and(|d| d.contains(c))
+            })
+            .collect::<Vec<_>>();
+        let mut capacity = (0..5)
+            .map(|p| {
+                if p == seat {
+                    0
+                } else {
+                    knowledge.hand_sizes[p]
+                }
+            })
+            .collect::<Vec<_>>();
+        capacity.push(if state.discarded.is_some() { 0 } else { 3 });
+        (capacity.iter().sum::<usize>() == cards.len()).then_some(Hidden {
+            knowledge,
+            cards,
+            capacity,
+        })
+    }
+
+    pub fn allowed(&self, card: Card, holder: usize) -> bool {
+        holder == 5 || self.knowledge.can_hold(holder, card)
+    }
+
+    /// The game with the hidden cards dealt to `holders`, the seat's own
+    /// hand filled in.
+    pub fn world(&self, state: &ExposedGameState, mut holders: Vec<Hand>) -> PlayPhase {
+        let discarded = holders.pop().unwrap();
+        holders[self.knowledge.seat] = state.hand.clone();
+        PlayPhase {
+            hands: holders,
+            declarer: state.declarer,
+            contract: state.contract,
+            partner_condition: state.partner_condition,
+            discarded: state.discarded.clone().unwrap_or(discarded),
+            partner_revealed: state.partner_revealed,
+            round: state.round,
+            joker_called: state.joker_called,
+            submitted: state.submitted.clone(),
+            round_starter: state.round_starter,
+            round_suit: state.round_suit,
+            round_results: state.round_results.clone(),
+            early_end: EarlyEnd::Never,
+        }
+    }
+}
+
+/// A full deal of the hidden cards consistent with what `seat` knows: its
+/// own hand, the cards played, the suits others are void in and, for the
+/// declarer, the discards. `None` when no deal was found in a few tries.
+pub fn sample_world(
+    seat: PlayerIndex,
+    state: &ExposedGameState,
+    rng: &mut StdRng,
+) -> Option<PlayPhase> {
+    let hidden = Hidden::new(seat, state)?;
+    'attempt: for _ in 0..100 {
+        // The cards with the fewest possible holders go first.
+        let mut cards = hidden.cards.clone();
+        cards.shuffle(rng);
+        cards.sort_by_key(|&c| (0..6).filter(|&h| hidden.allowed(c, h)).count());
+        let mut left = hidden.capacity.clone();
+        let mut holders = vec![Hand::new(); 6];
+        for card in cards {
+            let room = (0..6)
+                .filter(|&h| hidden.allowed(card, h))
+                .map(|h| (h, left[h]))
+                .collect::<Vec<_>>();
+            let total = room.iter().map(|&(_, n)| n).sum::<usize>();
+            if total == 0 {
+                continue 'attempt;
+            }
+            let mut pick = rng.random_range(0..total);
+            let &(holder, _) = room
+                .iter()
+                .find(|&&(_, n)| {
+                    let found = pick < n;
+                    pick = pick.saturating_sub(n);
+                    found
+                })
+                .unwrap();
+            left[holder] -= 1;
+            holders[holder].insert(card);
+        }
+        return Some(hidden.world(state, holders));
+    }
+    None
+}
+
+impl Advisor {
+    /// The expected final points of the declarer team after each legal
+    /// action of `seat`, best for `seat` first. Empty when no world could
+    /// be sampled.
+    pub fn evaluate(&self, seat: PlayerIndex, state: &ExposedGameState) -> Vec<(PlayAction, f64)> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let actions = state.legal_actions();
+        let mut sums = vec![0.0; actions.len()];
+        let mut worlds = 0;
+        for _ in 0..self.worlds {
+            let Some(world) = sample_world(seat, state, &mut rng) else {
+                continue;
+            };
+            worlds += 1;
+            for (sum, points) in sums.iter_mut().zip(self.action_points(&world, &actions)) {
+                *sum += points as f64;
+            }
+        }
+        if worlds == 0 {
+            return vec![];
+        }
+
+        let mut values = actions
+            .into_iter()
+            .zip(sums.into_iter().map(|s| s / worlds as f64))
+            .collect::<Vec<_>>();
+        if on_declarer_side(seat, state) {
+            values.sort_by(|a, b| b.1.total_cmp(&a.1));
+        } else {
+            values.sort_by(|a, b| a.1.total_cmp(&b.1));
+        }
+        values
+    }
+
+    /// The likeliest contract to make among the lowest legal bids, with
+    /// its odds from as many deals as there are worlds.
+    pub fn bid_hint(&self, state: &BiddingState) -> Option<(Contract, f64)> {
+        let mut estimator = Estimator::new("heuristic").unwrap();
+        estimator.samples = self.worlds;
+        estimator.depth = self.depth;
+        estimator.seed = self.seed;
+        let odds = estimator.estimate(&state.hand).ok()?;
+        odds.best_bid(state.min_effective_count)
+    }
+
+    /// The best action of `seat`, as `evaluate` ranks them.
+    pub fn hint(&self, seat: PlayerIndex, state: &ExposedGameState) -> Option<Hint> {
+        let values = self.evaluate(seat, state);
+        let &(action, points) = values.first()?;
+        let delta = values
+            .get(1)
+            .map_or(0.0, |&(_, next)| (points - next).abs());
+        Some(Hint {
+            action,
+            points,
+            delta,
+            friend: friend_odds(seat, state),
+        })
+    }
+
+    /// Final points of the declarer team in `world` after each of
+    /// `actions` by the player to move.
+    fn action_points(&self, world: &PlayPhase, actions: &[PlayAction]) -> Vec<u8> {
+        let mut solver = Solver::new();
+        if 10 - world.round <= self.depth {
+            let values = solver.action_values(world);
+            return actions
+                .iter()
+                .map(|a| values.iter().find(|(b, _)| a == b).unwrap().1)
+                .collect();
+        }
+        let bot = HeuristicPlayer::new(Weights::default());
+        let player = world.turn_player().unwrap();
+        actions
+            .iter()
+            .map(|&action| {
+                let mut game = world.clone();
+                game.player_acts(player, action);
+                while 10 - game.round > self.depth {
+                    let next = game.turn_player().unwrap();
+                    let action = bot.play_action(game.play_state(next));
+                    game.player_acts(next, action);
+                }
+                solver.solve(&game).points
+            })
+            .collect()
+    }
+
+    /// Compares every move of `seats` in the record with the engine's
+    /// choice from the mover's view. Moves without a choice are left out.
+    pub fn review(
+        &self,
+        record: &GameRecord,
+        seats: &[PlayerIndex],
+    ) -> Result<Vec<Annotation>, String> {
+        record.play_phase(record.actions.len())?;
+        let mut game = record.play_phase(0)?;
+        let mut annotations = vec![];
+        for (i, &(player, played)) in record.actions.iter().enumerate() {
+            let state = game.play_state(player);
+            if seats.contains(&player) && state.legal_actions().len() > 1 {
+                let values = self.evaluate(player, &state);
+                if let (Some(&(best, best_points)), Some(&(_, points))) =
+                    (values.first(), values.iter().find(|(a, _)| *a == played))
+                {
+                    let loss = (best_points - points).abs();
+                    let mistake = if loss >= self.blunder {
+                        Some(Mistake::Blunder)
+                    } else if loss >= self.inaccuracy {
+                        Some(Mistake::Inaccuracy)
+                    } else {
+                        None
+                    };
+                    annotations.push(Annotation {
+                        action: i,
+                        player,
+                        played,
+                        best,
+                        loss,
+                        mistake,
+                    });
+                }
+            }
+            game.player_acts(player, played);
+        }
+        Ok(annotations)
+    }
+}
+
+/// Notes every inaccuracy and blunder of `annotations` in `record`.
+pub fn mark_mistakes(record: &mut GameRecord, annotations: &[Annotation]) {
+    let start = record.bids.len() + 1;
+    for annotation in annotations.iter().filter(|a| a.mistake.is_some()) {
+        record
+            .notes
+            .push((start + annotation.action, annotation.to_string()));
+    }
+}
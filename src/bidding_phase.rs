@@ -1,13 +1,18 @@
-use crate::card::{Card, Suit};
+use crate::card::Card;
 use crate::common::{Contract, Hand, PlayerIndex, MAX_EFFECTIVE_COUNT};
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{rng, Rng, SeedableRng};
 use std::collections::HashSet;
 
 #[derive(Clone, Debug)]
 pub struct BiddingState {
     pub hand: Hand,
     pub curr_contract: Option<Contract>,
+    /// A bid must have a greater effective count than this.
+    pub min_effective_count: u8,
+    /// Players yet to pass, the bidder included.
+    pub players_remaining: usize,
     // TODO: consider other players' contracts
 }
 
@@ -31,18 +36,37 @@ pub struct PledgePhase {
 
 impl PledgePhase {
     pub fn new(start_player: PlayerIndex, min_pledge: u8) -> Self {
-        let mut cards = vec![Card::Joker];
-        for suit in [Suit::H, Suit::D, Suit::C, Suit::S] {
-            for n in 2..=14 {
-                cards.push(Card::Shaped(suit, n));
-            }
-        }
-        cards.shuffle(&mut thread_rng());
+        PledgePhase::with_rng(start_player, min_pledge, &mut rng())
+    }
+
+    /// The same deal for the same seed, to replay a hand with other players.
+    pub fn seeded(start_player: PlayerIndex, min_pledge: u8, seed: u64) -> Self {
+        PledgePhase::with_rng(start_player, min_pledge, &mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn with_rng<R: Rng + ?Sized>(
+        start_player: PlayerIndex,
+        min_pledge: u8,
+        rng: &mut R,
+    ) -> Self {
+        let mut cards = Card::deck();
+        cards.shuffle(rng);
         let mut hands: Vec<Hand> = vec![];
         for _ in 0..5 {
             hands.push(cards.drain(0..10).collect());
         }
         let bottom = cards.iter().cloned().collect();
+        PledgePhase::from_deal(start_player, min_pledge, hands, bottom)
+    }
+
+    /// A given deal, e.g. one read back from a game record.
+    pub fn from_deal(
+        start_player: PlayerIndex,
+        min_pledge: u8,
+        hands: Vec<Hand>,
+        bottom: HashSet<Card>,
+    ) -> Self {
+        assert_eq!(hands.len(), 5);
         let mut players_queue = (0..5).collect::<Vec<PlayerIndex>>();
         players_queue.rotate_left(start_player);
         PledgePhase {
@@ -59,6 +83,8 @@ impl PledgePhase {
         BiddingState {
             hand: self.hands[player].clone(),
             curr_contract: self.curr_contract,
+            min_effective_count: self.min_effective_count,
+            players_remaining: self.players_queue.len(),
         }
     }
 
@@ -84,7 +110,7 @@ impl PledgePhase {
                 self.players_queue.len() <= 1
                     || curr_contract.effective_count() >= MAX_EFFECTIVE_COUNT
             }
-            None => self.players_queue.len() == 0,
+            None => self.players_queue.is_empty(),
         }
     }
 
@@ -93,6 +119,6 @@ impl PledgePhase {
     }
 
     pub fn cancelled(&self) -> bool {
-        self.players_queue.len() == 0
+        self.players_queue.is_empty()
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
 pub enum Suit {
     H,
     D,
@@ -8,7 +9,7 @@ pub enum Suit {
     S,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Card {
     Shaped(Suit, u8),
     Joker,
@@ -38,7 +39,120 @@ impl fmt::Debug for Card {
     }
 }
 
+/// Plain ASCII notation such as `SA`, `H10` or `JK`, for files and the
+/// command line.
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Card::Joker => write!(f, "JK"),
+            Card::Shaped(s, n) => {
+                write!(f, "{}", s)?;
+                match n {
+                    11 => write!(f, "J"),
+                    12 => write!(f, "Q"),
+                    13 => write!(f, "K"),
+                    14 => write!(f, "A"),
+                    n => write!(f, "{}", n),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("JK") {
+            return Ok(Card::Joker);
+        }
+        let suit = s.get(..1).ok_or(format!("invalid card: {}", s))?.parse()?;
+        let n = match &s[1..] {
+            "J" | "j" => 11,
+            "Q" | "q" => 12,
+            "K" | "k" => 13,
+            "A" | "a" => 14,
+            n => match n.parse() {
+                Ok(n @ 2..=10) => n,
+                _ => return Err(format!("invalid card: {}", s)),
+            },
+        };
+        Ok(Card::Shaped(suit, n))
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Suit::H => write!(f, "H"),
+            Suit::D => write!(f, "D"),
+            Suit::C => write!(f, "C"),
+            Suit::S => write!(f, "S"),
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "H" | "h" => Ok(Suit::H),
+            "D" | "d" => Ok(Suit::D),
+            "C" | "c" => Ok(Suit::C),
+            "S" | "s" => Ok(Suit::S),
+            _ => Err(format!("invalid suit: {}", s)),
+        }
+    }
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::H, Suit::D, Suit::C, Suit::S];
+
+    pub fn index(&self) -> usize {
+        match self {
+            Suit::H => 0,
+            Suit::D => 1,
+            Suit::C => 2,
+            Suit::S => 3,
+        }
+    }
+}
+
 impl Card {
+    pub fn deck() -> Vec<Card> {
+        let mut cards = vec![Card::Joker];
+        for suit in Suit::ALL {
+            for n in 2..=14 {
+                cards.push(Card::Shaped(suit, n));
+            }
+        }
+        cards
+    }
+
+    /// Dense index in `0..53`, with the joker last.
+    pub fn index(&self) -> usize {
+        match self {
+            Card::Shaped(s, n) => s.index() * 13 + (*n as usize - 2),
+            Card::Joker => 52,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Card {
+        if index == 52 {
+            Card::Joker
+        } else {
+            Card::Shaped(Suit::ALL[index / 13], (index % 13) as u8 + 2)
+        }
+    }
+
+    pub fn mighty(trump: Option<Suit>) -> Card {
+        match trump {
+            Some(Suit::S) => Card::Shaped(Suit::D, 14),
+            _ => Card::Shaped(Suit::S, 14),
+        }
+    }
+
     pub fn is_mighty(&self, trump: Option<Suit>) -> bool {
         match self {
             Card::Shaped(suit, n) => match trump {
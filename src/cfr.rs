@@ -0,0 +1,450 @@
+//! Bidding by counterfactual regret minimization over an abstraction of the
+//! auction.
+//!
+//! A bidder is seen only through an `InfoSet`: how strong the hand is for
+//! each trump and for no trump, in four buckets, the effective count of the
+//! current contract and the players yet to pass. A bid is always made in
+//! the denomination the hand is strongest for, so the abstract actions are
+//! to pass or to raise by the least amount, by one more or by two more.
+//!
+//! `Cfr` runs Monte Carlo CFR with external sampling over random deals. A
+//! finished auction is worth the payouts of playing the hand out, with the
+//! declarer's plan made by `default_plan` and the cards played by bots;
+//! each deal plays each (declarer, contract) ending out once. The average
+//! strategy is saved as a text table, one information set a line, that a
+//! `CfrPlayer` follows.
+
+use crate::bidding_phase::{BiddingState, PledgePhase};
+use crate::card::{Card, Suit};
+use crate::clock::default_plan;
+use crate::common::{Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::extra_phase::{ExtraExposedState, ExtraPhase};
+use crate::play_phase::ExposedGameState;
+use crate::player::{check_player, create_player, Player, RandomPlayer};
+use crate::rules::RuleSet;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Pass, or raise by 0, 1 or 2 over the least bid allowed.
+pub const ACTIONS: usize = 4;
+
+/// Starts every strategy table.
+const HEADER: &str = "# mighty-rs bidding strategy v1";
+
+/// What a bidder's decision depends on in the abstraction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct InfoSet {
+    /// The strength bucket of the hand, from 0 to 3, for each trump in the
+    /// order of `Suit::ALL` and then for no trump.
+    pub strength: [u8; 5],
+    /// The effective count of the current contract.
+    pub contract: Option<u8>,
+    pub players_remaining: u8,
+}
+
+/// How many tricks the hand might take with `trump`, roughly: the cards of
+/// the suit and its honours, the mighty and the joker; without trump, the
+/// aces, the mighty and the joker.
+fn strength(hand: &HashSet<Card>, trump: Option<Suit>) -> u8 {
+    hand.iter()
+        .map(|&card| match (card, trump) {
+            (Card::Joker, _) => 2,
+            _ if card.is_mighty(trump) => 2,
+            (Card::Shaped(suit, rank), Some(trump)) if suit == trump => 1 + (rank >= 12) as u8,
+            (Card::Shaped(_, rank), None) => (rank == 14) as u8,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn bucket(strength: u8, trump: Option<Suit>) -> u8 {
+    let steps: [u8; 3] = match trump {
+        Some(_) => [5, 7, 9],
+        None => [3, 4, 6],
+    };
+    steps.iter().filter(|&&step| strength >= step).count() as u8
+}
+
+/// The trumps in the order of `InfoSet::strength`.
+fn denominations() -> [Option<Suit>; 5] {
+    let mut all = [None; 5];
+    for (i, suit) in Suit::ALL.into_iter().enumerate() {
+        all[i] = Some(suit);
+    }
+    all
+}
+
+impl InfoSet {
+    pub fn new(state: &BiddingState) -> Self {
+        let mut strength = [0; 5];
+        for (i, trump) in denominations().into_iter().enumerate() {
+            strength[i] = bucket(self::strength(&state.hand, trump), trump);
+        }
+        InfoSet {
+            strength,
+            contract: state.curr_contract.map(|c| c.effective_count()),
+            players_remaining: state.players_remaining as u8,
+        }
+    }
+}
+
+/// The denomination the hand bids in: the trump of the highest bucket,
+/// ties going to the stronger suit, or no trump when its bucket is above
+/// every suit's.
+pub fn denomination(hand: &HashSet<Card>) -> Option<Suit> {
+    let suit = Suit::ALL
+        .into_iter()
+        .max_by_key(|&suit| {
+            let strength = strength(hand, Some(suit));
+            (bucket(strength, Some(suit)), strength)
+        })
+        .unwrap();
+    let no_trump = bucket(strength(hand, None), None);
+    if no_trump > bucket(strength(hand, Some(suit)), Some(suit)) {
+        None
+    } else {
+        Some(suit)
+    }
+}
+
+/// The bid of an abstract action, or `None` for a raise above the highest
+/// contract. Action 0 passes.
+pub fn abstract_bid(state: &BiddingState, action: usize) -> Option<Option<Contract>> {
+    if action == 0 {
+        return Some(None);
+    }
+    let suit = denomination(&state.hand);
+    let effective = state.min_effective_count + action as u8;
+    let count = match suit {
+        Some(_) => effective,
+        None => effective - 1,
+    };
+    (count <= 20).then_some(Some(Contract { suit, count }))
+}
+
+/// The probability of each action by regret matching, over the legal ones.
+fn matched(regrets: &[f64; ACTIONS], legal: &[bool; ACTIONS]) -> [f64; ACTIONS] {
+    let mut strategy = [0.0; ACTIONS];
+    for a in 0..ACTIONS {
+        if legal[a] {
+            strategy[a] = regrets[a].max(0.0);
+        }
+    }
+    normalized(strategy, legal)
+}
+
+/// `weights` scaled to sum to one, or uniform over the legal actions when
+/// they are all zero.
+fn normalized(mut weights: [f64; ACTIONS], legal: &[bool; ACTIONS]) -> [f64; ACTIONS] {
+    let sum = weights.iter().sum::<f64>();
+    let count = legal.iter().filter(|&&l| l).count() as f64;
+    for a in 0..ACTIONS {
+        weights[a] = match (legal[a], sum > 0.0) {
+            (false, _) => 0.0,
+            (true, true) => weights[a] / sum,
+            (true, false) => 1.0 / count,
+        };
+    }
+    weights
+}
+
+fn legal_actions(state: &BiddingState) -> [bool; ACTIONS] {
+    let mut legal = [false; ACTIONS];
+    for (a, l) in legal.iter_mut().enumerate() {
+        *l = abstract_bid(state, a).is_some();
+    }
+    legal
+}
+
+fn sample(strategy: &[f64; ACTIONS], rng: &mut impl Rng) -> usize {
+    let mut point = rng.random::<f64>();
+    let last = (0..ACTIONS).rev().find(|&a| strategy[a] > 0.0).unwrap_or(0);
+    (0..ACTIONS)
+        .find(|&a| {
+            point -= strategy[a];
+            point < 0.0 && strategy[a] > 0.0
+        })
+        .unwrap_or(last)
+}
+
+/// The declarer and contract an auction ended in, unless it was cancelled.
+type Ending = Option<(PlayerIndex, Option<Suit>, u8)>;
+
+#[derive(Clone, Default, Debug)]
+struct Node {
+    regrets: [f64; ACTIONS],
+    strategy_sum: [f64; ACTIONS],
+}
+
+/// The solver. Each iteration deals a hand and traverses the auction once
+/// for each seat.
+pub struct Cfr {
+    pub rules: RuleSet,
+    /// The bot playing the cards of every seat in the play-outs.
+    pub playout: String,
+    nodes: HashMap<InfoSet, Node>,
+    rng: StdRng,
+    iterations: usize,
+}
+
+impl Cfr {
+    pub fn new(rules: RuleSet, playout: &str, seed: u64) -> Result<Self, String> {
+        check_player(playout)?;
+        Ok(Cfr {
+            rules,
+            playout: playout.to_string(),
+            nodes: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+            iterations: 0,
+        })
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Information sets visited so far.
+    pub fn info_sets(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn iterate(&mut self, iterations: usize) {
+        let players = (0..5)
+            .map(|_| create_player(&self.playout).unwrap())
+            .collect::<Vec<_>>();
+        for _ in 0..iterations {
+            let start = self.rng.random_range(0..5);
+            let deal = PledgePhase::with_rng(start, self.rules.min_pledge, &mut self.rng);
+            let mut payouts = HashMap::new();
+            for traverser in 0..5 {
+                self.traverse(&deal, traverser, &players, &mut payouts);
+            }
+            self.iterations += 1;
+        }
+    }
+
+    /// The value of `game` to `traverser`, updating the regrets of the
+    /// traverser and the average strategy of the others on the way.
+    fn traverse(
+        &mut self,
+        game: &PledgePhase,
+        traverser: PlayerIndex,
+        players: &[Box<dyn Player>],
+        payouts: &mut HashMap<Ending, Vec<i32>>,
+    ) -> f64 {
+        if game.pledge_done() {
+            let ending = game
+                .curr_contract
+                .map(|c| (game.players_queue[0], c.suit, c.count));
+            let payout = payouts
+                .entry(ending)
+                .or_insert_with(|| self.play_out(game, players));
+            return payout[traverser] as f64;
+        }
+        let player = game.turn_player();
+        let state = game.bidding_state(player);
+        let info = InfoSet::new(&state);
+        let legal = legal_actions(&state);
+        let node = self.nodes.entry(info).or_default();
+        let strategy = matched(&node.regrets, &legal);
+        let child = |action: usize| {
+            let mut next = game.clone();
+            next.player_bids(player, abstract_bid(&state, action).unwrap());
+            next
+        };
+
+        if player != traverser {
+            for (sum, p) in node.strategy_sum.iter_mut().zip(strategy) {
+                *sum += p;
+            }
+            let action = sample(&strategy, &mut self.rng);
+            return self.traverse(&child(action), traverser, players, payouts);
+        }
+        let mut values = [0.0; ACTIONS];
+        let mut value = 0.0;
+        for a in (0..ACTIONS).filter(|&a| legal[a]) {
+            values[a] = self.traverse(&child(a), traverser, players, payouts);
+            value += strategy[a] * values[a];
+        }
+        let node = self.nodes.get_mut(&info).unwrap();
+        for a in (0..ACTIONS).filter(|&a| legal[a]) {
+            node.regrets[a] += values[a] - value;
+        }
+        value
+    }
+
+    /// The payouts of the finished auction `game`, played out once.
+    fn play_out(&self, game: &PledgePhase, players: &[Box<dyn Player>]) -> Vec<i32> {
+        if game.cancelled() {
+            return vec![0; 5];
+        }
+        let mut extra = ExtraPhase::from_pledge(game.clone());
+        let (contract, friend, discards) = default_plan(&extra.extra_state());
+        let mut game = extra.submit_plan(contract, friend, discards);
+        while let Some(player) = game.turn_player() {
+            let action = players[player].play_action(game.play_state(player));
+            game.player_acts(player, action);
+            for (i, player) in players.iter().enumerate() {
+                player.observe(&game.play_state(i));
+            }
+        }
+        self.rules.settle(&game).payouts
+    }
+
+    /// The average strategy of every information set visited.
+    pub fn strategy(&self) -> Strategy {
+        let all = [true; ACTIONS];
+        let table = self
+            .nodes
+            .iter()
+            .map(|(&info, node)| (info, normalized(node.strategy_sum, &all)))
+            .collect();
+        Strategy { table }
+    }
+}
+
+/// A bidding strategy: the probability of each abstract action in each
+/// information set.
+#[derive(Clone, Default, Debug)]
+pub struct Strategy {
+    pub table: HashMap<InfoSet, [f64; ACTIONS]>,
+}
+
+impl Strategy {
+    pub fn load(path: impl AsRef<Path>) -> Result<Strategy, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        text.parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// The probability of each action in the information set of `state`,
+    /// over the legal actions; `None` for an information set never visited.
+    pub fn probabilities(&self, state: &BiddingState) -> Option<[f64; ACTIONS]> {
+        let weights = *self.table.get(&InfoSet::new(state))?;
+        let legal = legal_actions(state);
+        let mut masked = [0.0; ACTIONS];
+        for a in (0..ACTIONS).filter(|&a| legal[a]) {
+            masked[a] = weights[a];
+        }
+        Some(normalized(masked, &legal))
+    }
+}
+
+/// A header line, then a line for each information set: the five strength
+/// buckets, the contract's effective count or `-`, the players remaining
+/// and the probabilities of passing and of the three raises.
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        let mut rows = self.table.iter().collect::<Vec<_>>();
+        rows.sort_by_key(|(info, _)| (info.strength, info.contract, info.players_remaining));
+        for (info, probabilities) in rows {
+            for bucket in info.strength {
+                write!(f, "{} ", bucket)?;
+            }
+            match info.contract {
+                Some(count) => write!(f, "{} ", count)?,
+                None => write!(f, "- ")?,
+            }
+            write!(f, "{}", info.players_remaining)?;
+            for p in probabilities {
+                write!(f, " {:.4}", p)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a bidding strategy".to_string());
+        }
+        let mut table = HashMap::new();
+        for (i, line) in lines.enumerate() {
+            let invalid = || format!("invalid line {}: {}", i + 2, line);
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            if words.len() != 7 + ACTIONS {
+                return Err(invalid());
+            }
+            let mut strength = [0; 5];
+            for (b, word) in strength.iter_mut().zip(&words) {
+                *b = word.parse().ok().filter(|&b| b < 4).ok_or_else(invalid)?;
+            }
+            let contract = match words[5] {
+                "-" => None,
+                count => Some(count.parse().map_err(|_| invalid())?),
+            };
+            let players_remaining = words[6].parse().map_err(|_| invalid())?;
+            let mut probabilities = [0.0; ACTIONS];
+            for (p, word) in probabilities.iter_mut().zip(&words[7..]) {
+                *p = word.parse().map_err(|_| invalid())?;
+            }
+            let info = InfoSet {
+                strength,
+                contract,
+                players_remaining,
+            };
+            table.insert(info, probabilities);
+        }
+        Ok(Strategy { table })
+    }
+}
+
+/// Bids by drawing from a strategy table, passing in an information set the
+/// table lacks. The declarer's plan is `default_plan` and cards are played
+/// as `RandomPlayer` plays them.
+pub struct CfrPlayer {
+    pub strategy: Strategy,
+    rng: RefCell<StdRng>,
+}
+
+impl CfrPlayer {
+    pub fn new(strategy: Strategy) -> Self {
+        CfrPlayer {
+            strategy,
+            rng: RefCell::new(StdRng::from_rng(&mut rand::rng())),
+        }
+    }
+
+    /// Seeds the draws, for games that can be played again.
+    pub fn with_seed(self, seed: u64) -> Self {
+        *self.rng.borrow_mut() = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Player for CfrPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        let probabilities = self.strategy.probabilities(state)?;
+        let action = sample(&probabilities, &mut *self.rng.borrow_mut());
+        abstract_bid(state, action).flatten()
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        default_plan(&state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        RandomPlayer {}.play_action(state)
+    }
+}
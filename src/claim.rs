@@ -0,0 +1,193 @@
+//! Claims of the rest of a hand.
+//!
+//! A player may assert that their side takes at least so many of the
+//! points, or rounds, still to be played. The claim stands only if one line
+//! of play of the player's own cards makes it however the cards the player
+//! cannot see lie, and whatever everyone else plays, the player's partner
+//! included. A claim that needs a guess, such as which side of a finesse
+//! a card is on, is rejected. An accepted claim is settled by playing the
+//! hand out as the solver would, which never gives the side less than it
+//! claimed.
+
+use crate::analysis::{on_declarer_side, Hidden};
+use crate::common::{Claim, Hand, PartnerCondition, PlayAction, PlayerIndex};
+use crate::play_phase::PlayPhase;
+use crate::solver::{Position, Solver};
+
+/// Claims are only checked when the hidden cards can lie in at most this
+/// many ways; earlier ones are rejected.
+pub const MAX_WORLDS: usize = 100_000;
+
+/// Checks the claim of `player` and returns the play from here to the end
+/// of the hand that settles it, or why the claim is rejected.
+pub fn check_claim(
+    game: &PlayPhase,
+    player: PlayerIndex,
+    claim: Claim,
+) -> Result<Vec<(PlayerIndex, PlayAction)>, String> {
+    if game.is_done() {
+        return Err("the hand is over".to_string());
+    }
+    if matches!(game.partner_condition, PartnerCondition::Round(_))
+        && game.partner_revealed.is_none()
+    {
+        return Err("the friend is not known yet".to_string());
+    }
+    let (mut solver, claimed, tricks) = match claim {
+        Claim::Points(n) => (Solver::new(), n, false),
+        Claim::Tricks(n) => (Solver::counting_tricks(), n, true),
+    };
+    let state = game.play_state(player);
+    let declarer_side = on_declarer_side(player, &state);
+    let hidden = Hidden::new(player, &state).ok_or("the cards seen do not add up")?;
+
+    let mut worlds = 0;
+    each_deal(&hidden, &mut |_| {
+        worlds += 1;
+        worlds <= MAX_WORLDS
+    });
+    if worlds > MAX_WORLDS {
+        return Err("too early to check the claim".to_string());
+    }
+    // Making the claim with every hand open in each lie is needed, and
+    // quick to rule out.
+    let mut positions = vec![];
+    let holds = each_deal(&hidden, &mut |holders| {
+        let world = hidden.world(&state, holders.to_vec());
+        let position = solver.position(&world);
+        positions.push((position, side_points(&position, declarer_side)));
+        taken(&mut solver, &world, declarer_side, tricks) >= claimed
+    });
+    if !holds {
+        return Err("the claim fails with some lie of the cards".to_string());
+    }
+    if !guaranteed(&positions, player, declarer_side, claimed) {
+        return Err("the claim needs a guess at how the cards lie".to_string());
+    }
+
+    let mut game = game.clone();
+    let mut line = vec![];
+    while let Some(player) = game.turn_player() {
+        let action = solver.solve(&game).action.unwrap();
+        game.player_acts(player, action);
+        line.push((player, action));
+    }
+    Ok(line)
+}
+
+/// What the side of the claimant has taken in `position`.
+fn side_points(position: &Position, declarer_side: bool) -> u8 {
+    if declarer_side {
+        position.team_points()
+    } else {
+        position.total_points() - position.team_points()
+    }
+}
+
+/// Whether one line of play of the claimant's own cards takes `claimed`
+/// more for their side in every world, against any play by everyone else.
+/// Each world comes with what the side had taken at the claim. The worlds
+/// share every card played, so the claimant picks one action for all of
+/// them; another player may play any card they hold in some world, which
+/// keeps the worlds where they hold it.
+fn guaranteed(
+    worlds: &[(Position, u8)],
+    claimant: PlayerIndex,
+    declarer_side: bool,
+    claimed: u8,
+) -> bool {
+    let gained = |(world, before): &(Position, u8)| side_points(world, declarer_side) - before;
+    if worlds.iter().all(|w| gained(w) >= claimed) {
+        return true;
+    }
+    if worlds.iter().any(|w| gained(w) + w.0.remaining() < claimed) {
+        return false;
+    }
+    // Some world is short of the claim with points left, so the hand goes on.
+    let player = worlds[0].0.turn_player().unwrap();
+    let after = |action: PlayAction| {
+        worlds
+            .iter()
+            .filter(|(world, _)| world.legal_actions(player).contains(&action))
+            .map(|&(mut world, before)| {
+                world.apply(player, action);
+                (world, before)
+            })
+            .collect::<Vec<_>>()
+    };
+    let guaranteed_after = |action| guaranteed(&after(action), claimant, declarer_side, claimed);
+    if player == claimant {
+        let actions = worlds[0].0.legal_actions(player);
+        actions.into_iter().any(guaranteed_after)
+    } else {
+        let mut actions = worlds
+            .iter()
+            .flat_map(|(world, _)| world.legal_actions(player))
+            .collect::<Vec<_>>();
+        actions.sort();
+        actions.dedup();
+        actions.into_iter().all(guaranteed_after)
+    }
+}
+
+/// Visits every way the hidden cards may lie, until `visit` returns false.
+/// Returns whether every deal was visited.
+fn each_deal(hidden: &Hidden, visit: &mut dyn FnMut(&[Hand]) -> bool) -> bool {
+    let mut left = hidden.capacity.clone();
+    deal(hidden, 0, &mut left, &mut vec![Hand::new(); 6], visit)
+}
+
+/// Gives the hidden cards from the `next` one on to the holders with room
+/// left, visiting each full deal.
+fn deal(
+    hidden: &Hidden,
+    next: usize,
+    left: &mut [usize],
+    holders: &mut Vec<Hand>,
+    visit: &mut dyn FnMut(&[Hand]) -> bool,
+) -> bool {
+    let Some(&card) = hidden.cards.get(next) else {
+        return visit(holders);
+    };
+    for holder in 0..6 {
+        if left[holder] == 0 || !hidden.allowed(card, holder) {
+            continue;
+        }
+        left[holder] -= 1;
+        holders[holder].insert(card);
+        let go_on = deal(hidden, next + 1, left, holders, visit);
+        holders[holder].remove(&card);
+        left[holder] += 1;
+        if !go_on {
+            return false;
+        }
+    }
+    true
+}
+
+/// What the side of the claimant takes from here on in `world` with best
+/// play, in rounds when counting `tricks` and in points otherwise.
+fn taken(solver: &mut Solver, world: &PlayPhase, declarer_side: bool, tricks: bool) -> u8 {
+    // A finished hand is worth what the declarer team has taken so far.
+    let before = solver
+        .solve(&PlayPhase {
+            round: 10,
+            ..world.clone()
+        })
+        .points;
+    let declarer_team = solver.solve(world).points - before;
+    if declarer_side {
+        return declarer_team;
+    }
+    let remaining = if tricks {
+        10 - world.round
+    } else {
+        let in_play = world
+            .hands
+            .iter()
+            .flatten()
+            .chain(world.submitted.iter().flatten());
+        in_play.map(|c| c.score()).sum()
+    };
+    remaining - declarer_team
+}
@@ -0,0 +1,225 @@
+use crate::common::PlayerIndex;
+use crate::event::Event;
+use crate::json::Json;
+use crate::lobby::RoomInfo;
+use crate::player::Player;
+use crate::protocol::{ClientMessage, ServerMessage};
+use crate::rules::RuleSet;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+/// A seat at a remote table.
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    pub seat: PlayerIndex,
+    /// The session token, for taking the seat back after a disconnect.
+    pub token: String,
+}
+
+fn invalid_data(e: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl Client {
+    fn open(addr: impl ToSocketAddrs, name: &str, token: Option<&str>) -> io::Result<Client> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let mut client = Client {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            seat: 0,
+            token: String::new(),
+        };
+        client.send(&ClientMessage::Join {
+            name: name.to_string(),
+            token: token.map(str::to_string),
+        })?;
+        Ok(client)
+    }
+
+    /// Joins the server under `name` and waits to be seated.
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> io::Result<Client> {
+        let mut client = Client::open(addr, name, None)?;
+        client.take_seat()?;
+        Ok(client)
+    }
+
+    /// Takes back the seat of a session that lost its connection. The hand
+    /// so far follows as a `Resume` message.
+    pub fn rejoin(addr: impl ToSocketAddrs, name: &str, token: &str) -> io::Result<Client> {
+        let mut client = Client::open(addr, name, Some(token))?;
+        client.take_seat()?;
+        Ok(client)
+    }
+
+    /// Joins a lobby under `name`. Returns the rooms open at the time.
+    pub fn enter_lobby(
+        addr: impl ToSocketAddrs,
+        name: &str,
+    ) -> io::Result<(Client, Vec<RoomInfo>)> {
+        let mut client = Client::open(addr, name, None)?;
+        let rooms = client.wait_for(rooms)?;
+        Ok((client, rooms))
+    }
+
+    pub fn list_rooms(&mut self) -> io::Result<Vec<RoomInfo>> {
+        self.send(&ClientMessage::ListRooms)?;
+        self.wait_for(rooms)
+    }
+
+    /// Opens a room and sits down in it. Returns the seat.
+    pub fn create_room(
+        &mut self,
+        name: &str,
+        rules: RuleSet,
+        hands: usize,
+        bots: &str,
+        seat: Option<PlayerIndex>,
+    ) -> io::Result<PlayerIndex> {
+        self.send(&ClientMessage::CreateRoom {
+            name: name.to_string(),
+            rules,
+            hands,
+            bots: bots.to_string(),
+            seat,
+        })?;
+        self.take_seat()
+    }
+
+    /// Sits down in a room, at `seat` or the first free one. Returns the seat.
+    pub fn join_room(&mut self, room: usize, seat: Option<PlayerIndex>) -> io::Result<PlayerIndex> {
+        self.send(&ClientMessage::JoinRoom { room, seat })?;
+        self.take_seat()
+    }
+
+    /// Goes back to the lobby. Returns the rooms open at the time.
+    pub fn leave_room(&mut self) -> io::Result<Vec<RoomInfo>> {
+        self.send(&ClientMessage::LeaveRoom)?;
+        self.wait_for(rooms)
+    }
+
+    /// Starts a match in the room, with bots in the empty seats.
+    pub fn start(&mut self) -> io::Result<()> {
+        self.send(&ClientMessage::Start)
+    }
+
+    fn take_seat(&mut self) -> io::Result<PlayerIndex> {
+        (self.seat, self.token) = self.wait_for(|m| match m {
+            ServerMessage::Welcome { seat, token } => Some((seat, token)),
+            _ => None,
+        })?;
+        Ok(self.seat)
+    }
+
+    /// Skips messages until `pick` takes one. A rejection is an error.
+    fn wait_for<T>(&mut self, pick: impl Fn(ServerMessage) -> Option<T>) -> io::Result<T> {
+        loop {
+            match self.receive()? {
+                ServerMessage::Error(e) => return Err(invalid_data(e)),
+                message => {
+                    if let Some(picked) = pick(message) {
+                        return Ok(picked);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        self.writer
+            .write_all(format!("{}\n", message.to_json()).as_bytes())
+    }
+
+    pub fn receive(&mut self) -> io::Result<ServerMessage> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        line.trim()
+            .parse::<Json>()
+            .and_then(|json| ServerMessage::from_json(&json))
+            .map_err(invalid_data)
+    }
+
+    /// Answers a request with `player`, or lets it observe the state.
+    fn answer(&mut self, player: &dyn Player, message: &ServerMessage) -> io::Result<()> {
+        match message {
+            ServerMessage::State(state) => player.observe(state),
+            ServerMessage::Error(e) => return Err(invalid_data(e.clone())),
+            message => {
+                if let Some(answer) = message.answer(player) {
+                    self.send(&answer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays one hand, letting `player` answer every request for this seat
+    /// and passing each public event to `on_event` as it comes. Returns all
+    /// the events of the hand. A rejected answer is an error, as the player
+    /// would most likely give it again.
+    pub fn play_hand(
+        &mut self,
+        player: &dyn Player,
+        mut on_event: impl FnMut(&Event),
+    ) -> io::Result<Vec<Event>> {
+        let mut events = vec![];
+        loop {
+            match self.receive()? {
+                ServerMessage::Event(event) => {
+                    on_event(&event);
+                    let ends_hand = event.ends_hand();
+                    events.push(event);
+                    if ends_hand {
+                        return Ok(events);
+                    }
+                }
+                ServerMessage::Resume {
+                    events: history,
+                    state,
+                } => {
+                    history.iter().for_each(&mut on_event);
+                    events = history;
+                    if let Some(state) = state {
+                        player.observe(&state);
+                    }
+                }
+                message => self.answer(player, &message)?,
+            }
+        }
+    }
+
+    /// Plays the room's next match to the end, like `play_hand`. Returns
+    /// the room as the match left it.
+    pub fn play_match(
+        &mut self,
+        player: &dyn Player,
+        mut on_event: impl FnMut(&Event),
+    ) -> io::Result<RoomInfo> {
+        let mut started = false;
+        loop {
+            match self.receive()? {
+                ServerMessage::Event(event) => on_event(&event),
+                ServerMessage::Resume { events, state } => {
+                    started = true;
+                    events.iter().for_each(&mut on_event);
+                    if let Some(state) = state {
+                        player.observe(&state);
+                    }
+                }
+                ServerMessage::Room(room) if room.playing => started = true,
+                ServerMessage::Room(room) if started => return Ok(room),
+                message => self.answer(player, &message)?,
+            }
+        }
+    }
+}
+
+fn rooms(message: ServerMessage) -> Option<Vec<RoomInfo>> {
+    match message {
+        ServerMessage::Rooms(rooms) => Some(rooms),
+        _ => None,
+    }
+}
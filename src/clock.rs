@@ -0,0 +1,106 @@
+//! Time controls for players. Players answer synchronously on the game's
+//! thread, so a move cannot be cut short; instead a move that took longer
+//! than allowed is thrown away and a default one made in its place, and a
+//! player whose game clock has run out is not asked at all. Players waiting
+//! on I/O, like external bots and network seats, are told when each answer
+//! is due and stop waiting then, so that a hung one does not stall play.
+
+use crate::card::Card;
+use crate::common::{Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::extra_phase::ExtraExposedState;
+use crate::play_phase::ExposedGameState;
+use crate::player::Player;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Limits on thinking time. `None` means no limit.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct TimeControl {
+    pub per_move: Option<Duration>,
+    /// Each player's total over a hand.
+    pub per_game: Option<Duration>,
+}
+
+/// The time each seat has used so far in a hand.
+pub struct Clocks {
+    control: TimeControl,
+    used: Vec<Duration>,
+}
+
+impl Clocks {
+    pub fn new(control: TimeControl) -> Self {
+        Clocks {
+            control,
+            used: vec![Duration::ZERO; 5],
+        }
+    }
+
+    /// Times a move of the player at `index`, telling it when the move is
+    /// due. Returns `None` when the move ran over the time control or the
+    /// player failed, and the default move should be made instead.
+    pub fn timed<T>(
+        &mut self,
+        index: PlayerIndex,
+        player: &dyn Player,
+        call: impl FnOnce(&dyn Player) -> T,
+    ) -> Option<T> {
+        let out_of_time = |used| self.control.per_game.is_some_and(|limit| used >= limit);
+        if out_of_time(self.used[index]) {
+            return None;
+        }
+        let start = Instant::now();
+        player.set_deadline(self.time_left(index).map(|left| start + left));
+        let answer = call(player);
+        let elapsed = start.elapsed();
+        self.used[index] += elapsed;
+        let too_slow = self.control.per_move.is_some_and(|limit| elapsed > limit);
+        if too_slow || out_of_time(self.used[index]) || player.failed() {
+            None
+        } else {
+            Some(answer)
+        }
+    }
+
+    /// How long the player at `index` may take over its next move.
+    fn time_left(&self, index: PlayerIndex) -> Option<Duration> {
+        let game = self
+            .control
+            .per_game
+            .map(|limit| limit.saturating_sub(self.used[index]));
+        self.control.per_move.into_iter().chain(game).min()
+    }
+}
+
+/// The bid made for a player out of time: a pass.
+pub fn default_bid() -> Option<Contract> {
+    None
+}
+
+/// The plan made for a declarer out of time: the contract as bid, the
+/// mighty (or else the joker) as the friend card, and the three least
+/// valuable cards discarded.
+pub fn default_plan(state: &ExtraExposedState) -> (Contract, PartnerCondition, HashSet<Card>) {
+    let trump = state.contract.suit;
+    let friend = [Card::mighty(trump), Card::Joker]
+        .into_iter()
+        .find(|c| !state.hand.contains(c))
+        .map_or(PartnerCondition::None, PartnerCondition::CardCondition);
+    let mut cards = state
+        .hand
+        .iter()
+        .filter(|c| **c != Card::Joker && !c.is_mighty(trump))
+        .copied()
+        .collect::<Vec<_>>();
+    // Point cards last, then trumps, then by rank, with ties broken by
+    // suit so that the plan does not depend on the order of the hand.
+    cards.sort_by_key(|c| match *c {
+        Card::Shaped(suit, rank) => (c.score(), Some(suit) == trump, rank, Some(suit)),
+        Card::Joker => (0, false, 0, None),
+    });
+    (state.contract, friend, cards.into_iter().take(3).collect())
+}
+
+/// The move made for a player out of time: the first legal one.
+pub fn default_action(state: &ExposedGameState) -> PlayAction {
+    state.legal_actions().into_iter().min().unwrap()
+}
@@ -1,12 +1,14 @@
 use crate::card::{Card, Suit};
 use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
 pub type Hand = HashSet<Card>;
 pub type PlayerIndex = usize;
 
 pub const MAX_EFFECTIVE_COUNT: u8 = 21; // 풀 노기루
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum PartnerCondition {
     CardCondition(Card),
     Round(u8),
@@ -14,13 +16,20 @@ pub enum PartnerCondition {
     None,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
 pub enum PlayAction {
     Hand(Card),
     JokerCall(Card),
     JokerStart(Suit),
 }
 
+/// What a player asserts their side takes from the rest of the hand.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Claim {
+    Points(u8),
+    Tricks(u8),
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Contract {
     pub suit: Option<Suit>,
@@ -35,3 +44,117 @@ impl Contract {
         }
     }
 }
+
+// Plain text forms, as used by game records and the command line.
+
+/// `S13`, or `NT12` without trump.
+impl fmt::Display for Contract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.suit {
+            Some(suit) => write!(f, "{}{}", suit, self.count),
+            None => write!(f, "NT{}", self.count),
+        }
+    }
+}
+
+impl FromStr for Contract {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid contract: {}", s);
+        let (suit, count) = match s.get(..2) {
+            Some(nt) if s.len() > 2 && nt.eq_ignore_ascii_case("NT") => (None, &s[2..]),
+            _ => {
+                let suit = s.get(..1).ok_or_else(invalid)?;
+                (Some(suit.parse().map_err(|_| invalid())?), &s[1..])
+            }
+        };
+        let count = count.parse().map_err(|_| invalid())?;
+        Ok(Contract { suit, count })
+    }
+}
+
+/// `card:SA`, `round:0`, `player:2` or `none`.
+impl fmt::Display for PartnerCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartnerCondition::CardCondition(card) => write!(f, "card:{}", card),
+            PartnerCondition::Round(n) => write!(f, "round:{}", n),
+            PartnerCondition::Player(p) => write!(f, "player:{}", p),
+            PartnerCondition::None => write!(f, "none"),
+        }
+    }
+}
+
+impl FromStr for PartnerCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid partner condition: {}", s);
+        if s == "none" {
+            return Ok(PartnerCondition::None);
+        }
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        match kind {
+            "card" => Ok(PartnerCondition::CardCondition(value.parse()?)),
+            "round" => match value.parse() {
+                Ok(n @ 0..=9) => Ok(PartnerCondition::Round(n)),
+                _ => Err(invalid()),
+            },
+            "player" => match value.parse() {
+                Ok(p @ 0..=4) => Ok(PartnerCondition::Player(p)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// `points:5` or `tricks:3`.
+impl fmt::Display for Claim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Claim::Points(n) => write!(f, "points:{}", n),
+            Claim::Tricks(n) => write!(f, "tricks:{}", n),
+        }
+    }
+}
+
+impl FromStr for Claim {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid claim: {}", s);
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        match (kind, value.parse()) {
+            ("points", Ok(n @ 0..=20)) => Ok(Claim::Points(n)),
+            ("tricks", Ok(n @ 0..=10)) => Ok(Claim::Tricks(n)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// `SA`, `call:C3` to call the joker, or `joker:S` to lead the joker asking
+/// for spades.
+impl fmt::Display for PlayAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayAction::Hand(card) => write!(f, "{}", card),
+            PlayAction::JokerCall(card) => write!(f, "call:{}", card),
+            PlayAction::JokerStart(suit) => write!(f, "joker:{}", suit),
+        }
+    }
+}
+
+impl FromStr for PlayAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("call", card)) => Ok(PlayAction::JokerCall(card.parse()?)),
+            Some(("joker", suit)) => Ok(PlayAction::JokerStart(suit.parse()?)),
+            Some(_) => Err(format!("invalid action: {}", s)),
+            None => Ok(PlayAction::Hand(s.parse()?)),
+        }
+    }
+}
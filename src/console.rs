@@ -0,0 +1,494 @@
+use crate::analysis::Advisor;
+use crate::bidding_phase::BiddingState;
+use crate::card::Card;
+use crate::common::{Claim, Contract, Hand, PartnerCondition, PlayAction, PlayerIndex};
+use crate::event::Event;
+use crate::extra_phase::ExtraExposedState;
+use crate::json::Json;
+use crate::play_phase::ExposedGameState;
+use crate::player::Player;
+use crate::protocol::cards_to_json;
+use crate::record::cards_text;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OutputStyle {
+    Plain,
+    Korean,
+    /// One JSON object per event, for other programs.
+    Json,
+}
+
+fn contract_korean(contract: Contract) -> String {
+    match contract.suit {
+        Some(suit) => format!("{} {}", suit, contract.count),
+        None => format!("노기루 {}", contract.count),
+    }
+}
+
+fn condition_korean(condition: PartnerCondition) -> String {
+    match condition {
+        PartnerCondition::CardCondition(card) => format!("{} 프렌드", card),
+        PartnerCondition::Round(0) => "초구 프렌드".to_string(),
+        PartnerCondition::Round(n) => format!("{}라운드 프렌드", n + 1),
+        PartnerCondition::Player(p) => format!("Player {} 프렌드", p),
+        PartnerCondition::None => "노프렌드".to_string(),
+    }
+}
+
+/// Prints the events of a hand to stdout in one of the output styles.
+pub struct Narrator {
+    pub style: OutputStyle,
+}
+
+impl Narrator {
+    pub fn new(style: OutputStyle) -> Self {
+        Narrator { style }
+    }
+
+    pub fn deal(&self, hands: &[Hand], bottom: &HashSet<Card>) {
+        match self.style {
+            OutputStyle::Plain | OutputStyle::Korean => {
+                for (player, hand) in hands.iter().enumerate() {
+                    println!("Player {}: {}", player, cards_text(hand));
+                }
+                println!("{}: {}", self.text("Bottom", "바닥"), cards_text(bottom));
+            }
+            OutputStyle::Json => {
+                let hands = hands.iter().map(cards_to_json).collect();
+                let event = Json::object(vec![
+                    ("event", "deal".into()),
+                    ("hands", Json::Array(hands)),
+                    ("bottom", cards_to_json(bottom)),
+                ]);
+                println!("{}", event);
+            }
+        }
+    }
+
+    /// The declarer's discards, for when nobody at the table could see them.
+    pub fn discards(&self, discards: &HashSet<Card>) {
+        match self.style {
+            OutputStyle::Plain | OutputStyle::Korean => println!(
+                "{}: {}",
+                self.text("Discards", "버린 카드"),
+                cards_text(discards)
+            ),
+            OutputStyle::Json => {
+                let event = Json::object(vec![
+                    ("event", "discards".into()),
+                    ("cards", cards_to_json(discards)),
+                ]);
+                println!("{}", event);
+            }
+        }
+    }
+
+    pub fn event(&self, event: &Event) {
+        match self.style {
+            OutputStyle::Plain => self.plain(event),
+            OutputStyle::Korean => self.korean(event),
+            OutputStyle::Json => println!("{}", event.to_json()),
+        }
+    }
+
+    fn plain(&self, event: &Event) {
+        match event {
+            Event::Bid {
+                player,
+                contract: Some(contract),
+            } => println!("Player {} bids {}", player, contract),
+            Event::Bid { player, .. } => println!("Player {} passes", player),
+            Event::Cancelled => println!("Pledge cancelled"),
+            Event::Plan {
+                declarer,
+                contract,
+                partner_condition,
+            } => println!(
+                "Player {} declares {} with friend {}",
+                declarer, contract, partner_condition
+            ),
+            Event::Surrender { declarer } => println!("Player {} surrenders", declarer),
+            Event::Play { player, action } => println!("Player {}: {}", player, action),
+            Event::Friend { player } => println!("Player {} is the friend", player),
+            Event::Away { player } => println!("Player {} is away; a bot plays for them", player),
+            Event::Back { player } => println!("Player {} is back", player),
+            Event::Timeout { player } => {
+                println!("Player {} ran out of time; a default move is made", player)
+            }
+            Event::Claim {
+                player,
+                claim,
+                accepted,
+            } => {
+                let outcome = if *accepted { "accepted" } else { "rejected" };
+                println!("Player {} claims {}: {}", player, claim, outcome)
+            }
+            Event::Trick {
+                round,
+                winner,
+                points,
+            } => println!(
+                "Round {} won by player {} ({} points)",
+                round + 1,
+                winner,
+                points
+            ),
+            Event::Result {
+                scores,
+                declarer_wins,
+                payouts,
+                ..
+            } => {
+                let side = if *declarer_wins {
+                    "declarer"
+                } else {
+                    "opposition"
+                };
+                println!("Scores: {:?}, the {} wins", scores, side);
+                println!("Payouts: {:?}", payouts);
+            }
+        }
+    }
+
+    fn korean(&self, event: &Event) {
+        match event {
+            Event::Bid {
+                player,
+                contract: Some(contract),
+            } => println!("Player {}: {}", player, contract_korean(*contract)),
+            Event::Bid { player, .. } => println!("Player {}: 패스", player),
+            Event::Cancelled => println!("공약 무효"),
+            Event::Plan {
+                declarer,
+                contract,
+                partner_condition,
+            } => {
+                println!("주공: Player {}", declarer);
+                println!(
+                    "공약: {}, {}",
+                    contract_korean(*contract),
+                    condition_korean(*partner_condition)
+                );
+            }
+            Event::Surrender { declarer } => println!("Player {}: 기권", declarer),
+            Event::Play { player, action } => println!("Player {}: {}", player, action),
+            Event::Friend { player } => println!("프렌드: Player {}", player),
+            Event::Away { player } => println!("Player {}: 연결 끊김, 봇이 대신 진행", player),
+            Event::Back { player } => println!("Player {}: 재접속", player),
+            Event::Timeout { player } => println!("Player {}: 시간 초과, 기본 수로 진행", player),
+            Event::Claim {
+                player,
+                claim,
+                accepted,
+            } => {
+                let outcome = if *accepted { "인정" } else { "거부" };
+                println!("Player {}: 클레임 {} {}", player, claim, outcome)
+            }
+            Event::Trick {
+                round,
+                winner,
+                points,
+            } => println!(
+                "== {}라운드: Player {} 승 ({}점) ==",
+                round + 1,
+                winner,
+                points
+            ),
+            Event::Result {
+                scores,
+                declarer_wins,
+                payouts,
+                ..
+            } => {
+                let side = if *declarer_wins { "주공" } else { "야당" };
+                println!("점수: {:?}, {} 승리", scores, side);
+                println!("정산: {:?}", payouts);
+            }
+        }
+    }
+
+    /// Solver values of each action, in declarer-team points with best play.
+    pub fn action_values(&self, player: PlayerIndex, values: &[(PlayAction, u8)]) {
+        match self.style {
+            OutputStyle::Plain | OutputStyle::Korean => {
+                println!("{}: Player {}", self.text("To play", "차례"), player);
+                for (action, points) in values {
+                    println!("  {:<8} {:>2}", action.to_string(), points);
+                }
+            }
+            OutputStyle::Json => {
+                let values = values
+                    .iter()
+                    .map(|(action, points)| {
+                        Json::object(vec![
+                            ("action", action.to_string().into()),
+                            ("points", (*points).into()),
+                        ])
+                    })
+                    .collect();
+                let event = Json::object(vec![
+                    ("event", "analysis".into()),
+                    ("player", player.into()),
+                    ("values", Json::Array(values)),
+                ]);
+                println!("{}", event);
+            }
+        }
+    }
+
+    fn text<'a>(&self, plain: &'a str, korean: &'a str) -> &'a str {
+        match self.style {
+            OutputStyle::Korean => korean,
+            _ => plain,
+        }
+    }
+}
+
+/// A person at the terminal. Prompts go to stderr, so that they stay apart
+/// from the narration on stdout. Answering `hint` asks `advisor` for a bid
+/// or a move, `claim points:N` or `claim tricks:N` claims the rest of the
+/// hand before a move, and a declarer may answer `surrender` instead of a
+/// plan.
+pub struct HumanPlayer {
+    pub seat: PlayerIndex,
+    pub style: OutputStyle,
+    pub advisor: Advisor,
+    // A card chosen when asked for a claim, played on the next move.
+    pending: RefCell<Option<PlayAction>>,
+    // A plan made when asked whether to surrender.
+    plan: RefCell<Option<(Contract, PartnerCondition, HashSet<Card>)>>,
+}
+
+enum Move {
+    Play(PlayAction),
+    Claim(Claim),
+}
+
+impl HumanPlayer {
+    pub fn new(seat: PlayerIndex, style: OutputStyle) -> Self {
+        HumanPlayer {
+            seat,
+            style,
+            advisor: Advisor::default(),
+            pending: RefCell::new(None),
+            plan: RefCell::new(None),
+        }
+    }
+
+    fn text<'a>(&self, plain: &'a str, korean: &'a str) -> &'a str {
+        match self.style {
+            OutputStyle::Korean => korean,
+            _ => plain,
+        }
+    }
+
+    /// Asks until `parse` accepts the answer. Panics when stdin is closed.
+    fn ask<T>(&self, prompt: &str, parse: impl Fn(&str) -> Result<T, String>) -> T {
+        let stdin = io::stdin();
+        loop {
+            eprint!("[Player {}] {}> ", self.seat, prompt);
+            io::stderr().flush().unwrap();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap() == 0 {
+                panic!("stdin closed");
+            }
+            match parse(line.trim()) {
+                Ok(answer) => return answer,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+
+    /// Asks the declarer for a plan, or `None` for a surrender when
+    /// `surrender` is allowed.
+    fn ask_plan(
+        &self,
+        state: &ExtraExposedState,
+        surrender: bool,
+    ) -> Option<(Contract, PartnerCondition, HashSet<Card>)> {
+        eprintln!(
+            "{}: {}",
+            self.text("Your hand", "손패"),
+            cards_text(&state.hand)
+        );
+        let prompt = if surrender {
+            self.text(
+                "contract, friend and three discards (e.g. S14 card:DA H2 H3 D4), or surrender",
+                "공약, 프렌드, 버릴 카드 세 장 (예: S14 card:DA H2 H3 D4), 또는 surrender로 기권",
+            )
+        } else {
+            self.text(
+                "contract, friend and three discards (e.g. S14 card:DA H2 H3 D4)",
+                "공약, 프렌드, 버릴 카드 세 장 (예: S14 card:DA H2 H3 D4)",
+            )
+        };
+        self.ask(prompt, |answer| {
+            if surrender && answer == "surrender" {
+                return Ok(None);
+            }
+            let mut words = answer.split_whitespace();
+            let contract: Contract = words.next().unwrap_or("").parse()?;
+            let condition: PartnerCondition = words.next().unwrap_or("").parse()?;
+            let discards = words
+                .map(str::parse)
+                .collect::<Result<HashSet<Card>, _>>()?;
+            if contract.effective_count() < state.contract.effective_count() || contract.count > 20
+            {
+                return Err(self
+                    .text("contract too low or too high", "공약이 범위를 벗어남")
+                    .to_string());
+            }
+            if discards.len() != 3 || !state.hand.is_superset(&discards) {
+                return Err(self
+                    .text(
+                        "discard three cards of your hand",
+                        "손패에서 세 장을 버려야 함",
+                    )
+                    .to_string());
+            }
+            Ok(Some((contract, condition, discards)))
+        })
+    }
+
+    /// Asks for a card to play, or a claim when `claims` are taken.
+    fn ask_move(&self, state: &ExposedGameState, claims: bool) -> Move {
+        let legal = state.legal_actions();
+        let legal_text = legal
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        eprintln!(
+            "{}: {}",
+            self.text("Your hand", "손패"),
+            cards_text(&state.hand)
+        );
+        eprintln!("{}: {}", self.text("Legal", "가능"), legal_text);
+        let prompt = if claims {
+            self.text("play, hint or claim", "낼 카드, hint 또는 claim")
+        } else {
+            self.text("play or hint", "낼 카드 또는 hint")
+        };
+        self.ask(prompt, |answer| {
+            if answer == "hint" {
+                return Err(match self.advisor.hint(self.seat, state) {
+                    Some(hint) => {
+                        let mut text = format!(
+                            "{}: {} ({:.1} {})",
+                            self.text("Hint", "추천"),
+                            hint.action,
+                            hint.delta,
+                            self.text("points over the next best", "점 차선보다 유리")
+                        );
+                        if let Some(friend) = hint.friend {
+                            let odds = friend
+                                .iter()
+                                .enumerate()
+                                .filter(|&(_, &p)| p > 0.0)
+                                .map(|(seat, p)| format!("Player {} {:.0}%", seat, 100.0 * p))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            text += &format!("; {}: {}", self.text("friend", "프렌드"), odds);
+                        }
+                        text
+                    }
+                    None => self.text("no hint", "추천 없음").to_string(),
+                });
+            }
+            if let Some(claim) = answer.strip_prefix("claim ") {
+                if !claims {
+                    return Err(self
+                        .text("one claim per move", "클레임은 한 수에 한 번")
+                        .to_string());
+                }
+                return Ok(Move::Claim(claim.trim().parse()?));
+            }
+            let action = answer.parse()?;
+            if !legal.contains(&action) {
+                return Err(self.text("illegal action", "낼 수 없는 카드").to_string());
+            }
+            Ok(Move::Play(action))
+        })
+    }
+}
+
+impl Player for HumanPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        eprintln!(
+            "{}: {}",
+            self.text("Your hand", "손패"),
+            cards_text(&state.hand)
+        );
+        if let Some(contract) = state.curr_contract {
+            eprintln!("{}: {}", self.text("Current bid", "현재 공약"), contract);
+        }
+        let prompt = self.text(
+            "bid (e.g. S14, NT13), pass or hint",
+            "공약 (예: S14, NT13), pass 또는 hint",
+        );
+        self.ask(prompt, |answer| {
+            if answer == "pass" {
+                return Ok(None);
+            }
+            if answer == "hint" {
+                return Err(match self.advisor.bid_hint(state) {
+                    Some((contract, probability)) => format!(
+                        "{}: {} ({} {:.0}%)",
+                        self.text("Hint", "추천"),
+                        contract,
+                        self.text("made in", "성공 확률"),
+                        100.0 * probability
+                    ),
+                    None => self.text("no hint", "추천 없음").to_string(),
+                });
+            }
+            let contract: Contract = answer.parse()?;
+            if contract.effective_count() <= state.min_effective_count || contract.count > 20 {
+                return Err(self
+                    .text("bid too low or too high", "공약이 범위를 벗어남")
+                    .to_string());
+            }
+            Ok(Some(contract))
+        })
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        if let Some(plan) = self.plan.borrow_mut().take() {
+            return plan;
+        }
+        self.ask_plan(&state, false).unwrap()
+    }
+
+    fn surrender(&self, state: &ExtraExposedState) -> bool {
+        let plan = self.ask_plan(state, true);
+        let surrender = plan.is_none();
+        *self.plan.borrow_mut() = plan;
+        surrender
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        if let Some(action) = self.pending.borrow_mut().take() {
+            return action;
+        }
+        match self.ask_move(&state, false) {
+            Move::Play(action) => action,
+            Move::Claim(_) => unreachable!(),
+        }
+    }
+
+    fn claim(&self, state: &ExposedGameState) -> Option<Claim> {
+        match self.ask_move(state, true) {
+            Move::Claim(claim) => Some(claim),
+            Move::Play(action) => {
+                *self.pending.borrow_mut() = Some(action);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Training data for bots that imitate other players: every decision of a
+//! game, with the acting seat's view, the legal actions, the action taken
+//! and how the game ended for that seat.
+//!
+//! Decisions are those of `encoding`: a bid, each step of the declarer's
+//! plan (see `PartialPlan`) or a surrender, and each card played. A
+//! dataset is a directory of shards, each holding whole games, in one of
+//! two formats:
+//!
+//! - `jsonl`: `shard-00000.jsonl`, one decision per line, with the view in
+//!   the forms of the network protocol and actions as text;
+//! - `columns`: a directory `shard-00000` with one file per column of raw
+//!   little-endian numbers, ready for `numpy.fromfile`, and a `meta.json`
+//!   giving each column's type and shape. Views are encoded in the layout
+//!   of the writer and actions are indices of its action space.
+
+use crate::bidding_phase::BiddingState;
+use crate::common::PlayerIndex;
+use crate::encoding::{bid_actions, Action, Layout, PartialPlan};
+use crate::extra_phase::{ExtraExposedState, ExtraPhase};
+use crate::json::Json;
+use crate::play_phase::ExposedGameState;
+use crate::protocol::{
+    bidding_state_to_json, cards_to_json, extra_state_to_json, play_state_to_json, text,
+};
+use crate::record::GameRecord;
+use crate::rules::RuleSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// What the acting seat knew when it decided.
+#[derive(Clone, Debug)]
+pub enum View {
+    Bidding(BiddingState),
+    /// The declarer's hand and bid, with the plan taken so far.
+    Declaring(ExtraExposedState, PartialPlan),
+    Playing(ExposedGameState),
+}
+
+impl View {
+    pub fn phase(&self) -> &'static str {
+        match self {
+            View::Bidding(_) => "bidding",
+            View::Declaring(..) => "declaring",
+            View::Playing(_) => "playing",
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        match self {
+            View::Bidding(state) => bidding_state_to_json(state),
+            View::Declaring(state, plan) => {
+                let Json::Object(mut fields) = extra_state_to_json(state) else {
+                    unreachable!()
+                };
+                fields.push(("plan_contract".to_string(), plan.contract.map(text).into()));
+                fields.push(("plan_friend".to_string(), plan.friend.map(text).into()));
+                fields.push(("plan_discards".to_string(), cards_to_json(&plan.discards)));
+                Json::Object(fields)
+            }
+            View::Playing(state) => play_state_to_json(state),
+        }
+    }
+
+    pub fn encode(&self, layout: Layout) -> Vec<f32> {
+        match self {
+            View::Bidding(state) => layout.encode_bidding(state),
+            View::Declaring(state, plan) => layout.encode_plan(state, plan),
+            View::Playing(state) => layout.encode_play(state),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Decision {
+    pub seat: PlayerIndex,
+    pub view: View,
+    pub legal: Vec<Action>,
+    pub action: Action,
+    /// Made for the seat, which had run out of time.
+    pub timed_out: bool,
+}
+
+/// How a game ended for one seat.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Outcome {
+    /// `None` when every player passed.
+    pub won: Option<bool>,
+    /// Point cards the seat took.
+    pub points: u8,
+    /// Whether the seat was the declarer or the revealed friend.
+    pub declarer_side: bool,
+    pub payout: i32,
+}
+
+/// The decisions and outcomes of one game.
+#[derive(Clone, Debug)]
+pub struct GameData {
+    /// The seed of a simulated game, or the number of a recorded one.
+    pub game: u64,
+    pub decisions: Vec<Decision>,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl GameData {
+    /// Plays a record back for its decisions. Fails on an illegal move or
+    /// a record of a game that did not finish.
+    pub fn from_record(game: u64, record: &GameRecord, rules: &RuleSet) -> Result<Self, String> {
+        let timed_out = |moves: usize| record.timeouts.iter().any(|&(_, m)| m == moves);
+        let mut decisions = vec![];
+        let mut pledge = record.pledge_phase();
+        for (moves, &(player, bid)) in record.bids.iter().enumerate() {
+            if pledge.pledge_done() || pledge.turn_player() != player {
+                return Err(format!("bid out of turn by player {}", player));
+            }
+            let state = pledge.bidding_state(player);
+            let legal = bid_actions(&state);
+            if !legal.contains(&Action::Bid(bid)) {
+                return Err(format!("bid too low by player {}", player));
+            }
+            decisions.push(Decision {
+                seat: player,
+                view: View::Bidding(state),
+                legal,
+                action: Action::Bid(bid),
+                timed_out: timed_out(moves),
+            });
+            pledge.player_bids(player, bid);
+        }
+        if !pledge.pledge_done() {
+            return Err("the record ends during the pledge".to_string());
+        }
+        if pledge.cancelled() {
+            let outcome = Outcome {
+                won: None,
+                points: 0,
+                declarer_side: false,
+                payout: 0,
+            };
+            return Ok(GameData {
+                game,
+                decisions,
+                outcomes: vec![outcome; 5],
+            });
+        }
+
+        if record.surrendered {
+            let extra = ExtraPhase::from_pledge(pledge);
+            let state = extra.extra_state();
+            let plan = PartialPlan::default();
+            decisions.push(Decision {
+                seat: extra.declarer(),
+                legal: plan.legal_actions(&state),
+                view: View::Declaring(state, plan),
+                action: Action::Surrender,
+                timed_out: timed_out(record.bids.len()),
+            });
+            let surrender = extra.surrender();
+            let payouts = rules.settle_surrender(&surrender).payouts;
+            let outcomes = (0..5)
+                .map(|seat| Outcome {
+                    won: Some(seat != surrender.declarer),
+                    points: 0,
+                    declarer_side: seat == surrender.declarer,
+                    payout: payouts[seat],
+                })
+                .collect();
+            return Ok(GameData {
+                game,
+                decisions,
+                outcomes,
+            });
+        }
+
+        // Checks the plan and the play before taking them apart.
+        let end = record.play_phase(record.actions.len())?;
+        if !end.is_done() {
+            return Err("the record ends before the last round".to_string());
+        }
+        let mut extra = ExtraPhase::from_pledge(pledge);
+        let state = extra.extra_state();
+        let plan = record.plan.clone().unwrap();
+        let mut partial = PartialPlan::default();
+        for step in PartialPlan::steps(&plan) {
+            decisions.push(Decision {
+                seat: extra.declarer(),
+                view: View::Declaring(state.clone(), partial.clone()),
+                legal: partial.legal_actions(&state),
+                action: step,
+                timed_out: timed_out(record.bids.len()),
+            });
+            partial.take(step);
+        }
+        let (contract, friend, discards) = plan;
+        let mut play = extra.submit_plan(contract, friend, discards);
+        for (i, &(player, action)) in record.actions.iter().enumerate() {
+            decisions.push(Decision {
+                seat: player,
+                view: View::Playing(play.play_state(player)),
+                legal: play
+                    .legal_actions(player)
+                    .into_iter()
+                    .map(Action::Play)
+                    .collect(),
+                action: Action::Play(action),
+                timed_out: timed_out(record.bids.len() + 1 + i),
+            });
+            play.player_acts(player, action);
+        }
+
+        let result = play.result();
+        let payouts = rules.settle(&play).payouts;
+        let outcomes = (0..5)
+            .map(|seat| Outcome {
+                won: Some(result.win[seat]),
+                points: result.scores[seat],
+                declarer_side: seat == play.declarer || Some(seat) == play.partner_revealed,
+                payout: payouts[seat],
+            })
+            .collect();
+        Ok(GameData {
+            game,
+            decisions,
+            outcomes,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DatasetFormat {
+    Jsonl,
+    Columns,
+}
+
+/// Writes a dataset shard by shard into a directory.
+pub struct DatasetWriter {
+    pub dir: PathBuf,
+    pub format: DatasetFormat,
+    pub layout: Layout,
+    shards: usize,
+}
+
+impl DatasetWriter {
+    /// Creates the directory, when missing.
+    pub fn new(dir: impl AsRef<Path>, format: DatasetFormat) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        Ok(DatasetWriter {
+            dir,
+            format,
+            layout: Layout::LATEST,
+            shards: 0,
+        })
+    }
+
+    /// Writes `games` as the next shard, and returns its path.
+    pub fn write_shard(&mut self, games: &[GameData]) -> Result<PathBuf, String> {
+        let name = format!("shard-{:05}", self.shards);
+        let path = match self.format {
+            DatasetFormat::Jsonl => self.dir.join(format!("{}.jsonl", name)),
+            DatasetFormat::Columns => self.dir.join(name),
+        };
+        let result = match self.format {
+            DatasetFormat::Jsonl => write_jsonl(&path, games),
+            DatasetFormat::Columns => write_columns(&path, games, self.layout),
+        };
+        result.map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.shards += 1;
+        Ok(path)
+    }
+}
+
+fn write_jsonl(path: &Path, games: &[GameData]) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for game in games {
+        for decision in &game.decisions {
+            let outcome = &game.outcomes[decision.seat];
+            let row = Json::object(vec![
+                ("game", game.game.into()),
+                ("seat", decision.seat.into()),
+                ("phase", decision.view.phase().into()),
+                ("state", decision.view.to_json()),
+                (
+                    "legal",
+                    Json::Array(decision.legal.iter().map(text).collect()),
+                ),
+                ("action", text(decision.action)),
+                ("timed_out", decision.timed_out.into()),
+                ("won", outcome.won.into()),
+                ("points", outcome.points.into()),
+                ("declarer_side", outcome.declarer_side.into()),
+                ("payout", outcome.payout.into()),
+            ]);
+            writeln!(out, "{}", row)?;
+        }
+    }
+    out.flush()
+}
+
+/// A column of fixed-width numbers, `width` to a row.
+struct Column {
+    name: &'static str,
+    dtype: &'static str,
+    width: usize,
+    bytes: Vec<u8>,
+}
+
+impl Column {
+    fn new(name: &'static str, dtype: &'static str, width: usize) -> Self {
+        Column {
+            name,
+            dtype,
+            width,
+            bytes: vec![],
+        }
+    }
+}
+
+fn write_columns(dir: &Path, games: &[GameData], layout: Layout) -> std::io::Result<()> {
+    let mut game_ids = Column::new("game", "uint64", 1);
+    let mut seats = Column::new("seat", "uint8", 1);
+    let mut phases = Column::new("phase", "uint8", 1);
+    let mut features = Column::new("features", "float32", layout.observation_size());
+    let mut legal = Column::new("legal", "uint8", layout.action_count());
+    let mut actions = Column::new("action", "uint16", 1);
+    let mut timed_out = Column::new("timed_out", "uint8", 1);
+    // 1 for a win, 0 for a loss and -1 when every player passed.
+    let mut won = Column::new("won", "int8", 1);
+    let mut points = Column::new("points", "uint8", 1);
+    let mut declarer_side = Column::new("declarer_side", "uint8", 1);
+    let mut payouts = Column::new("payout", "int32", 1);
+    let mut rows = 0;
+    for game in games {
+        // A layout without a surrender has no row for one.
+        let decisions = game.decisions.iter().filter(|d| layout.encodes(d.action));
+        for decision in decisions {
+            let outcome = &game.outcomes[decision.seat];
+            let phase = match decision.view {
+                View::Bidding(_) => 0,
+                View::Declaring(..) => 1,
+                View::Playing(_) => 2,
+            };
+            game_ids.bytes.extend(game.game.to_le_bytes());
+            seats.bytes.push(decision.seat as u8);
+            phases.bytes.push(phase);
+            for value in decision.view.encode(layout) {
+                features.bytes.extend(value.to_le_bytes());
+            }
+            let mask = layout.action_mask(decision.legal.iter().copied());
+            legal.bytes.extend(mask.into_iter().map(u8::from));
+            let action = layout.encode_action(decision.action) as u16;
+            actions.bytes.extend(action.to_le_bytes());
+            timed_out.bytes.push(decision.timed_out as u8);
+            won.bytes.push(outcome.won.map_or(-1, i8::from) as u8);
+            points.bytes.push(outcome.points);
+            declarer_side.bytes.push(outcome.declarer_side as u8);
+            payouts.bytes.extend(outcome.payout.to_le_bytes());
+            rows += 1;
+        }
+    }
+
+    fs::create_dir_all(dir)?;
+    let columns = [
+        game_ids,
+        seats,
+        phases,
+        features,
+        legal,
+        actions,
+        timed_out,
+        won,
+        points,
+        declarer_side,
+        payouts,
+    ];
+    let mut meta = vec![];
+    for column in &columns {
+        fs::write(dir.join(format!("{}.bin", column.name)), &column.bytes)?;
+        let shape = match column.width {
+            1 => vec![rows.into()],
+            width => vec![rows.into(), width.into()],
+        };
+        let info = Json::object(vec![
+            ("dtype", column.dtype.into()),
+            ("shape", Json::Array(shape)),
+        ]);
+        meta.push((column.name.to_string(), info));
+    }
+    let meta = Json::object(vec![
+        ("layout", layout.version().into()),
+        ("rows", rows.into()),
+        ("games", games.len().into()),
+        ("columns", Json::Object(meta)),
+    ]);
+    fs::write(dir.join("meta.json"), format!("{}\n", meta))
+}
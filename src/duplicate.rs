@@ -0,0 +1,137 @@
+use crate::bidding_phase::PledgePhase;
+use crate::clock::TimeControl;
+use crate::game::play_hand;
+use crate::play_phase::EarlyEnd;
+use crate::rules::RuleSet;
+use crate::tournament::{mean_interval, Entrant};
+use std::fmt;
+
+/// Duplicate Mighty: every seeded deal is played once per rotation of the
+/// lineup, so each bot holds each seat's cards in turn.
+///
+/// A bot's score on a deal is its mean payout over all its seats. Since every
+/// bot held every hand equally often, the luck of the deal cancels out of the
+/// differences between bots' scores.
+pub struct Duplicate {
+    pub entrants: Vec<Entrant>,
+    pub rules: RuleSet,
+    pub deals: usize,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct DuplicateReport {
+    pub names: Vec<String>,
+    /// Per deal, each bot's mean payout over the five rotations.
+    pub scores: Vec<Vec<f64>>,
+    /// Per deal, each bot's mean payout in a single rotation, taken in turn,
+    /// which is what ordinary, non-duplicate games would have measured.
+    pub single_scores: Vec<Vec<f64>>,
+}
+
+impl Duplicate {
+    pub fn run(&self) -> DuplicateReport {
+        assert!((1..=5).contains(&self.entrants.len()));
+        let lineup = (0..5)
+            .map(|seat| seat % self.entrants.len())
+            .collect::<Vec<_>>();
+        let mut report = DuplicateReport {
+            names: self.entrants.iter().map(|e| e.name.clone()).collect(),
+            scores: vec![],
+            single_scores: vec![],
+        };
+
+        for deal in 0..self.deals {
+            let seed = self.seed.wrapping_add(deal as u64);
+            let mut plays = vec![];
+            for rotation in 0..5 {
+                let mut seating = lineup.clone();
+                seating.rotate_right(rotation);
+                let players = seating
+                    .iter()
+                    .map(|&e| (self.entrants[e].factory)())
+                    .collect::<Vec<_>>();
+                let game = PledgePhase::seeded(0, self.rules.min_pledge, seed);
+                let (end, _) = play_hand(&players, game, TimeControl::default(), EarlyEnd::Never);
+                let payouts = end.payouts(&self.rules);
+                plays.push((seating, payouts));
+            }
+            report
+                .scores
+                .push(mean_payouts(self.entrants.len(), &plays));
+            report
+                .single_scores
+                .push(mean_payouts(self.entrants.len(), &plays[deal % 5..][..1]));
+        }
+        report
+    }
+}
+
+fn mean_payouts(entrants: usize, plays: &[(Vec<usize>, Vec<i32>)]) -> Vec<f64> {
+    let mut sums = vec![0.0; entrants];
+    let mut seats = vec![0; entrants];
+    for (seating, payouts) in plays {
+        for (&entrant, &payout) in seating.iter().zip(payouts) {
+            sums[entrant] += payout as f64;
+            seats[entrant] += 1;
+        }
+    }
+    sums.iter().zip(seats).map(|(s, n)| s / n as f64).collect()
+}
+
+fn summarize(samples: impl Iterator<Item = f64>) -> (f64, (f64, f64)) {
+    let (mut n, mut sum, mut square_sum) = (0, 0.0, 0.0);
+    for x in samples {
+        n += 1;
+        sum += x;
+        square_sum += x * x;
+    }
+    (sum / n.max(1) as f64, mean_interval(sum, square_sum, n))
+}
+
+impl DuplicateReport {
+    /// Mean duplicate score of a bot per deal, with its 95% interval.
+    pub fn score(&self, bot: usize) -> (f64, (f64, f64)) {
+        summarize(self.scores.iter().map(|s| s[bot]))
+    }
+
+    pub fn single_score(&self, bot: usize) -> (f64, (f64, f64)) {
+        summarize(self.single_scores.iter().map(|s| s[bot]))
+    }
+
+    /// Mean per-deal score difference of `a` over `b`, with its 95% interval.
+    pub fn difference(&self, a: usize, b: usize) -> (f64, (f64, f64)) {
+        summarize(self.scores.iter().map(|s| s[a] - s[b]))
+    }
+}
+
+impl fmt::Display for DuplicateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} deals, 5 rotations each", self.scores.len())?;
+        writeln!(
+            f,
+            "{:<16} {:>24} {:>24}",
+            "bot", "duplicate score", "single-play score"
+        )?;
+        for (bot, name) in self.names.iter().enumerate() {
+            let (score, (score_lo, score_hi)) = self.score(bot);
+            let (single, (single_lo, single_hi)) = self.single_score(bot);
+            writeln!(
+                f,
+                "{:<16} {:>6.2} [{:>6.2}, {:>6.2}] {:>6.2} [{:>6.2}, {:>6.2}]",
+                name, score, score_lo, score_hi, single, single_lo, single_hi,
+            )?;
+        }
+        for a in 0..self.names.len() {
+            for b in a + 1..self.names.len() {
+                let (diff, (lo, hi)) = self.difference(a, b);
+                writeln!(
+                    f,
+                    "{} - {}: {:.2} [{:.2}, {:.2}]",
+                    self.names[a], self.names[b], diff, lo, hi
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+//! Fixed-size numeric encodings of what a seat sees, and a flat index space
+//! of every decision, for neural bots.
+//!
+//! Layouts are versioned: a model trained on one layout keeps working as
+//! long as it is fed that layout, and a change to either encoding comes as
+//! a new `Layout` variant rather than an edit to an old one.
+//!
+//! # Layout 1
+//!
+//! Observations are 790 floats of 0 or 1, in sections of one-hots and
+//! bitmaps. Seats are absolute, and cards are numbered by suit (H, D, C, S)
+//! and rank, the joker last.
+//!
+//! | offset | size | section |
+//! |---|---|---|
+//! | 0 | 3 | phase: bidding, declaring, playing |
+//! | 3 | 53 | the seat's hand |
+//! | 56 | 25 | contract: trump (H, D, C, S, no-trump) and count 1 to 20 |
+//! | 81 | 22 | least effective count a bid must beat, 0 to 21 |
+//! | 103 | 5 | declarer |
+//! | 108 | 5 | player to move |
+//! | 113 | 69 | partner condition: card, round 1 to 10, player, none |
+//! | 182 | 5 | revealed friend |
+//! | 187 | 53 | discards, when the seat knows them |
+//! | 240 | 5 × 53 | cards played in finished tricks, per seat |
+//! | 505 | 5 × 53 | cards of the current trick, per seat |
+//! | 770 | 5 | leader of the current trick |
+//! | 775 | 4 | suit led |
+//! | 779 | 1 | joker called |
+//! | 780 | 10 | round |
+//!
+//! While bidding, the contract is the highest bid so far. The player to move
+//! is only known in play; at any other time the seat itself is the one to
+//! decide.
+//!
+//! Actions are indices below 333: a pass (0), contracts by trump and count
+//! (1), discards by card (101), friend cards (154), friend rounds (207),
+//! friend players (217), no friend (222), plays by card (223), joker calls by
+//! the called card (276) and joker leads by suit (329). Contracts double as
+//! the final contract of the declarer's plan.
+//!
+//! # Layout 2
+//!
+//! Observations as in layout 1. Actions as in layout 1, and a surrender
+//! (333), which the declarer may take in place of the plan's first step.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::{Card, Suit};
+use crate::common::{Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::extra_phase::ExtraExposedState;
+use crate::play_phase::ExposedGameState;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A decision, as one index of the action space.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Action {
+    /// A bid, or in the declarer's plan the final contract.
+    Bid(Option<Contract>),
+    Friend(PartnerCondition),
+    Discard(Card),
+    Play(PlayAction),
+    /// The declarer giving up the hand, in place of a plan. Layout 1 has
+    /// no index for it.
+    Surrender,
+}
+
+/// Actions in the text forms of the network protocol, a pass as `pass`.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Bid(Some(contract)) => write!(f, "{}", contract),
+            Action::Bid(None) => write!(f, "pass"),
+            Action::Friend(condition) => write!(f, "{}", condition),
+            Action::Discard(card) => write!(f, "{}", card),
+            Action::Play(action) => write!(f, "{}", action),
+            Action::Surrender => write!(f, "surrender"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Layout {
+    V1,
+    V2,
+}
+
+// Sections of the observation.
+const PHASE: usize = 0;
+const HAND: usize = PHASE + 3;
+const CONTRACT: usize = HAND + 53;
+const MIN_BID: usize = CONTRACT + 25;
+const DECLARER: usize = MIN_BID + 22;
+const TO_MOVE: usize = DECLARER + 5;
+const PARTNER: usize = TO_MOVE + 5;
+const REVEALED: usize = PARTNER + 69;
+const DISCARDED: usize = REVEALED + 5;
+const PLAYED: usize = DISCARDED + 53;
+const TRICK: usize = PLAYED + 5 * 53;
+const LEADER: usize = TRICK + 5 * 53;
+const LEAD_SUIT: usize = LEADER + 5;
+const JOKER_CALLED: usize = LEAD_SUIT + 4;
+const ROUND: usize = JOKER_CALLED + 1;
+const OBSERVATION_SIZE: usize = ROUND + 10;
+
+// Sections of the action space.
+const CONTRACTS: usize = 1;
+const DISCARDS: usize = CONTRACTS + 5 * 20;
+const FRIEND_CARDS: usize = DISCARDS + 53;
+const FRIEND_ROUNDS: usize = FRIEND_CARDS + 53;
+const FRIEND_PLAYERS: usize = FRIEND_ROUNDS + 10;
+const NO_FRIEND: usize = FRIEND_PLAYERS + 5;
+const PLAYS: usize = NO_FRIEND + 1;
+const JOKER_CALLS: usize = PLAYS + 53;
+const JOKER_STARTS: usize = JOKER_CALLS + 53;
+const ACTION_COUNT: usize = JOKER_STARTS + 4;
+const SURRENDER: usize = ACTION_COUNT;
+
+/// Every contract, by trump (no-trump last) and count.
+pub fn all_contracts() -> impl Iterator<Item = Contract> {
+    let suits = Suit::ALL.into_iter().map(Some).chain([None]);
+    suits.flat_map(|suit| (1..=20).map(move |count| Contract { suit, count }))
+}
+
+/// Every partner condition, in the order of the friend actions.
+pub fn all_partner_conditions() -> impl Iterator<Item = PartnerCondition> {
+    let cards = (0..53).map(Card::from_index);
+    let cards = cards.map(PartnerCondition::CardCondition);
+    let rounds = (0..10).map(PartnerCondition::Round);
+    let players = (0..5).map(PartnerCondition::Player);
+    cards
+        .chain(rounds)
+        .chain(players)
+        .chain([PartnerCondition::None])
+}
+
+/// The legal bids: a pass, or a contract above the least effective count.
+pub fn bid_actions(state: &BiddingState) -> Vec<Action> {
+    all_contracts()
+        .filter(|c| c.effective_count() > state.min_effective_count)
+        .map(Some)
+        .chain([None])
+        .map(Action::Bid)
+        .collect()
+}
+
+/// A declarer's plan part way, as it is taken one action at a time: the
+/// final contract, then the friend, then the three discards. A surrender
+/// may be chosen in place of the first step, and ends the hand instead.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct PartialPlan {
+    pub contract: Option<Contract>,
+    pub friend: Option<PartnerCondition>,
+    pub discards: HashSet<Card>,
+}
+
+impl PartialPlan {
+    /// The actions of a whole plan in the order they are taken, the
+    /// discards by card index.
+    pub fn steps(plan: &(Contract, PartnerCondition, HashSet<Card>)) -> Vec<Action> {
+        let (contract, friend, discards) = plan;
+        let mut discards = discards.iter().copied().collect::<Vec<_>>();
+        discards.sort_by_key(Card::index);
+        [Action::Bid(Some(*contract)), Action::Friend(*friend)]
+            .into_iter()
+            .chain(discards.into_iter().map(Action::Discard))
+            .collect()
+    }
+
+    pub fn legal_actions(&self, state: &ExtraExposedState) -> Vec<Action> {
+        match (self.contract, self.friend) {
+            (None, _) => all_contracts()
+                .filter(|c| c.effective_count() >= state.contract.effective_count())
+                .map(|c| Action::Bid(Some(c)))
+                .chain([Action::Surrender])
+                .collect(),
+            (_, None) => all_partner_conditions().map(Action::Friend).collect(),
+            _ if self.discards.len() < 3 => state
+                .hand
+                .difference(&self.discards)
+                .map(|&c| Action::Discard(c))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Takes the next step. Panics on an action that is not a plan step,
+    /// such as a surrender.
+    pub fn take(&mut self, action: Action) {
+        match action {
+            Action::Bid(contract) => self.contract = contract,
+            Action::Friend(friend) => self.friend = Some(friend),
+            Action::Discard(card) => {
+                self.discards.insert(card);
+            }
+            Action::Play(_) | Action::Surrender => {
+                panic!("not a step of a plan: {:?}", action)
+            }
+        }
+    }
+
+    /// The plan, once every step is taken.
+    pub fn complete(&self) -> Option<(Contract, PartnerCondition, HashSet<Card>)> {
+        match (self.contract, self.friend) {
+            (Some(contract), Some(friend)) if self.discards.len() == 3 => {
+                Some((contract, friend, self.discards.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn contract_index(contract: Contract) -> usize {
+    contract.suit.map_or(4, |s| s.index()) * 20 + (contract.count as usize - 1)
+}
+
+impl Layout {
+    pub const LATEST: Layout = Layout::V2;
+
+    pub fn version(self) -> u32 {
+        match self {
+            Layout::V1 => 1,
+            Layout::V2 => 2,
+        }
+    }
+
+    pub fn from_version(version: u32) -> Option<Layout> {
+        match version {
+            1 => Some(Layout::V1),
+            2 => Some(Layout::V2),
+            _ => None,
+        }
+    }
+
+    pub fn observation_size(self) -> usize {
+        match self {
+            Layout::V1 | Layout::V2 => OBSERVATION_SIZE,
+        }
+    }
+
+    pub fn action_count(self) -> usize {
+        match self {
+            Layout::V1 => ACTION_COUNT,
+            Layout::V2 => ACTION_COUNT + 1,
+        }
+    }
+
+    /// Whether the action space has an index for `action`.
+    pub fn encodes(self, action: Action) -> bool {
+        action != Action::Surrender || self != Layout::V1
+    }
+
+    /// The index of `action`. Panics on an action the layout has no index
+    /// for.
+    pub fn encode_action(self, action: Action) -> usize {
+        assert!(
+            self.encodes(action),
+            "layout {} has no {:?}",
+            self.version(),
+            action
+        );
+        match action {
+            Action::Bid(None) => 0,
+            Action::Bid(Some(contract)) => CONTRACTS + contract_index(contract),
+            Action::Discard(card) => DISCARDS + card.index(),
+            Action::Friend(PartnerCondition::CardCondition(card)) => FRIEND_CARDS + card.index(),
+            Action::Friend(PartnerCondition::Round(round)) => FRIEND_ROUNDS + round as usize,
+            Action::Friend(PartnerCondition::Player(player)) => FRIEND_PLAYERS + player,
+            Action::Friend(PartnerCondition::None) => NO_FRIEND,
+            Action::Play(PlayAction::Hand(card)) => PLAYS + card.index(),
+            Action::Play(PlayAction::JokerCall(card)) => JOKER_CALLS + card.index(),
+            Action::Play(PlayAction::JokerStart(suit)) => JOKER_STARTS + suit.index(),
+            Action::Surrender => SURRENDER,
+        }
+    }
+
+    pub fn decode_action(self, index: usize) -> Option<Action> {
+        let action = match index {
+            0 => Action::Bid(None),
+            i if i < DISCARDS => {
+                let i = i - CONTRACTS;
+                Action::Bid(Some(Contract {
+                    suit: Suit::ALL.get(i / 20).copied(),
+                    count: (i % 20) as u8 + 1,
+                }))
+            }
+            i if i < FRIEND_CARDS => Action::Discard(Card::from_index(i - DISCARDS)),
+            i if i < FRIEND_ROUNDS => Action::Friend(PartnerCondition::CardCondition(
+                Card::from_index(i - FRIEND_CARDS),
+            )),
+            i if i < FRIEND_PLAYERS => {
+                Action::Friend(PartnerCondition::Round((i - FRIEND_ROUNDS) as u8))
+            }
+            i if i < NO_FRIEND => Action::Friend(PartnerCondition::Player(i - FRIEND_PLAYERS)),
+            NO_FRIEND => Action::Friend(PartnerCondition::None),
+            i if i < JOKER_CALLS => Action::Play(PlayAction::Hand(Card::from_index(i - PLAYS))),
+            i if i < JOKER_STARTS => {
+                Action::Play(PlayAction::JokerCall(Card::from_index(i - JOKER_CALLS)))
+            }
+            i if i < ACTION_COUNT => {
+                Action::Play(PlayAction::JokerStart(Suit::ALL[i - JOKER_STARTS]))
+            }
+            SURRENDER if self.encodes(Action::Surrender) => Action::Surrender,
+            _ => return None,
+        };
+        Some(action)
+    }
+
+    /// Marks `actions` in a mask over the action space, leaving out those
+    /// the layout has no index for.
+    pub fn action_mask(self, actions: impl IntoIterator<Item = Action>) -> Vec<bool> {
+        let mut mask = vec![false; self.action_count()];
+        for action in actions.into_iter().filter(|&a| self.encodes(a)) {
+            mask[self.encode_action(action)] = true;
+        }
+        mask
+    }
+
+    pub fn encode_bidding(self, state: &BiddingState) -> Vec<f32> {
+        let mut features = Features::new(0, state.hand.iter().copied());
+        if let Some(contract) = state.curr_contract {
+            features.set(CONTRACT + contract_index(contract));
+        }
+        features.set(MIN_BID + state.min_effective_count as usize);
+        features.values
+    }
+
+    pub fn encode_extra(self, state: &ExtraExposedState) -> Vec<f32> {
+        self.encode_plan(state, &PartialPlan::default())
+    }
+
+    /// The declarer's view part way through a plan: the contract, friend
+    /// and discards chosen so far take the place of the bid contract, the
+    /// partner condition and the discards.
+    pub fn encode_plan(self, state: &ExtraExposedState, plan: &PartialPlan) -> Vec<f32> {
+        let mut features = Features::new(1, state.hand.iter().copied());
+        let contract = plan.contract.unwrap_or(state.contract);
+        features.set(CONTRACT + contract_index(contract));
+        features.set(MIN_BID + state.contract.effective_count() as usize - 1);
+        if let Some(friend) = plan.friend {
+            features.partner(friend);
+        }
+        features.cards(DISCARDED, plan.discards.iter().copied());
+        features.values
+    }
+
+    pub fn encode_play(self, state: &ExposedGameState) -> Vec<f32> {
+        let mut features = Features::new(2, state.hand.iter().copied());
+        features.set(CONTRACT + contract_index(state.contract));
+        features.set(DECLARER + state.declarer);
+        if let Some(player) = to_move(state) {
+            features.set(TO_MOVE + player);
+        }
+        features.partner(state.partner_condition);
+        if let Some(friend) = state.partner_revealed {
+            features.set(REVEALED + friend);
+        }
+        if let Some(discarded) = &state.discarded {
+            features.cards(DISCARDED, discarded.iter().copied());
+        }
+        for result in &state.round_results {
+            for (seat, &card) in result.submitted.iter().enumerate() {
+                features.set(PLAYED + seat * 53 + card.index());
+            }
+        }
+        for (seat, card) in state.submitted.iter().enumerate() {
+            if let Some(card) = card {
+                features.set(TRICK + seat * 53 + card.index());
+            }
+        }
+        if (state.round as usize) < 10 {
+            features.set(LEADER + state.round_starter);
+            features.set(ROUND + state.round as usize);
+        }
+        if let Some(suit) = state.round_suit {
+            features.set(LEAD_SUIT + suit.index());
+        }
+        if state.joker_called {
+            features.set(JOKER_CALLED);
+        }
+        features.values
+    }
+}
+
+/// The next player of the current trick, unless the hand is over.
+fn to_move(state: &ExposedGameState) -> Option<PlayerIndex> {
+    if state.round >= 10 {
+        return None;
+    }
+    (0..5)
+        .map(|i| (state.round_starter + i) % 5)
+        .find(|&p| state.submitted[p].is_none())
+}
+
+struct Features {
+    values: Vec<f32>,
+}
+
+impl Features {
+    fn new(phase: usize, hand: impl Iterator<Item = Card>) -> Self {
+        let mut features = Features {
+            values: vec![0.0; OBSERVATION_SIZE],
+        };
+        features.set(PHASE + phase);
+        features.cards(HAND, hand);
+        features
+    }
+
+    fn set(&mut self, index: usize) {
+        self.values[index] = 1.0;
+    }
+
+    fn cards(&mut self, section: usize, cards: impl Iterator<Item = Card>) {
+        for card in cards {
+            self.set(section + card.index());
+        }
+    }
+
+    fn partner(&mut self, condition: PartnerCondition) {
+        // The same order as the friend actions.
+        let index = Layout::V1.encode_action(Action::Friend(condition)) - FRIEND_CARDS;
+        self.set(PARTNER + index);
+    }
+}
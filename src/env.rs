@@ -0,0 +1,274 @@
+//! A hand of Mighty as an environment for reinforcement learning, played
+//! one decision at a time. Every decision is an index into the action space
+//! of an `encoding::Layout`, so that an agent only ever picks from a mask of
+//! legal indices. The declarer's plan is taken in steps: the final contract,
+//! then the friend, then the three discards one card at a time, unless the
+//! declarer surrenders in place of the first step.
+
+use crate::bidding_phase::PledgePhase;
+use crate::common::PlayerIndex;
+use crate::encoding::{bid_actions, Action, Layout, PartialPlan};
+use crate::extra_phase::{ExtraExposedState, ExtraPhase};
+use crate::json::Json;
+use crate::play_phase::PlayPhase;
+use crate::protocol::{bidding_state_to_json, cards_to_json, play_state_to_json, text};
+use crate::rules::RuleSet;
+
+enum Stage {
+    Pledge(PledgePhase),
+    Extra {
+        game: ExtraPhase,
+        plan: PartialPlan,
+    },
+    Play(PlayPhase),
+    /// The hand is over; `None` when every player passed or the declarer
+    /// surrendered.
+    Done(Option<PlayPhase>),
+}
+
+/// One hand at a time, from the deal to the settlement.
+pub struct MightyEnv {
+    pub rules: RuleSet,
+    pub layout: Layout,
+    stage: Stage,
+    payouts: Vec<i32>,
+}
+
+impl MightyEnv {
+    pub fn new(rules: RuleSet) -> Self {
+        let mut env = MightyEnv {
+            rules,
+            layout: Layout::LATEST,
+            stage: Stage::Done(None),
+            payouts: vec![0; 5],
+        };
+        env.reset(0);
+        env
+    }
+
+    /// Deals the hand for `seed`; player 0 bids first.
+    pub fn reset(&mut self, seed: u64) {
+        self.stage = Stage::Pledge(PledgePhase::seeded(0, self.rules.min_pledge, seed));
+        self.payouts = vec![0; 5];
+    }
+
+    pub fn done(&self) -> bool {
+        matches!(self.stage, Stage::Done(_))
+    }
+
+    /// The player to decide next, until the hand is over.
+    pub fn to_move(&self) -> Option<PlayerIndex> {
+        match &self.stage {
+            Stage::Pledge(game) => Some(game.turn_player()),
+            Stage::Extra { game, .. } => Some(game.declarer()),
+            Stage::Play(game) => game.turn_player(),
+            Stage::Done(_) => None,
+        }
+    }
+
+    /// The payout of each player once the hand is over, and zeros before.
+    pub fn payouts(&self) -> &[i32] {
+        &self.payouts
+    }
+
+    /// The game at the end of the hand, unless every player passed or the
+    /// declarer surrendered.
+    pub fn game(&self) -> Option<&PlayPhase> {
+        match &self.stage {
+            Stage::Play(game) | Stage::Done(Some(game)) => Some(game),
+            _ => None,
+        }
+    }
+
+    /// The legal actions of the player to move, in increasing order.
+    pub fn legal_actions(&self) -> Vec<usize> {
+        let actions: Vec<Action> = match &self.stage {
+            Stage::Pledge(game) => bid_actions(&game.bidding_state(game.turn_player())),
+            Stage::Extra { game, plan } => plan.legal_actions(&game.extra_state()),
+            Stage::Play(game) => match game.turn_player() {
+                Some(player) => game
+                    .legal_actions(player)
+                    .into_iter()
+                    .map(Action::Play)
+                    .collect(),
+                None => vec![],
+            },
+            Stage::Done(_) => vec![],
+        };
+        let mut actions = actions
+            .into_iter()
+            .filter(|&a| self.layout.encodes(a))
+            .map(|a| self.layout.encode_action(a))
+            .collect::<Vec<_>>();
+        actions.sort();
+        actions
+    }
+
+    pub fn legal_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.layout.action_count()];
+        for i in self.legal_actions() {
+            mask[i] = true;
+        }
+        mask
+    }
+
+    /// Takes a decision for the player to move. Fails on an action that is
+    /// not legal now, leaving the hand as it was.
+    pub fn step(&mut self, index: usize) -> Result<(), String> {
+        if !self.legal_actions().contains(&index) {
+            return Err(format!("illegal action: {}", index));
+        }
+        let action = self.layout.decode_action(index).unwrap();
+        let stage = std::mem::replace(&mut self.stage, Stage::Done(None));
+        self.stage = match (stage, action) {
+            (Stage::Pledge(mut game), Action::Bid(bid)) => {
+                game.player_bids(game.turn_player(), bid);
+                match (game.pledge_done(), game.cancelled()) {
+                    (false, _) => Stage::Pledge(game),
+                    (true, true) => Stage::Done(None),
+                    (true, false) => Stage::Extra {
+                        game: ExtraPhase::from_pledge(game),
+                        plan: PartialPlan::default(),
+                    },
+                }
+            }
+            (Stage::Extra { game, .. }, Action::Surrender) => {
+                self.payouts = self.rules.settle_surrender(&game.surrender()).payouts;
+                Stage::Done(None)
+            }
+            (Stage::Extra { mut game, mut plan }, action) => {
+                plan.take(action);
+                match plan.complete() {
+                    Some((contract, friend, discards)) => {
+                        Stage::Play(game.submit_plan(contract, friend, discards))
+                    }
+                    None => Stage::Extra { game, plan },
+                }
+            }
+            (Stage::Play(mut game), Action::Play(action)) => {
+                game.player_acts(game.turn_player().unwrap(), action);
+                if game.is_done() {
+                    self.payouts = self.rules.settle(&game).payouts;
+                    Stage::Done(Some(game))
+                } else {
+                    Stage::Play(game)
+                }
+            }
+            _ => unreachable!("legal actions match the stage"),
+        };
+        Ok(())
+    }
+
+    /// What `seat` knows of the hand: the phase, the player to move and the
+    /// seat's view in the forms of the network protocol.
+    pub fn observation(&self, seat: PlayerIndex) -> Json {
+        let (phase, state) = match &self.stage {
+            Stage::Pledge(game) => ("pledge", bidding_state_to_json(&game.bidding_state(seat))),
+            Stage::Extra { game, plan } => {
+                let mut state = Json::object(vec![
+                    ("hand", cards_to_json(&game.hands[seat])),
+                    ("contract", text(game.contract)),
+                    ("declarer", game.declarer().into()),
+                ]);
+                if seat == game.declarer() {
+                    let Json::Object(fields) = &mut state else {
+                        unreachable!()
+                    };
+                    fields.push(("plan_contract".to_string(), plan.contract.map(text).into()));
+                    fields.push(("plan_friend".to_string(), plan.friend.map(text).into()));
+                    fields.push(("plan_discards".to_string(), cards_to_json(&plan.discards)));
+                }
+                ("extra", state)
+            }
+            Stage::Play(game) => ("play", play_state_to_json(&game.play_state(seat))),
+            Stage::Done(Some(game)) => ("done", play_state_to_json(&game.play_state(seat))),
+            Stage::Done(None) => ("done", Json::Null),
+        };
+        Json::object(vec![
+            ("phase", phase.into()),
+            ("seat", seat.into()),
+            ("to_move", self.to_move().into()),
+            ("state", state),
+        ])
+    }
+
+    /// What `seat` knows of the hand, encoded in the env's layout. A seat
+    /// other than the declarer's sees its own hand and the bid contract
+    /// while the plan is made.
+    pub fn features(&self, seat: PlayerIndex) -> Vec<f32> {
+        match &self.stage {
+            Stage::Pledge(game) => self.layout.encode_bidding(&game.bidding_state(seat)),
+            Stage::Extra { game, plan } => {
+                let state = ExtraExposedState {
+                    hand: game.hands[seat].clone(),
+                    contract: game.contract,
+                };
+                if seat == game.declarer() {
+                    self.layout.encode_plan(&state, plan)
+                } else {
+                    self.layout.encode_extra(&state)
+                }
+            }
+            Stage::Play(game) | Stage::Done(Some(game)) => {
+                self.layout.encode_play(&game.play_state(seat))
+            }
+            Stage::Done(None) => vec![0.0; self.layout.observation_size()],
+        }
+    }
+}
+
+/// Many environments stepped together. A finished hand is dealt again at
+/// once, from its seed advanced by the number of environments, so that the
+/// batch never plays the same deal twice.
+pub struct VecEnv {
+    pub envs: Vec<MightyEnv>,
+    seeds: Vec<u64>,
+}
+
+impl VecEnv {
+    pub fn new(count: usize, rules: RuleSet) -> Self {
+        VecEnv {
+            envs: (0..count).map(|_| MightyEnv::new(rules.clone())).collect(),
+            seeds: vec![0; count],
+        }
+    }
+
+    /// Deals environment `i` from `seed + i`.
+    pub fn reset(&mut self, seed: u64) {
+        for (i, env) in self.envs.iter_mut().enumerate() {
+            self.seeds[i] = seed.wrapping_add(i as u64);
+            env.reset(self.seeds[i]);
+        }
+    }
+
+    /// Steps every environment with its action. Returns the payouts and
+    /// whether the hand ended, for each environment; an ended hand has
+    /// been dealt again. Fails, before stepping any, when an action is
+    /// illegal.
+    pub fn step(&mut self, actions: &[usize]) -> Result<Vec<(Vec<i32>, bool)>, String> {
+        if actions.len() != self.envs.len() {
+            return Err(format!(
+                "expected {} actions, got {}",
+                self.envs.len(),
+                actions.len()
+            ));
+        }
+        for (i, (env, action)) in self.envs.iter().zip(actions).enumerate() {
+            if !env.legal_actions().contains(action) {
+                return Err(format!("illegal action {} in environment {}", action, i));
+            }
+        }
+        let count = self.envs.len() as u64;
+        let mut results = vec![];
+        for (i, (env, &action)) in self.envs.iter_mut().zip(actions).enumerate() {
+            env.step(action)?;
+            let done = env.done();
+            results.push((env.payouts().to_vec(), done));
+            if done {
+                self.seeds[i] = self.seeds[i].wrapping_add(count);
+                env.reset(self.seeds[i]);
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,231 @@
+//! Monte Carlo odds of making a contract with a given hand.
+//!
+//! The rest of the deck is dealt out at random to the kitty and the other
+//! four hands. For each trump the hand becomes the declarer's, the playout
+//! bot picks the discards and the friend and plays every seat until `depth`
+//! rounds are left, and the solver finishes the hand. The final points of
+//! the declarer team do not depend on the count bid, so each deal tells
+//! for every count at once.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::{Card, Suit};
+use crate::common::{Contract, Hand, PartnerCondition, PlayAction};
+use crate::extra_phase::{ExtraExposedState, ExtraPhase};
+use crate::heuristic::{HeuristicPlayer, Weights};
+use crate::play_phase::ExposedGameState;
+use crate::player::{create_player, Player};
+use crate::solver::Solver;
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The trumps in the order of `MakeOdds::points`, no trump last.
+pub const DENOMINATIONS: [Option<Suit>; 5] = [
+    Some(Suit::H),
+    Some(Suit::D),
+    Some(Suit::C),
+    Some(Suit::S),
+    None,
+];
+
+/// How often each final point total came up, per trump.
+#[derive(Clone, Debug)]
+pub struct MakeOdds {
+    pub samples: usize,
+    /// Deals in which the declarer team ended with exactly `i` points, at
+    /// index `i` for each of `DENOMINATIONS`.
+    pub points: [[usize; 21]; 5],
+}
+
+impl MakeOdds {
+    /// The share of deals in which the declarer team took at least `count`
+    /// points with `trump`.
+    pub fn probability(&self, trump: Option<Suit>, count: u8) -> f64 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        let i = DENOMINATIONS.iter().position(|&d| d == trump).unwrap();
+        let made = self.points[i][(count as usize).min(21)..]
+            .iter()
+            .sum::<usize>();
+        made as f64 / self.samples as f64
+    }
+
+    /// The likeliest contract to make among the lowest bids of each trump
+    /// above `min_effective_count`, with its probability.
+    pub fn best_bid(&self, min_effective_count: u8) -> Option<(Contract, f64)> {
+        DENOMINATIONS
+            .into_iter()
+            .map(|suit| {
+                let count = match suit {
+                    Some(_) => min_effective_count + 1,
+                    None => min_effective_count,
+                };
+                Contract { suit, count }
+            })
+            .filter(|c| c.count <= 20)
+            .map(|c| (c, self.probability(c.suit, c.count)))
+            .fold(None, |best: Option<(Contract, f64)>, next| match best {
+                Some(best) if best.1 >= next.1 => Some(best),
+                _ => Some(next),
+            })
+    }
+}
+
+/// A row per trump with the chance of making each count from 13 to 20.
+impl fmt::Display for MakeOdds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "    ")?;
+        for count in 13..=20 {
+            write!(f, " {:>4}", count)?;
+        }
+        for trump in DENOMINATIONS {
+            let name = trump.map_or("NT".to_string(), |s| s.to_string());
+            write!(f, "\n{:<4}", name)?;
+            for count in 13..=20 {
+                write!(f, " {:>3.0}%", 100.0 * self.probability(trump, count))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Estimator {
+    /// Deals sampled per estimate.
+    pub samples: usize,
+    /// Rounds left from which the solver finishes the hand.
+    pub depth: u8,
+    pub seed: u64,
+    players: Vec<Box<dyn Player>>,
+}
+
+impl Estimator {
+    /// An estimator playing hands out with the bot named `playout`.
+    pub fn new(playout: &str) -> Result<Estimator, String> {
+        let players = (0..5)
+            .map(|_| create_player(playout))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Estimator {
+            samples: 32,
+            depth: 4,
+            seed: 0,
+            players,
+        })
+    }
+
+    pub fn estimate(&self, hand: &Hand) -> Result<MakeOdds, String> {
+        if hand.len() != 10 {
+            return Err(format!("a hand has 10 cards, not {}", hand.len()));
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut odds = MakeOdds {
+            samples: self.samples,
+            points: [[0; 21]; 5],
+        };
+        for _ in 0..self.samples {
+            let mut rest = Card::deck()
+                .into_iter()
+                .filter(|c| !hand.contains(c))
+                .collect::<Vec<_>>();
+            rest.shuffle(&mut rng);
+            let mut hands = vec![hand.clone()];
+            for _ in 0..4 {
+                hands.push(rest.drain(..10).collect());
+            }
+            hands[0].extend(rest);
+            for (i, trump) in DENOMINATIONS.into_iter().enumerate() {
+                let points = self.play_out(hands.clone(), trump);
+                odds.points[i][points as usize] += 1;
+            }
+        }
+        Ok(odds)
+    }
+
+    /// Final points of the declarer team, with seat 0 declaring `trump`
+    /// holding the kitty in `hands`.
+    fn play_out(&self, hands: Vec<Hand>, trump: Option<Suit>) -> u8 {
+        let contract = Contract {
+            suit: trump,
+            count: 13,
+        };
+        let mut extra = ExtraPhase {
+            hands,
+            contract,
+            declarer: 0,
+        };
+        let (_, condition, discards) = self.players[0].declare_plan(extra.extra_state());
+        let mut game = extra.submit_plan(contract, condition, discards);
+        while let Some(player) = game.turn_player() {
+            if 10 - game.round <= self.depth {
+                return Solver::new().solve(&game).points;
+            }
+            let action = self.players[player].play_action(game.play_state(player));
+            game.player_acts(player, action);
+            for (i, bot) in self.players.iter().enumerate() {
+                bot.observe(&game.play_state(i));
+            }
+        }
+        game.declarer_team_score()
+    }
+}
+
+/// Bids the likeliest contract to make at the lowest level, when the odds
+/// are at least `threshold`, and otherwise plays as `HeuristicPlayer`.
+pub struct EstimatePlayer {
+    pub estimator: Estimator,
+    pub threshold: f64,
+    heuristic: HeuristicPlayer,
+    // The odds of the last hand, which stay the same through the pledge.
+    odds: RefCell<Option<(Vec<Card>, MakeOdds)>>,
+}
+
+impl EstimatePlayer {
+    pub fn new(estimator: Estimator) -> Self {
+        EstimatePlayer {
+            estimator,
+            threshold: 0.5,
+            heuristic: HeuristicPlayer::new(Weights::default()),
+            odds: RefCell::new(None),
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn odds(&self, hand: &Hand) -> MakeOdds {
+        let mut cards = hand.iter().copied().collect::<Vec<_>>();
+        cards.sort();
+        let mut cache = self.odds.borrow_mut();
+        match &*cache {
+            Some((cached, odds)) if *cached == cards => odds.clone(),
+            _ => {
+                let odds = self.estimator.estimate(hand).unwrap();
+                *cache = Some((cards, odds.clone()));
+                odds
+            }
+        }
+    }
+}
+
+impl Player for EstimatePlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        let (contract, probability) = self.odds(&state.hand).best_bid(state.min_effective_count)?;
+        (probability >= self.threshold).then_some(contract)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        self.heuristic.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        self.heuristic.play_action(state)
+    }
+}
@@ -0,0 +1,255 @@
+use crate::common::{Claim, Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::json::Json;
+use crate::play_phase::{PartyType, PlayPhase};
+use crate::protocol::{optional, parse, player, round, text};
+use crate::rules::Settlement;
+
+/// Public events of a hand: what every seat at the table gets to see.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Event {
+    Bid {
+        player: PlayerIndex,
+        contract: Option<Contract>,
+    },
+    Cancelled,
+    Plan {
+        declarer: PlayerIndex,
+        contract: Contract,
+        partner_condition: PartnerCondition,
+    },
+    /// The declarer gives up instead of making a plan. The result follows.
+    Surrender {
+        declarer: PlayerIndex,
+    },
+    Play {
+        player: PlayerIndex,
+        action: PlayAction,
+    },
+    Friend {
+        player: PlayerIndex,
+    },
+    Trick {
+        round: u8,
+        winner: PlayerIndex,
+        points: u8,
+    },
+    /// A bot plays for the player, who lost the connection.
+    Away {
+        player: PlayerIndex,
+    },
+    /// The player is back from being away.
+    Back {
+        player: PlayerIndex,
+    },
+    /// The player ran out of time, and a default move is made for them.
+    Timeout {
+        player: PlayerIndex,
+    },
+    /// The player claims the rest of the hand. An accepted claim is
+    /// followed by the plays settling it.
+    Claim {
+        player: PlayerIndex,
+        claim: Claim,
+        accepted: bool,
+    },
+    Result {
+        scores: Vec<u8>,
+        declarer_wins: bool,
+        run: bool,
+        back_run: bool,
+        payouts: Vec<i32>,
+    },
+}
+
+impl Event {
+    /// Events of `player` taking `action`, given the game before and after.
+    pub fn after_action(
+        before: &PlayPhase,
+        game: &PlayPhase,
+        player: PlayerIndex,
+        action: PlayAction,
+    ) -> Vec<Event> {
+        let mut events = vec![Event::Play { player, action }];
+        if let Some(friend) = game
+            .partner_revealed
+            .filter(|_| before.partner_revealed.is_none())
+        {
+            events.push(Event::Friend { player: friend });
+        }
+        if game.round > before.round {
+            let result = game.round_results.last().unwrap();
+            events.push(Event::Trick {
+                round: before.round,
+                winner: result.winner,
+                points: result.submitted.iter().map(|c| c.score()).sum(),
+            });
+        }
+        events
+    }
+
+    pub fn result(game: &PlayPhase, settlement: &Settlement) -> Event {
+        let result = game.result();
+        Event::Result {
+            scores: result.scores,
+            declarer_wins: result.win_side == PartyType::Leading,
+            run: settlement.run,
+            back_run: settlement.back_run,
+            payouts: settlement.payouts.clone(),
+        }
+    }
+
+    /// The result of a hand the declarer gave up, in which no card was
+    /// played.
+    pub fn surrender_result(settlement: &Settlement) -> Event {
+        Event::Result {
+            scores: vec![0; 5],
+            declarer_wins: false,
+            run: false,
+            back_run: false,
+            payouts: settlement.payouts.clone(),
+        }
+    }
+
+    /// Whether the hand is over after this event.
+    pub fn ends_hand(&self) -> bool {
+        matches!(self, Event::Cancelled | Event::Result { .. })
+    }
+
+    pub fn to_json(&self) -> Json {
+        let (event, mut fields) = match self {
+            Event::Bid { player, contract } => (
+                "bid",
+                vec![
+                    ("player", (*player).into()),
+                    ("contract", contract.map(text).into()),
+                ],
+            ),
+            Event::Cancelled => ("cancelled", vec![]),
+            Event::Plan {
+                declarer,
+                contract,
+                partner_condition,
+            } => (
+                "plan",
+                vec![
+                    ("declarer", (*declarer).into()),
+                    ("contract", text(contract)),
+                    ("partner_condition", text(partner_condition)),
+                ],
+            ),
+            Event::Surrender { declarer } => ("surrender", vec![("declarer", (*declarer).into())]),
+            Event::Play { player, action } => (
+                "play",
+                vec![("player", (*player).into()), ("action", text(action))],
+            ),
+            Event::Friend { player } => ("friend", vec![("player", (*player).into())]),
+            Event::Away { player } => ("away", vec![("player", (*player).into())]),
+            Event::Back { player } => ("back", vec![("player", (*player).into())]),
+            Event::Timeout { player } => ("timeout", vec![("player", (*player).into())]),
+            Event::Claim {
+                player,
+                claim,
+                accepted,
+            } => (
+                "claim",
+                vec![
+                    ("player", (*player).into()),
+                    ("claim", text(claim)),
+                    ("accepted", (*accepted).into()),
+                ],
+            ),
+            Event::Trick {
+                round,
+                winner,
+                points,
+            } => (
+                "trick",
+                vec![
+                    ("round", (*round).into()),
+                    ("winner", (*winner).into()),
+                    ("points", (*points).into()),
+                ],
+            ),
+            Event::Result {
+                scores,
+                declarer_wins,
+                run,
+                back_run,
+                payouts,
+            } => (
+                "result",
+                vec![
+                    ("scores", scores.clone().into()),
+                    ("declarer_wins", (*declarer_wins).into()),
+                    ("run", (*run).into()),
+                    ("back_run", (*back_run).into()),
+                    ("payouts", payouts.clone().into()),
+                ],
+            ),
+        };
+        fields.insert(0, ("event", event.into()));
+        Json::object(fields)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Event, String> {
+        match json.field("event")?.as_str()? {
+            "bid" => Ok(Event::Bid {
+                player: player(json.field("player")?)?,
+                contract: optional(json.field("contract")?, parse)?,
+            }),
+            "cancelled" => Ok(Event::Cancelled),
+            "plan" => Ok(Event::Plan {
+                declarer: player(json.field("declarer")?)?,
+                contract: parse(json.field("contract")?)?,
+                partner_condition: parse(json.field("partner_condition")?)?,
+            }),
+            "surrender" => Ok(Event::Surrender {
+                declarer: player(json.field("declarer")?)?,
+            }),
+            "play" => Ok(Event::Play {
+                player: player(json.field("player")?)?,
+                action: parse(json.field("action")?)?,
+            }),
+            "friend" => Ok(Event::Friend {
+                player: player(json.field("player")?)?,
+            }),
+            "away" => Ok(Event::Away {
+                player: player(json.field("player")?)?,
+            }),
+            "back" => Ok(Event::Back {
+                player: player(json.field("player")?)?,
+            }),
+            "timeout" => Ok(Event::Timeout {
+                player: player(json.field("player")?)?,
+            }),
+            "claim" => Ok(Event::Claim {
+                player: player(json.field("player")?)?,
+                claim: parse(json.field("claim")?)?,
+                accepted: json.field("accepted")?.as_bool()?,
+            }),
+            "trick" => Ok(Event::Trick {
+                round: round(json.field("round")?)?,
+                winner: player(json.field("winner")?)?,
+                points: json.field("points")?.as_u64()? as u8,
+            }),
+            "result" => Ok(Event::Result {
+                scores: json
+                    .field("scores")?
+                    .as_array()?
+                    .iter()
+                    .map(|s| s.as_u64().map(|s| s as u8))
+                    .collect::<Result<_, _>>()?,
+                declarer_wins: json.field("declarer_wins")?.as_bool()?,
+                run: json.field("run")?.as_bool()?,
+                back_run: json.field("back_run")?.as_bool()?,
+                payouts: json
+                    .field("payouts")?
+                    .as_array()?
+                    .iter()
+                    .map(|p| p.as_i64().map(|p| p as i32))
+                    .collect::<Result<_, _>>()?,
+            }),
+            event => Err(format!("unknown event: {}", event)),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Tuning of `HeuristicPlayer` weights by a genetic algorithm. A candidate's
+//! fitness is its mean payout in a `Tournament` against the opponent bot;
+//! every candidate of a generation is dealt the same hands, so that they
+//! are compared on equal terms. Given the seed, the generations come out
+//! the same whatever the number of threads, as long as the opponent plays
+//! the same way in the same position, as `heuristic` does.
+
+use crate::clock::TimeControl;
+use crate::heuristic::{HeuristicPlayer, Weights, PARAMETERS};
+use crate::player::{check_player, create_player};
+use crate::rules::RuleSet;
+use crate::tournament::{Entrant, Tournament};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+pub struct Evolution {
+    pub population: usize,
+    pub generations: usize,
+    /// Hands each candidate plays per generation.
+    pub games: usize,
+    pub seed: u64,
+    /// The largest change a mutation makes to a weight, as a fraction of
+    /// the weight's range.
+    pub mutation: f64,
+    /// The best candidates carried over unchanged to the next generation.
+    pub elite: usize,
+    pub opponent: String,
+    pub rules: RuleSet,
+    pub threads: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Generation {
+    pub number: usize,
+    /// The candidates with their fitness, the fittest first.
+    pub ranked: Vec<(Weights, f64)>,
+}
+
+impl Generation {
+    pub fn best(&self) -> &(Weights, f64) {
+        &self.ranked[0]
+    }
+
+    pub fn mean_fitness(&self) -> f64 {
+        let sum = self.ranked.iter().map(|(_, f)| f).sum::<f64>();
+        sum / self.ranked.len() as f64
+    }
+}
+
+impl Evolution {
+    /// Runs every generation, starting from the default weights and
+    /// mutations of them, and reports each generation as it is ranked.
+    /// Returns the fittest weights of the last generation.
+    pub fn run(&self, mut report: impl FnMut(&Generation)) -> Result<Weights, String> {
+        if self.population < 2 {
+            return Err("the population needs at least 2 candidates".to_string());
+        }
+        if self.generations == 0 {
+            return Err("at least one generation is needed".to_string());
+        }
+        check_player(&self.opponent)?;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut population = vec![Weights::default()];
+        while population.len() < self.population {
+            population.push(self.mutate(&Weights::default(), &mut rng));
+        }
+        let mut best = Weights::default();
+        for number in 0..self.generations {
+            let deals = self.seed.wrapping_add(number as u64);
+            let fitness = self.fitness(&population, deals);
+            let mut ranked = population.into_iter().zip(fitness).collect::<Vec<_>>();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let generation = Generation { number, ranked };
+            report(&generation);
+            best = generation.best().0.clone();
+            population = self.breed(&generation.ranked, &mut rng);
+        }
+        Ok(best)
+    }
+
+    /// The mean payout of each candidate, played over the worker threads.
+    fn fitness(&self, population: &[Weights], deals: u64) -> Vec<f64> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![0.0; population.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= population.len() {
+                        break;
+                    }
+                    let weights = population[i].clone();
+                    let opponent = self.opponent.clone();
+                    let tournament = Tournament {
+                        entrants: vec![
+                            Entrant::new("candidate", move || {
+                                Box::new(HeuristicPlayer::new(weights.clone()))
+                            }),
+                            Entrant::new(&self.opponent, move || create_player(&opponent).unwrap()),
+                        ],
+                        rules: self.rules.clone(),
+                        games: self.games,
+                        seed: deals,
+                        time_control: TimeControl::default(),
+                    };
+                    let fitness = tournament.run().stats[0].mean_payout();
+                    results.lock().unwrap()[i] = fitness;
+                });
+            }
+        });
+        results.into_inner().unwrap()
+    }
+
+    /// The next generation: the elite, then children of parents picked by
+    /// two-way tournament selection, by uniform crossover and mutation.
+    fn breed(&self, ranked: &[(Weights, f64)], rng: &mut StdRng) -> Vec<Weights> {
+        let mut next = ranked
+            .iter()
+            .take(self.elite.min(self.population))
+            .map(|(w, _)| w.clone())
+            .collect::<Vec<_>>();
+        // `ranked` is fittest first, so the lower index wins.
+        let pick = |rng: &mut StdRng| {
+            let a = rng.random_range(0..ranked.len());
+            let b = rng.random_range(0..ranked.len());
+            ranked[a.min(b)].0.to_vec()
+        };
+        while next.len() < self.population {
+            let (mother, father) = (pick(rng), pick(rng));
+            let child = mother
+                .iter()
+                .zip(&father)
+                .map(|(&m, &f)| if rng.random_bool(0.5) { m } else { f })
+                .collect::<Vec<_>>();
+            next.push(self.mutate(&Weights::from_vec(&child), rng));
+        }
+        next
+    }
+
+    /// `weights` with each weight moved by up to `mutation` of its range,
+    /// kept within the range.
+    fn mutate(&self, weights: &Weights, rng: &mut StdRng) -> Weights {
+        let values = weights
+            .to_vec()
+            .into_iter()
+            .zip(PARAMETERS)
+            .map(|(value, (_, low, high))| {
+                let step = self.mutation * (high - low) * rng.random_range(-1.0..=1.0);
+                (value + step).clamp(low, high)
+            })
+            .collect::<Vec<_>>();
+        Weights::from_vec(&values)
+    }
+}
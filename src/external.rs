@@ -0,0 +1,320 @@
+//! Bots written in other languages, run as a child process that speaks
+//! JSON lines over its stdin and stdout.
+//!
+//! # Protocol
+//!
+//! Every message is a JSON object on a line of its own, in the forms of the
+//! network protocol (see `protocol`): cards, contracts, partner conditions
+//! and actions are written as text (`SA`, `S14`, `card:DA`, `call:C3`).
+//!
+//! 1. The engine opens with `{"type":"hello","version":1}`. The bot answers
+//!    `{"type":"ready","version":1,"name":"..."}`; a bot speaking another
+//!    version is refused.
+//! 2. For each decision the engine sends a request, which the bot answers
+//!    with one message:
+//!    - `{"type":"bidding","state":{...}}` with `{"type":"bid","contract":"S14"}`,
+//!      or a `null` contract to pass;
+//!    - `{"type":"declare","state":{...}}` with `{"type":"plan","contract":"S14",
+//!      "partner_condition":"card:SA","discards":["H2","H3","D4"]}`, or
+//!      `{"type":"surrender"}` to give up the hand;
+//!    - `{"type":"turn","state":{...},"legal_actions":[...]}` with
+//!      `{"type":"play","action":"SA"}`.
+//! 3. After every card played the engine sends the seat's view as
+//!    `{"type":"state","state":{...}}`, which needs no answer.
+//! 4. A process plays a single hand. Before the next hand, and once the
+//!    engine is done with the bot, it sends `{"type":"quit"}`. A bot that
+//!    has not exited a second later is killed.
+//!
+//! What a bot writes to stderr is passed through. A bot that exits, writes
+//! something unreadable, answers with an illegal move or takes longer than
+//! the timeout or the time control allows is killed; its move is made for
+//! it as on a timeout, and a fresh process is started for its next move.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::Card;
+use crate::clock::{default_action, default_bid, default_plan};
+use crate::common::{Contract, PartnerCondition, PlayAction};
+use crate::extra_phase::ExtraExposedState;
+use crate::json::Json;
+use crate::play_phase::ExposedGameState;
+use crate::player::Player;
+use crate::protocol::{accept_action, accept_bid, accept_plan, ClientMessage, ServerMessage};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The version of the protocol above.
+pub const VERSION: u64 = 1;
+
+/// A bot in a child process.
+pub struct ExternalPlayer {
+    command: Vec<String>,
+    /// How long the bot may take over a message before it is taken to have
+    /// hung.
+    pub timeout: Duration,
+    /// When the answer to the current request is due, under a time control.
+    deadline: Cell<Option<Instant>>,
+    bot: RefCell<Option<Bot>>,
+    /// Whether the running bot has yet to be asked anything.
+    fresh: Cell<bool>,
+    /// Whether the bot failed to give the last answer.
+    failed: Cell<bool>,
+    // The plan answered when asked whether to surrender.
+    plan: RefCell<Option<(Contract, PartnerCondition, HashSet<Card>)>>,
+}
+
+struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl ExternalPlayer {
+    /// Starts the program and its arguments in `command` as a bot. Fails
+    /// when it cannot be started or does not complete the handshake.
+    pub fn spawn(command: Vec<String>) -> Result<ExternalPlayer, String> {
+        if command.is_empty() {
+            return Err("no bot command".to_string());
+        }
+        let player = ExternalPlayer {
+            command,
+            timeout: Duration::from_secs(60),
+            deadline: Cell::new(None),
+            bot: RefCell::new(None),
+            fresh: Cell::new(true),
+            failed: Cell::new(false),
+            plan: RefCell::new(None),
+        };
+        *player.bot.borrow_mut() = Some(player.start()?);
+        Ok(player)
+    }
+
+    fn start(&self) -> Result<Bot, String> {
+        let mut bot = Bot::spawn(&self.command)?;
+        bot.send(&Json::object(vec![
+            ("type", "hello".into()),
+            ("version", VERSION.into()),
+        ]))?;
+        let ready = bot.receive(self.timeout)?;
+        if ready.field("type")?.as_str()? != "ready" {
+            return Err("expected ready".to_string());
+        }
+        let version = ready.field("version")?.as_u64()?;
+        if version != VERSION {
+            return Err(format!(
+                "the bot speaks version {} of the protocol, not {}",
+                version, VERSION
+            ));
+        }
+        Ok(bot)
+    }
+
+    /// Asks the bot, starting it first when it is not running. Returns
+    /// `None` when the bot failed, after killing it.
+    fn ask<T>(
+        &self,
+        request: &ServerMessage,
+        accept: impl Fn(ClientMessage) -> Result<T, String>,
+    ) -> Option<T> {
+        let mut slot = self.bot.borrow_mut();
+        self.fresh.set(false);
+        let answer = (|| {
+            let bot = match &mut *slot {
+                Some(bot) => bot,
+                None => slot.insert(self.start()?),
+            };
+            bot.send(&request.to_json())?;
+            let due = self
+                .deadline
+                .get()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let reply = bot.receive(due.map_or(self.timeout, |due| due.min(self.timeout)))?;
+            accept(ClientMessage::from_json(&reply)?)
+        })();
+        self.failed.set(answer.is_err());
+        match answer {
+            Ok(answer) => Some(answer),
+            Err(e) => {
+                self.fail(&mut slot, &e);
+                None
+            }
+        }
+    }
+
+    fn fail(&self, slot: &mut Option<Bot>, error: &str) {
+        eprintln!("bot {}: {}", self.command.join(" "), error);
+        *slot = None;
+    }
+}
+
+impl Player for ExternalPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        self.ask(&ServerMessage::Bidding(state.clone()), |m| {
+            accept_bid(state, m)
+        })
+        .unwrap_or_else(default_bid)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        if let Some(plan) = self.plan.borrow_mut().take() {
+            return plan;
+        }
+        self.ask(&ServerMessage::Declare(state.clone()), |m| {
+            accept_plan(&state, m)
+        })
+        .flatten()
+        .unwrap_or_else(|| default_plan(&state))
+    }
+
+    fn surrender(&self, state: &ExtraExposedState) -> bool {
+        let answer = self.ask(&ServerMessage::Declare(state.clone()), |m| {
+            accept_plan(state, m)
+        });
+        match answer {
+            Some(None) => true,
+            plan => {
+                *self.plan.borrow_mut() = plan.flatten();
+                false
+            }
+        }
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        let request = ServerMessage::Turn {
+            state: state.clone(),
+            claims: false,
+        };
+        self.ask(&request, |m| accept_action(&state, m))
+            .unwrap_or_else(|| default_action(&state))
+    }
+
+    fn observe(&self, state: &ExposedGameState) {
+        let mut slot = self.bot.borrow_mut();
+        if let Some(bot) = &mut *slot {
+            if let Err(e) = bot.send(&ServerMessage::State(state.clone()).to_json()) {
+                self.fail(&mut slot, &e);
+            }
+        }
+    }
+
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
+
+    fn failed(&self) -> bool {
+        self.failed.get()
+    }
+
+    /// Quits a bot that has played, so that the hand starts with a fresh
+    /// one.
+    fn new_hand(&self) {
+        if !self.fresh.replace(true) {
+            *self.bot.borrow_mut() = None;
+        }
+    }
+}
+
+/// The bot side of the protocol: answers the engine on `input` and
+/// `output` with `player` until told to quit.
+pub fn run_bot(
+    player: &dyn Player,
+    name: &str,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), String> {
+    for line in input.lines() {
+        let json = line.map_err(|e| e.to_string())?.trim().parse::<Json>()?;
+        let reply = match json.field("type")?.as_str()? {
+            "hello" => {
+                let version = json.field("version")?.as_u64()?;
+                if version != VERSION {
+                    return Err(format!("unsupported protocol version {}", version));
+                }
+                Json::object(vec![
+                    ("type", "ready".into()),
+                    ("version", VERSION.into()),
+                    ("name", name.into()),
+                ])
+            }
+            "quit" => return Ok(()),
+            _ => match ServerMessage::from_json(&json)? {
+                ServerMessage::State(state) => {
+                    player.observe(&state);
+                    continue;
+                }
+                message => match message.answer(player) {
+                    Some(answer) => answer.to_json(),
+                    None => continue,
+                },
+            },
+        };
+        writeln!(output, "{}", reply)
+            .and_then(|_| output.flush())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+impl Bot {
+    fn spawn(command: &[String]) -> Result<Bot, String> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", command[0], e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        // Read on a thread of its own, so that a hung bot can be timed out.
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Bot {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn send(&mut self, message: &Json) -> Result<(), String> {
+        writeln!(self.stdin, "{}", message).map_err(|_| "the bot has exited".to_string())
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Json, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => line.trim().parse(),
+            Err(RecvTimeoutError::Timeout) => Err("the bot took too long".to_string()),
+            Err(RecvTimeoutError::Disconnected) => Err("the bot has exited".to_string()),
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let quit = Json::object(vec![("type", "quit".into())]);
+        if self.send(&quit).is_ok() {
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = self.child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -1,7 +1,7 @@
 use crate::bidding_phase::PledgePhase;
 use crate::card::Card;
 use crate::common::{Contract, Hand, PartnerCondition, PlayerIndex};
-use crate::play_phase::PlayPhase;
+use crate::play_phase::{EarlyEnd, PlayPhase};
 use std::collections::HashSet;
 
 #[derive(Clone, Debug)]
@@ -11,6 +11,13 @@ pub struct ExtraExposedState {
     // TODO: consider other players' contracts
 }
 
+/// A hand the declarer gave up after taking the kitty, before play.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Surrender {
+    pub declarer: PlayerIndex,
+    pub contract: Contract,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct ExtraPhase {
     pub hands: Vec<Hand>,
@@ -42,6 +49,14 @@ impl ExtraPhase {
         }
     }
 
+    /// Ends the hand with the declarer giving up, instead of a plan.
+    pub fn surrender(&self) -> Surrender {
+        Surrender {
+            declarer: self.declarer,
+            contract: self.contract,
+        }
+    }
+
     pub fn submit_plan(
         &mut self,
         contract: Contract,
@@ -71,6 +86,7 @@ impl ExtraPhase {
             round_results: vec![],
             round_starter: self.declarer,
             round_suit: None,
+            early_end: EarlyEnd::Never,
         }
     }
 }
@@ -0,0 +1,270 @@
+use crate::card::{Card, Suit};
+use crate::common::{PartnerCondition, PlayerIndex};
+use crate::inference::Knowledge;
+use crate::play_phase::ExposedGameState;
+use std::collections::HashMap;
+
+/// Prior weight of the declarer having called a card it kept or discarded
+/// itself, relative to the card being in any other unknown slot. A declarer
+/// calls a card it lacks, so the card only lies with it when it plays alone
+/// on purpose or bluffs by discarding it; one in ten is a guess at how often
+/// that happens, not fitted to recorded play.
+pub const DEFAULT_DECLARER_WEIGHT: f64 = 0.1;
+
+// The hidden discards, counted as a sixth holder next to the five seats.
+const DISCARDS: usize = 5;
+
+/// One seat's belief about who the friend is, kept up to date as play goes.
+///
+/// Under a `CardCondition`, every deal of the unknown cards consistent with the
+/// play so far (voids and declined joker calls) is taken as equally likely,
+/// except that deals where the declarer holds or discarded the called card are
+/// down-weighted by `declarer_weight`. The probabilities are exact under that
+/// model and are obtained by counting deals, not by sampling. The model does
+/// not look at which card was called, only at where it may lie. Under a
+/// `Round` condition the friend is whoever wins that round, which no deal
+/// tells, so until then every seat, the declarer included, is given one in
+/// five.
+#[derive(Clone, Debug)]
+pub struct FriendInference {
+    pub declarer_weight: f64,
+    knowledge: Knowledge,
+    declarer: PlayerIndex,
+    partner_condition: PartnerCondition,
+    partner_revealed: Option<PlayerIndex>,
+    probabilities: Vec<f64>,
+}
+
+impl FriendInference {
+    pub fn new(seat: PlayerIndex, state: &ExposedGameState) -> Self {
+        let mut inference = FriendInference {
+            declarer_weight: DEFAULT_DECLARER_WEIGHT,
+            knowledge: Knowledge::new(seat, state),
+            declarer: state.declarer,
+            partner_condition: state.partner_condition,
+            partner_revealed: state.partner_revealed,
+            probabilities: vec![],
+        };
+        inference.recompute();
+        inference
+    }
+
+    pub fn update(&mut self, state: &ExposedGameState) {
+        self.knowledge.update(state);
+        self.partner_revealed = state.partner_revealed;
+        self.recompute();
+    }
+
+    pub fn knowledge(&self) -> &Knowledge {
+        &self.knowledge
+    }
+
+    /// Probability of each seat being the friend. The declarer's entry is the
+    /// chance that it holds the called card itself; whatever is missing from a
+    /// total of one is the chance of the card lying among the discards.
+    pub fn probabilities(&self) -> &[f64] {
+        &self.probabilities
+    }
+
+    pub fn no_friend(&self) -> f64 {
+        1.0 - self.probabilities.iter().sum::<f64>()
+    }
+
+    fn recompute(&mut self) {
+        let mut probabilities = vec![0.0; 5];
+        if let Some(partner) = self.partner_revealed {
+            probabilities[partner] = 1.0;
+        } else {
+            match self.partner_condition {
+                PartnerCondition::CardCondition(card) => {
+                    probabilities = self.card_holder_probabilities(card);
+                }
+                PartnerCondition::Player(partner) => probabilities[partner] = 1.0,
+                // Decided by the winner of the round, which no deal tells.
+                PartnerCondition::Round(_) => probabilities = vec![0.2; 5],
+                PartnerCondition::None => {}
+            }
+        }
+        self.probabilities = probabilities;
+    }
+
+    fn card_holder_probabilities(&self, card: Card) -> Vec<f64> {
+        let knowledge = &self.knowledge;
+        let seat = knowledge.seat;
+        let mut probabilities = vec![0.0; 5];
+        if knowledge.hand.contains(&card) {
+            probabilities[seat] = 1.0;
+            return probabilities;
+        }
+        if knowledge
+            .discarded
+            .as_ref()
+            .is_some_and(|d| d.contains(&card))
+        {
+            return probabilities;
+        }
+
+        let mut capacities = [0; 6];
+        for player in (0..5).filter(|&p| p != seat) {
+            capacities[player] = knowledge.hand_sizes[player];
+        }
+        if knowledge.discarded.is_none() {
+            capacities[DISCARDS] = 3;
+        }
+        let mut classes = vec![];
+        for class in CardClass::ALL {
+            let count = Card::deck()
+                .into_iter()
+                .filter(|&c| CardClass::of(c) == class && self.is_unknown(c))
+                .count();
+            let allowed = (0..6)
+                .filter(|&h| capacities[h] > 0 && self.may_hold(h, class))
+                .fold(0, |acc, h| acc | 1 << h);
+            classes.push((count, allowed));
+        }
+
+        let target = CardClass::ALL
+            .iter()
+            .position(|&c| c == CardClass::of(card))
+            .unwrap();
+        let mut weights = [0.0; 6];
+        for (holder, weight) in weights.iter_mut().enumerate() {
+            if classes[target].1 & 1 << holder == 0 {
+                continue;
+            }
+            let mut classes = classes.clone();
+            let mut capacities = capacities;
+            classes[target].0 -= 1;
+            capacities[holder] -= 1;
+            *weight = count_deals(&merge(&classes), capacities, &mut HashMap::new());
+            if holder == self.declarer || holder == DISCARDS {
+                *weight *= self.declarer_weight;
+            }
+        }
+
+        let total = weights.iter().sum::<f64>();
+        if total > 0.0 {
+            for (p, probability) in probabilities.iter_mut().enumerate() {
+                *probability = weights[p] / total;
+            }
+        }
+        probabilities
+    }
+
+    fn is_unknown(&self, card: Card) -> bool {
+        let knowledge = &self.knowledge;
+        !knowledge.played.contains_key(&card)
+            && !knowledge.hand.contains(&card)
+            && !knowledge
+                .discarded
+                .as_ref()
+                .is_some_and(|d| d.contains(&card))
+    }
+
+    fn may_hold(&self, holder: usize, class: CardClass) -> bool {
+        holder == DISCARDS
+            || match class {
+                CardClass::Suit(s) => !self.knowledge.voids[holder].contains(&s),
+                CardClass::Joker => !self.knowledge.without_joker[holder],
+            }
+    }
+}
+
+/// Cards that are interchangeable as far as the known constraints go.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum CardClass {
+    Suit(Suit),
+    Joker,
+}
+
+impl CardClass {
+    const ALL: [CardClass; 5] = [
+        CardClass::Suit(Suit::H),
+        CardClass::Suit(Suit::D),
+        CardClass::Suit(Suit::C),
+        CardClass::Suit(Suit::S),
+        CardClass::Joker,
+    ];
+
+    fn of(card: Card) -> CardClass {
+        match card {
+            Card::Shaped(s, _) => CardClass::Suit(s),
+            Card::Joker => CardClass::Joker,
+        }
+    }
+}
+
+/// The classes with the same holders as one, since only their total count
+/// matters to the number of deals. Early in the hand, before any void shows,
+/// this leaves a single class.
+fn merge(classes: &[(usize, u8)]) -> Vec<(usize, u8)> {
+    let mut merged: Vec<(usize, u8)> = vec![];
+    for &(count, allowed) in classes.iter().filter(|&&(count, _)| count > 0) {
+        match merged.iter_mut().find(|(_, a)| *a == allowed) {
+            Some((total, _)) => *total += count,
+            None => merged.push((count, allowed)),
+        }
+    }
+    merged
+}
+
+/// Number of ways to deal the distinct cards of each `(count, allowed)` class
+/// to holders with exactly the given capacities.
+fn count_deals(
+    classes: &[(usize, u8)],
+    capacities: [usize; 6],
+    memo: &mut HashMap<(usize, [usize; 6]), f64>,
+) -> f64 {
+    let Some(&(count, allowed)) = classes.first() else {
+        return if capacities.iter().all(|&c| c == 0) {
+            1.0
+        } else {
+            0.0
+        };
+    };
+    let key = (classes.len(), capacities);
+    if let Some(&deals) = memo.get(&key) {
+        return deals;
+    }
+
+    let holders = (0..6)
+        .filter(|&h| allowed & 1 << h != 0)
+        .collect::<Vec<_>>();
+    let mut deals = 0.0;
+    let mut split = vec![0; holders.len()];
+    distribute(count, 0, &holders, &capacities, &mut split, &mut |split| {
+        let mut rest = capacities;
+        let mut ways = factorial(count);
+        for (&h, &n) in holders.iter().zip(split.iter()) {
+            rest[h] -= n;
+            ways /= factorial(n);
+        }
+        deals += ways * count_deals(&classes[1..], rest, memo);
+    });
+    memo.insert(key, deals);
+    deals
+}
+
+fn distribute(
+    remaining: usize,
+    index: usize,
+    holders: &[usize],
+    capacities: &[usize; 6],
+    split: &mut Vec<usize>,
+    visit: &mut dyn FnMut(&[usize]),
+) {
+    if index == holders.len() {
+        if remaining == 0 {
+            visit(split);
+        }
+        return;
+    }
+    for n in 0..=remaining.min(capacities[holders[index]]) {
+        split[index] = n;
+        distribute(remaining - n, index + 1, holders, capacities, split, visit);
+    }
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
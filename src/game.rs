@@ -0,0 +1,193 @@
+use crate::bidding_phase::PledgePhase;
+use crate::claim::check_claim;
+use crate::clock::{default_action, default_bid, default_plan, Clocks, TimeControl};
+use crate::common::PlayerIndex;
+use crate::event::Event;
+use crate::extra_phase::{ExtraPhase, Surrender};
+use crate::play_phase::{EarlyEnd, PlayPhase};
+use crate::player::Player;
+use crate::record::GameRecord;
+use crate::rules::RuleSet;
+
+/// How a dealt hand ended.
+#[derive(Clone, Debug)]
+pub enum HandEnd {
+    /// Every player passed during the pledge.
+    Cancelled,
+    /// The declarer gave up before play.
+    Surrendered(Surrender),
+    Played(PlayPhase),
+}
+
+impl HandEnd {
+    pub fn played(self) -> Option<PlayPhase> {
+        match self {
+            HandEnd::Played(game) => Some(game),
+            _ => None,
+        }
+    }
+
+    /// Chips won or paid by each player, nothing for a cancelled hand.
+    pub fn payouts(&self, rules: &RuleSet) -> Vec<i32> {
+        match self {
+            HandEnd::Cancelled => vec![0; 5],
+            HandEnd::Surrendered(surrender) => rules.settle_surrender(surrender).payouts,
+            HandEnd::Played(game) => rules.settle(game).payouts,
+        }
+    }
+
+    /// The event announcing the result, nothing for a cancelled hand.
+    pub fn result(&self, rules: &RuleSet) -> Option<Event> {
+        match self {
+            HandEnd::Cancelled => None,
+            HandEnd::Surrendered(surrender) => {
+                Some(Event::surrender_result(&rules.settle_surrender(surrender)))
+            }
+            HandEnd::Played(game) => Some(Event::result(game, &rules.settle(game))),
+        }
+    }
+}
+
+/// Plays a dealt hand to the end without any output, and tells how it
+/// ended.
+pub fn play_game(players: &[Box<dyn Player>], game: PledgePhase) -> HandEnd {
+    play_timed_game(players, game, TimeControl::default()).0
+}
+
+/// Plays a dealt hand like `play_game`, holding the players to a time
+/// control. Also returns the record of the hand, timeouts included.
+pub fn play_timed_game(
+    players: &[Box<dyn Player>],
+    game: PledgePhase,
+    control: TimeControl,
+) -> (HandEnd, GameRecord) {
+    play_hand(players, game, control, EarlyEnd::Never)
+}
+
+/// Plays a dealt hand like `play_timed_game`, stopping it as soon as
+/// `early_end` allows.
+pub fn play_hand(
+    players: &[Box<dyn Player + '_>],
+    game: PledgePhase,
+    control: TimeControl,
+    early_end: EarlyEnd,
+) -> (HandEnd, GameRecord) {
+    play_hand_with_events(players, game, control, early_end, |_, _| {})
+}
+
+/// Plays a dealt hand like `play_hand`, passing each public event to
+/// `on_event` as it happens, along with the record so far. The result is
+/// left to the caller; see `HandEnd::result`.
+pub fn play_hand_with_events(
+    players: &[Box<dyn Player + '_>],
+    mut game: PledgePhase,
+    control: TimeControl,
+    early_end: EarlyEnd,
+    mut on_event: impl FnMut(&Event, &GameRecord),
+) -> (HandEnd, GameRecord) {
+    let mut clocks = Clocks::new(control);
+    let mut record = GameRecord::new(&game);
+    record.early_end = early_end;
+    for player in players {
+        player.new_hand();
+    }
+    while !game.pledge_done() {
+        let player = game.turn_player();
+        let state = game.bidding_state(player);
+        let contract = clocks
+            .timed(player, &*players[player], |p| p.bidding(&state))
+            .unwrap_or_else(|| {
+                timeout(&mut record, player, &mut on_event);
+                default_bid()
+            });
+        game.player_bids(player, contract);
+        record.bids.push((player, contract));
+        on_event(&Event::Bid { player, contract }, &record);
+    }
+    if game.cancelled() {
+        on_event(&Event::Cancelled, &record);
+        return (HandEnd::Cancelled, record);
+    }
+
+    let mut game = ExtraPhase::from_pledge(game);
+    let declarer = game.declarer();
+    let state = game.extra_state();
+    let surrender = clocks.timed(declarer, &*players[declarer], |p| p.surrender(&state));
+    if surrender == Some(true) {
+        record.surrendered = true;
+        on_event(&Event::Surrender { declarer }, &record);
+        return (HandEnd::Surrendered(game.surrender()), record);
+    }
+    // Time spent on whether to surrender counts against the plan.
+    let (contract, partner_condition, discards) = surrender
+        .and_then(|_| {
+            clocks.timed(declarer, &*players[declarer], |p| {
+                p.declare_plan(state.clone())
+            })
+        })
+        .unwrap_or_else(|| {
+            timeout(&mut record, declarer, &mut on_event);
+            default_plan(&state)
+        });
+    record.plan = Some((contract, partner_condition, discards.clone()));
+    let plan = Event::Plan {
+        declarer,
+        contract,
+        partner_condition,
+    };
+    on_event(&plan, &record);
+    let mut game = game.submit_plan(contract, partner_condition, discards);
+    game.set_early_end(early_end);
+
+    while let Some(player) = game.turn_player() {
+        let state = game.play_state(player);
+        let claim = clocks.timed(player, &*players[player], |p| p.claim(&state));
+        // The plays settling an accepted claim, or the move made.
+        let mut line = vec![];
+        if let Some(Some(claim)) = claim {
+            let settled = check_claim(&game, player, claim);
+            let accepted = settled.is_ok();
+            record.claim(player, claim, accepted);
+            let event = Event::Claim {
+                player,
+                claim,
+                accepted,
+            };
+            on_event(&event, &record);
+            line = settled.unwrap_or_default();
+        }
+        if line.is_empty() {
+            // Time spent on the claim counts against the move.
+            let action = claim
+                .and_then(|_| {
+                    clocks.timed(player, &*players[player], |p| p.play_action(state.clone()))
+                })
+                .unwrap_or_else(|| {
+                    timeout(&mut record, player, &mut on_event);
+                    default_action(&state)
+                });
+            line.push((player, action));
+        }
+        for (player, action) in line {
+            let before = game.clone();
+            game.player_acts(player, action);
+            record.actions.push((player, action));
+            for event in Event::after_action(&before, &game, player, action) {
+                on_event(&event, &record);
+            }
+            for (i, player) in players.iter().enumerate() {
+                player.observe(&game.play_state(i));
+            }
+        }
+    }
+    (HandEnd::Played(game), record)
+}
+
+fn timeout(
+    record: &mut GameRecord,
+    player: PlayerIndex,
+    on_event: &mut impl FnMut(&Event, &GameRecord),
+) {
+    record.timeout(player);
+    on_event(&Event::Timeout { player }, record);
+}
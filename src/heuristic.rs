@@ -0,0 +1,366 @@
+//! A rule-based bot driven by a handful of weights, which `evolve` tunes.
+//!
+//! The bot values its hand card by card for each trump, bids up to the
+//! count that value is worth, calls the strongest card it lacks as its
+//! friend and discards its weakest cards, unless the hand is too weak for
+//! the contract and it gives up instead. In play it takes a round as
+//! cheaply as it can when an opponent leads it, and otherwise gives up
+//! points to a partner and keeps them from an opponent. Ties are broken by
+//! card order, so the bot plays the same way in the same position.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::{Card, Suit};
+use crate::common::{Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::extra_phase::ExtraExposedState;
+use crate::play_phase::ExposedGameState;
+use crate::player::Player;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The names of the weights, in the order of `Weights::to_vec`, with the
+/// range each is kept to while tuning.
+pub const PARAMETERS: [(&str, f64, f64); 12] = [
+    ("trump_card", 0.0, 3.0),
+    ("trump_honour", 0.0, 3.0),
+    ("mighty", 0.0, 5.0),
+    ("joker", 0.0, 5.0),
+    ("side_ace", 0.0, 3.0),
+    ("bid_base", 0.0, 15.0),
+    ("bid_per_value", 0.0, 3.0),
+    ("no_trump_bonus", -5.0, 5.0),
+    ("solo_value", 5.0, 30.0),
+    ("surrender_value", 0.0, 15.0),
+    ("point_weight", 0.0, 50.0),
+    ("lead_threshold", 0.0, 200.0),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Weights {
+    /// Hand value of each trump.
+    pub trump_card: f64,
+    /// Extra value of the trump ace, king and queen.
+    pub trump_honour: f64,
+    pub mighty: f64,
+    pub joker: f64,
+    /// Value of an ace of a side suit.
+    pub side_ace: f64,
+    /// The effective count bid on a hand of no value.
+    pub bid_base: f64,
+    /// Effective count bid for each point of hand value.
+    pub bid_per_value: f64,
+    /// Added to the value of a hand for no trump.
+    pub no_trump_bonus: f64,
+    /// The declarer goes without a friend from this hand value up.
+    pub solo_value: f64,
+    /// The declarer gives up a hand worth less than this with its trump.
+    pub surrender_value: f64,
+    /// How many round values a point card is worth, when giving one up.
+    pub point_weight: f64,
+    /// The bot leads its strongest card when that takes a round with at
+    /// least this value, and its weakest otherwise.
+    pub lead_threshold: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            trump_card: 1.0,
+            trump_honour: 0.5,
+            mighty: 2.0,
+            joker: 2.0,
+            side_ace: 1.0,
+            bid_base: 5.0,
+            bid_per_value: 1.0,
+            no_trump_bonus: 0.0,
+            solo_value: 16.0,
+            surrender_value: 0.0,
+            point_weight: 10.0,
+            lead_threshold: 80.0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn to_vec(&self) -> Vec<f64> {
+        vec![
+            self.trump_card,
+            self.trump_honour,
+            self.mighty,
+            self.joker,
+            self.side_ace,
+            self.bid_base,
+            self.bid_per_value,
+            self.no_trump_bonus,
+            self.solo_value,
+            self.surrender_value,
+            self.point_weight,
+            self.lead_threshold,
+        ]
+    }
+
+    pub fn from_vec(values: &[f64]) -> Weights {
+        assert_eq!(values.len(), PARAMETERS.len());
+        Weights {
+            trump_card: values[0],
+            trump_honour: values[1],
+            mighty: values[2],
+            joker: values[3],
+            side_ace: values[4],
+            bid_base: values[5],
+            bid_per_value: values[6],
+            no_trump_bonus: values[7],
+            solo_value: values[8],
+            surrender_value: values[9],
+            point_weight: values[10],
+            lead_threshold: values[11],
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Weights, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        text.parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// What `hand` is worth with `trump`.
+    pub fn hand_value(&self, hand: &HashSet<Card>, trump: Option<Suit>) -> f64 {
+        // Summed in card order, so that the value does not depend on the
+        // order of the hand.
+        let mut hand = hand.iter().copied().collect::<Vec<_>>();
+        hand.sort();
+        let cards = hand.into_iter().map(|card| match card {
+            Card::Joker => self.joker,
+            _ if card.is_mighty(trump) => self.mighty,
+            Card::Shaped(suit, rank) if Some(suit) == trump => {
+                self.trump_card + if rank >= 12 { self.trump_honour } else { 0.0 }
+            }
+            Card::Shaped(_, 14) => self.side_ace,
+            Card::Shaped(..) => 0.0,
+        });
+        let bonus = match trump {
+            Some(_) => 0.0,
+            None => self.no_trump_bonus,
+        };
+        cards.sum::<f64>() + bonus
+    }
+
+    /// The trump the hand is worth the most with, and that value.
+    fn best_trump(&self, hand: &HashSet<Card>) -> (Option<Suit>, f64) {
+        let trumps = Suit::ALL.into_iter().map(Some).chain([None]);
+        trumps
+            .map(|trump| (trump, self.hand_value(hand, trump)))
+            .fold((None, f64::NEG_INFINITY), |best, next| {
+                if next.1 > best.1 {
+                    next
+                } else {
+                    best
+                }
+            })
+    }
+}
+
+/// A line `name = value` for each weight. Blank lines and lines starting
+/// with `#` are skipped when reading, and weights left out keep their
+/// defaults.
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for ((name, _, _), value) in PARAMETERS.iter().zip(self.to_vec()) {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Weights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = Weights::default().to_vec();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("invalid line: {}", line);
+            let (name, value) = line.split_once('=').ok_or_else(invalid)?;
+            let i = PARAMETERS
+                .iter()
+                .position(|(n, _, _)| *n == name.trim())
+                .ok_or(format!("unknown weight: {}", name.trim()))?;
+            values[i] = value.trim().parse().map_err(|_| invalid())?;
+        }
+        Ok(Weights::from_vec(&values))
+    }
+}
+
+pub struct HeuristicPlayer {
+    pub weights: Weights,
+}
+
+impl HeuristicPlayer {
+    pub fn new(weights: Weights) -> Self {
+        HeuristicPlayer { weights }
+    }
+
+    /// How much the bot would rather keep `card`: its strength in a round
+    /// led in its own suit, and its points.
+    fn keep_value(&self, card: Card, trump: Option<Suit>) -> f64 {
+        let strength = match card {
+            Card::Joker => 100,
+            _ if card.is_mighty(trump) => 200,
+            Card::Shaped(suit, rank) if Some(suit) == trump => 70 + rank,
+            Card::Shaped(_, rank) => rank,
+        };
+        strength as f64 + self.weights.point_weight * card.score() as f64
+    }
+
+    /// Whether `player` is known to play on the bot's side.
+    fn partner(&self, state: &ExposedGameState, me: PlayerIndex, player: PlayerIndex) -> bool {
+        let friend = |p| p == state.declarer || Some(p) == state.partner_revealed;
+        let called = match state.partner_condition {
+            PartnerCondition::CardCondition(card) => state.hand.contains(&card),
+            PartnerCondition::Player(p) => p == me,
+            _ => false,
+        };
+        let no_friend = state.partner_condition == PartnerCondition::None;
+        if friend(me) || called {
+            friend(player)
+        } else {
+            !friend(player) && (state.partner_revealed.is_some() || no_friend)
+        }
+    }
+
+    fn lead(&self, state: &ExposedGameState, legal: Vec<PlayAction>) -> PlayAction {
+        let trump = state.contract.suit;
+        let lead_value = |action: &PlayAction| match *action {
+            PlayAction::Hand(Card::Shaped(suit, rank)) if Some(suit) != trump => {
+                let card = Card::Shaped(suit, rank);
+                if card.is_mighty(trump) {
+                    200
+                } else {
+                    30 + rank
+                }
+            }
+            PlayAction::Hand(card) | PlayAction::JokerCall(card) => state.card_value(card),
+            PlayAction::JokerStart(_) => state.card_value(Card::Joker),
+        };
+        // The joker leads in the trump suit, or in spades without one.
+        let lead_suit = trump.unwrap_or(Suit::S);
+        let actions = legal
+            .into_iter()
+            .filter(|a| match a {
+                PlayAction::JokerStart(suit) => *suit == lead_suit,
+                PlayAction::JokerCall(_) => false,
+                PlayAction::Hand(_) => true,
+            })
+            .collect::<Vec<_>>();
+        let strongest = actions.iter().max_by_key(|a| (lead_value(a), *a)).unwrap();
+        if lead_value(strongest) as f64 >= self.weights.lead_threshold {
+            return *strongest;
+        }
+        let card = |a: &PlayAction| match a {
+            PlayAction::Hand(card) => *card,
+            _ => Card::Joker,
+        };
+        *actions
+            .iter()
+            .min_by(|a, b| {
+                let (a, b) = (card(a), card(b));
+                self.keep_value(a, trump)
+                    .total_cmp(&self.keep_value(b, trump))
+                    .then(a.cmp(&b))
+            })
+            .unwrap()
+    }
+}
+
+impl Player for HeuristicPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        let (suit, value) = self.weights.best_trump(&state.hand);
+        let target = self.weights.bid_base + self.weights.bid_per_value * value;
+        let effective = state.min_effective_count + 1;
+        let count = match suit {
+            Some(_) => effective,
+            None => effective - 1,
+        };
+        (effective as f64 <= target && count <= 20).then_some(Contract { suit, count })
+    }
+
+    fn surrender(&self, state: &ExtraExposedState) -> bool {
+        self.weights.hand_value(&state.hand, state.contract.suit) < self.weights.surrender_value
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        let trump = state.contract.suit;
+        let friend = if self.weights.hand_value(&state.hand, trump) >= self.weights.solo_value {
+            PartnerCondition::None
+        } else {
+            let mut wanted = vec![Card::mighty(trump), Card::Joker];
+            if let Some(trump) = trump {
+                wanted.extend((12..=14).rev().map(|rank| Card::Shaped(trump, rank)));
+            }
+            wanted
+                .into_iter()
+                .find(|c| !state.hand.contains(c))
+                .map_or(PartnerCondition::None, PartnerCondition::CardCondition)
+        };
+        let mut cards = state.hand.iter().copied().collect::<Vec<_>>();
+        cards.sort_by(|&a, &b| {
+            self.keep_value(a, trump)
+                .total_cmp(&self.keep_value(b, trump))
+                .then(a.cmp(&b))
+        });
+        (state.contract, friend, cards.into_iter().take(3).collect())
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        let mut legal = state.legal_actions();
+        legal.sort();
+        let Some(leader) = state.round_leader() else {
+            return self.lead(&state, legal);
+        };
+        let me = whose_turn(&state);
+        let best = state.card_value(state.submitted[leader].unwrap());
+        let cards = legal.iter().filter_map(|a| match a {
+            PlayAction::Hand(card) => Some(*card),
+            _ => None,
+        });
+        let points = |card: Card| self.weights.point_weight * card.score() as f64;
+        let card = if self.partner(&state, me, leader) {
+            // Give the partner points, from the weakest cards.
+            cards.min_by(|&a, &b| {
+                let cost = |c: Card| state.card_value(c) as f64 - points(c);
+                cost(a).total_cmp(&cost(b)).then(a.cmp(&b))
+            })
+        } else {
+            let taking = cards
+                .clone()
+                .filter(|&c| state.card_value(c) > best)
+                .min_by_key(|&c| (state.card_value(c), c));
+            taking.or_else(|| {
+                cards.min_by(|&a, &b| {
+                    let cost = |c: Card| state.card_value(c) as f64 + points(c);
+                    cost(a).total_cmp(&cost(b)).then(a.cmp(&b))
+                })
+            })
+        };
+        card.map_or(legal[0], PlayAction::Hand)
+    }
+}
+
+/// The seat to play next in the current round.
+fn whose_turn(state: &ExposedGameState) -> PlayerIndex {
+    let played = state.submitted.iter().filter(|c| c.is_some()).count();
+    (state.round_starter + played) % 5
+}
@@ -0,0 +1,158 @@
+use crate::card::{Card, Suit};
+use crate::common::{Hand, PlayerIndex};
+use crate::play_phase::ExposedGameState;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CardLocation {
+    Hand(PlayerIndex),
+    Played(PlayerIndex),
+    Discarded,
+    Unknown,
+}
+
+/// What a single seat can tell about the whereabouts of every card, from its
+/// own hand, the tricks played so far and, for the declarer, the discards.
+#[derive(Clone, Debug)]
+pub struct Knowledge {
+    pub seat: PlayerIndex,
+    pub hand: Hand,
+    pub discarded: Option<HashSet<Card>>,
+    pub played: HashMap<Card, PlayerIndex>,
+    pub voids: Vec<HashSet<Suit>>,
+    /// Players known not to hold the joker, from declining a joker call.
+    pub without_joker: Vec<bool>,
+    pub hand_sizes: Vec<usize>,
+    trump: Option<Suit>,
+    rounds_seen: usize,
+}
+
+impl Knowledge {
+    pub fn new(seat: PlayerIndex, state: &ExposedGameState) -> Self {
+        let mut knowledge = Knowledge {
+            seat,
+            hand: state.hand.clone(),
+            discarded: state.discarded.clone(),
+            played: HashMap::new(),
+            voids: vec![HashSet::new(); 5],
+            without_joker: vec![false; 5],
+            hand_sizes: vec![10; 5],
+            trump: state.contract.suit,
+            rounds_seen: 0,
+        };
+        knowledge.update(state);
+        knowledge
+    }
+
+    /// Catches up with `state`, looking only at the tricks not seen before.
+    pub fn update(&mut self, state: &ExposedGameState) {
+        self.hand = state.hand.clone();
+        for result in &state.round_results[self.rounds_seen..] {
+            let submitted = result
+                .submitted
+                .iter()
+                .map(|&c| Some(c))
+                .collect::<Vec<_>>();
+            self.observe_trick(
+                result.round_starter,
+                &submitted,
+                result.round_suit,
+                result.joker_called,
+            );
+        }
+        self.rounds_seen = state.round_results.len();
+        self.observe_trick(
+            state.round_starter,
+            &state.submitted,
+            state.round_suit,
+            state.joker_called,
+        );
+
+        let rounds = state.round_results.len();
+        for (player, size) in self.hand_sizes.iter_mut().enumerate() {
+            *size = 10 - rounds - state.submitted[player].is_some() as usize;
+        }
+    }
+
+    fn observe_trick(
+        &mut self,
+        round_starter: PlayerIndex,
+        submitted: &[Option<Card>],
+        round_suit: Option<Suit>,
+        joker_called: bool,
+    ) {
+        for offset in 0..5 {
+            let player = (round_starter + offset) % 5;
+            let Some(card) = submitted[player] else {
+                break;
+            };
+            self.played.insert(card, player);
+            if offset == 0 {
+                continue;
+            }
+
+            if joker_called && card != Card::Joker {
+                self.without_joker[player] = true;
+            }
+            if let (Some(suit), Card::Shaped(s, _)) = (round_suit, card) {
+                if s != suit && !card.is_mighty(self.trump) {
+                    self.voids[player].insert(suit);
+                }
+            }
+        }
+    }
+
+    pub fn location(&self, card: Card) -> CardLocation {
+        if let Some(&player) = self.played.get(&card) {
+            return CardLocation::Played(player);
+        }
+        if self.hand.contains(&card) {
+            return CardLocation::Hand(self.seat);
+        }
+        if self.discarded.as_ref().is_some_and(|d| d.contains(&card)) {
+            return CardLocation::Discarded;
+        }
+        match self.possible_holders(card)[..] {
+            [player] if self.discarded.is_some() => CardLocation::Hand(player),
+            _ => CardLocation::Unknown,
+        }
+    }
+
+    /// Whether `player` may still be holding `card`, as far as this seat knows.
+    pub fn can_hold(&self, player: PlayerIndex, card: Card) -> bool {
+        if self.played.contains_key(&card) {
+            return false;
+        }
+        if player == self.seat {
+            return self.hand.contains(&card);
+        }
+        if self.hand.contains(&card) || self.discarded.as_ref().is_some_and(|d| d.contains(&card)) {
+            return false;
+        }
+        match card {
+            Card::Joker => !self.without_joker[player],
+            Card::Shaped(s, _) => !self.voids[player].contains(&s),
+        }
+    }
+
+    pub fn possible_holders(&self, card: Card) -> Vec<PlayerIndex> {
+        (0..5).filter(|&p| self.can_hold(p, card)).collect()
+    }
+
+    /// Cards whose location this seat cannot pin down: in another player's
+    /// hand or, unless this seat is the declarer, among the discards.
+    pub fn unknown(&self) -> HashSet<Card> {
+        Card::deck()
+            .into_iter()
+            .filter(|&c| self.location(c) == CardLocation::Unknown)
+            .collect()
+    }
+
+    pub fn joker(&self) -> CardLocation {
+        self.location(Card::Joker)
+    }
+
+    pub fn mighty(&self) -> CardLocation {
+        self.location(Card::mighty(self.trump))
+    }
+}
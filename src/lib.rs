@@ -0,0 +1,41 @@
+pub mod analysis;
+pub mod bidding_phase;
+pub mod card;
+pub mod cfr;
+pub mod claim;
+pub mod client;
+pub mod clock;
+pub mod common;
+pub mod console;
+pub mod dataset;
+pub mod duplicate;
+pub mod encoding;
+pub mod env;
+pub mod estimate;
+pub mod event;
+pub mod evolve;
+pub mod external;
+pub mod extra_phase;
+pub mod friend;
+pub mod game;
+pub mod heuristic;
+pub mod inference;
+pub mod json;
+pub mod lobby;
+pub mod mlp;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod play_phase;
+pub mod player;
+pub mod protocol;
+#[cfg(feature = "python")]
+mod python;
+pub mod record;
+pub mod rules;
+pub mod server;
+pub mod session;
+pub mod simulate;
+pub mod solver;
+pub mod tournament;
+pub mod train;
+pub mod web;
@@ -0,0 +1,520 @@
+//! Many tables in one server. Clients that join land in the lobby, where
+//! they can list, create and join rooms. Each room has its own rules and
+//! plays its matches on a thread of its own; seats nobody took are filled
+//! with bots when a match starts.
+
+use crate::bidding_phase::PledgePhase;
+use crate::clock::TimeControl;
+use crate::common::PlayerIndex;
+use crate::json::Json;
+use crate::player::{create_room_bot, ROOM_BOTS};
+use crate::protocol::{optional, rules_from_json, rules_to_json};
+use crate::protocol::{ClientMessage, ServerMessage};
+use crate::rules::RuleSet;
+use crate::server::{host_game, Connection, Seat};
+use crate::session::{Remote, Sessions, Takeover};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// What the lobby shows of a room.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RoomInfo {
+    pub id: usize,
+    pub name: String,
+    pub rules: RuleSet,
+    /// Hands in a match.
+    pub hands: usize,
+    /// The bot that fills empty seats.
+    pub bots: String,
+    /// The client in each seat, by name.
+    pub seats: Vec<Option<String>>,
+    pub playing: bool,
+    /// Hands played in the current or last match, and the total payouts.
+    pub hands_played: usize,
+    pub scores: Vec<i32>,
+}
+
+impl RoomInfo {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("id", self.id.into()),
+            ("name", self.name.as_str().into()),
+            ("rules", rules_to_json(&self.rules)),
+            ("hands", self.hands.into()),
+            ("bots", self.bots.as_str().into()),
+            ("seats", self.seats.clone().into()),
+            ("playing", self.playing.into()),
+            ("hands_played", self.hands_played.into()),
+            ("scores", self.scores.clone().into()),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<RoomInfo, String> {
+        Ok(RoomInfo {
+            id: json.field("id")?.as_u64()? as usize,
+            name: json.field("name")?.as_str()?.to_string(),
+            rules: rules_from_json(json.field("rules")?)?,
+            hands: json.field("hands")?.as_u64()? as usize,
+            bots: json.field("bots")?.as_str()?.to_string(),
+            seats: json
+                .field("seats")?
+                .as_array()?
+                .iter()
+                .map(|s| optional(s, |s| s.as_str().map(str::to_string)))
+                .collect::<Result<_, _>>()?,
+            playing: json.field("playing")?.as_bool()?,
+            hands_played: json.field("hands_played")?.as_u64()? as usize,
+            scores: json
+                .field("scores")?
+                .as_array()?
+                .iter()
+                .map(|s| s.as_i64().map(|s| s as i32))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn free_seat(&self, seat: Option<PlayerIndex>) -> Result<PlayerIndex, String> {
+        match seat {
+            Some(seat) if seat >= 5 => Err(format!("invalid seat: {}", seat)),
+            Some(seat) if self.seats[seat].is_some() => Err(format!("seat {} is taken", seat)),
+            Some(seat) => Ok(seat),
+            None => (0..5)
+                .find(|&s| self.seats[s].is_none())
+                .ok_or("the room is full".to_string()),
+        }
+    }
+}
+
+type Arrival = (Connection, Option<PlayerIndex>);
+
+struct Room {
+    info: RoomInfo,
+    arrivals: Sender<Arrival>,
+}
+
+#[derive(Default)]
+struct Rooms {
+    next_id: usize,
+    rooms: BTreeMap<usize, Room>,
+}
+
+impl Rooms {
+    fn list(&self) -> Vec<RoomInfo> {
+        self.rooms.values().map(|r| r.info.clone()).collect()
+    }
+}
+
+/// What every thread of a lobby shares.
+#[derive(Clone)]
+struct Shared {
+    rooms: Arc<Mutex<Rooms>>,
+    sessions: Sessions,
+    takeover: Takeover,
+    time_control: TimeControl,
+}
+
+/// A server hosting any number of rooms.
+pub struct Lobby {
+    listener: TcpListener,
+    rooms: Arc<Mutex<Rooms>>,
+    sessions: Sessions,
+    pub takeover: Takeover,
+    /// The time control of every room.
+    pub time_control: TimeControl,
+}
+
+impl Lobby {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Lobby> {
+        Ok(Lobby {
+            listener: TcpListener::bind(addr)?,
+            rooms: Arc::default(),
+            sessions: Sessions::default(),
+            takeover: Takeover::default(),
+            time_control: TimeControl::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn rooms(&self) -> Vec<RoomInfo> {
+        self.rooms.lock().unwrap().list()
+    }
+
+    /// Takes clients into the lobby until the listener fails. A client
+    /// joining with the token of a seat goes straight back to it.
+    pub fn run(&self) -> io::Result<()> {
+        let shared = Shared {
+            rooms: self.rooms.clone(),
+            sessions: self.sessions.clone(),
+            takeover: self.takeover.clone(),
+            time_control: self.time_control,
+        };
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let Ok(Some(connection)) = Connection::accept(stream) else {
+                    return;
+                };
+                let connection = match connection.token.clone() {
+                    Some(token) => match shared.sessions.rejoin(&token, connection) {
+                        Ok(()) => return,
+                        Err(connection) => connection,
+                    },
+                    None => connection,
+                };
+                enter(connection, shared);
+            });
+        }
+    }
+}
+
+/// Serves a client in the lobby until it takes a seat or goes away.
+fn enter(mut connection: Connection, shared: Shared) {
+    let list = shared.rooms.lock().unwrap().list();
+    if connection.send(&ServerMessage::Rooms(list)).is_err() {
+        return;
+    }
+    loop {
+        let Ok(message) = connection.receive() else {
+            return;
+        };
+        let request = match message {
+            Ok(ClientMessage::ListRooms) => Ok(None),
+            Ok(ClientMessage::CreateRoom {
+                name,
+                rules,
+                hands,
+                bots,
+                seat,
+            }) => match open_room(&shared, name, rules, hands, bots, connection, seat) {
+                Ok(()) => return,
+                Err((rejected, e)) => {
+                    connection = rejected;
+                    Err(e)
+                }
+            },
+            Ok(ClientMessage::JoinRoom { room, seat }) => Ok(Some((room, seat))),
+            Ok(_) => Err("not in a room".to_string()),
+            Err(e) => Err(e),
+        };
+        let reply = match request {
+            Ok(Some((room, seat))) => match take_seat(&shared.rooms, connection, room, seat) {
+                Ok(()) => return,
+                Err((rejected, e)) => {
+                    connection = rejected;
+                    ServerMessage::Error(e)
+                }
+            },
+            Ok(None) => ServerMessage::Rooms(shared.rooms.lock().unwrap().list()),
+            Err(e) => ServerMessage::Error(e),
+        };
+        if connection.send(&reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// Opens a room with its first client, or gives the client back with the
+/// reason it could not.
+fn open_room(
+    shared: &Shared,
+    name: String,
+    rules: RuleSet,
+    hands: usize,
+    bots: String,
+    connection: Connection,
+    seat: Option<PlayerIndex>,
+) -> Result<(), (Connection, String)> {
+    if hands == 0 {
+        return Err((connection, "a match needs at least one hand".to_string()));
+    }
+    if !ROOM_BOTS.contains(&bots.as_str()) {
+        return Err((connection, format!("unknown room bot: {}", bots)));
+    }
+    if let Some(seat) = seat.filter(|&s| s >= 5) {
+        return Err((connection, format!("invalid seat: {}", seat)));
+    }
+    let mut locked = shared.rooms.lock().unwrap();
+    let id = locked.next_id;
+    locked.next_id += 1;
+    let info = RoomInfo {
+        id,
+        name,
+        rules,
+        hands,
+        bots,
+        seats: vec![None; 5],
+        playing: false,
+        hands_played: 0,
+        scores: vec![0; 5],
+    };
+    let (sender, arrivals) = mpsc::channel();
+    // The client is on its way in before the room can find itself empty.
+    sender.send((connection, seat)).unwrap();
+    locked.rooms.insert(
+        id,
+        Room {
+            info: info.clone(),
+            arrivals: sender,
+        },
+    );
+    let table = Table {
+        info,
+        shared: shared.clone(),
+        seats: (0..5).map(|_| None).collect(),
+        arrivals,
+    };
+    thread::spawn(move || table.run());
+    Ok(())
+}
+
+/// Hands the client over to a room, or gives it back with the reason.
+fn take_seat(
+    rooms: &Mutex<Rooms>,
+    connection: Connection,
+    room: usize,
+    seat: Option<PlayerIndex>,
+) -> Result<(), (Connection, String)> {
+    // The lock is held while sending, so that a room never closes with a
+    // client on its way in.
+    let locked = rooms.lock().unwrap();
+    let Some(room) = locked.rooms.get(&room) else {
+        return Err((connection, format!("no such room: {}", room)));
+    };
+    if room.info.playing {
+        return Err((connection, "the match has started".to_string()));
+    }
+    if let Err(e) = room.info.free_seat(seat) {
+        return Err((connection, e));
+    }
+    room.arrivals
+        .send((connection, seat))
+        .map_err(|e| (e.0 .0, "the room has closed".to_string()))
+}
+
+/// A room as seen from its own thread.
+struct Table {
+    info: RoomInfo,
+    shared: Shared,
+    seats: Vec<Option<Remote>>,
+    arrivals: Receiver<Arrival>,
+}
+
+impl Table {
+    fn run(mut self) {
+        loop {
+            let mut changed = false;
+            while let Ok((connection, seat)) = self.arrivals.try_recv() {
+                changed |= self.sit(connection, seat);
+            }
+            let mut start = false;
+            for seat in 0..5 {
+                let Some(remote) = &mut self.seats[seat] else {
+                    continue;
+                };
+                // Between matches there is no hand to catch up on.
+                if let Some(mut connection) = remote.returning() {
+                    let welcome = ServerMessage::Welcome {
+                        seat,
+                        token: remote.token.clone(),
+                    };
+                    let _ = connection.send(&welcome);
+                    remote.resume(connection);
+                    changed = true;
+                }
+                let Some(connection) = remote.connection() else {
+                    self.seats[seat] = None;
+                    changed = true;
+                    continue;
+                };
+                let reply = match connection.poll() {
+                    Ok(None) => continue,
+                    Ok(Some(Ok(ClientMessage::Start))) => {
+                        start = true;
+                        continue;
+                    }
+                    Ok(Some(Ok(ClientMessage::LeaveRoom))) => {
+                        if let Some(connection) = self.seats[seat].take().unwrap().leave() {
+                            self.to_lobby(connection);
+                        }
+                        changed = true;
+                        continue;
+                    }
+                    Ok(Some(Ok(_))) => "the match has not started".to_string(),
+                    Ok(Some(Err(e))) => e,
+                    Err(_) => {
+                        self.seats[seat] = None;
+                        changed = true;
+                        continue;
+                    }
+                };
+                let _ = connection.send(&ServerMessage::Error(reply));
+            }
+            if changed {
+                self.publish();
+            }
+            if self.seats.iter().all(Option::is_none) && self.close() {
+                return;
+            }
+            if start || self.seats.iter().all(Option::is_some) {
+                if let Err(e) = self.play_match() {
+                    self.abandon(e);
+                    return;
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Seats an arriving client, or sends it back to the lobby when its
+    /// seat was taken in the meantime.
+    fn sit(&mut self, mut connection: Connection, seat: Option<PlayerIndex>) -> bool {
+        self.info.seats = self.names();
+        match self.info.free_seat(seat) {
+            Ok(seat) => {
+                let mut remote =
+                    Remote::new(connection, &self.shared.sessions, &self.shared.takeover);
+                let welcome = ServerMessage::Welcome {
+                    seat,
+                    token: remote.token.clone(),
+                };
+                if remote.send(&welcome) {
+                    self.seats[seat] = Some(remote);
+                }
+                true
+            }
+            Err(e) => {
+                let _ = connection.send(&ServerMessage::Error(e));
+                self.to_lobby(connection);
+                false
+            }
+        }
+    }
+
+    fn to_lobby(&self, connection: Connection) {
+        let shared = self.shared.clone();
+        thread::spawn(move || enter(connection, shared));
+    }
+
+    fn names(&self) -> Vec<Option<String>> {
+        self.seats
+            .iter()
+            .map(|s| s.as_ref().map(|r| r.name.clone()))
+            .collect()
+    }
+
+    /// Shows the room as it is now in the lobby, and returns the message
+    /// telling its players.
+    fn update(&mut self) -> ServerMessage {
+        let mut locked = self.shared.rooms.lock().unwrap();
+        locked.rooms.get_mut(&self.info.id).unwrap().info = self.info.clone();
+        ServerMessage::Room(self.info.clone())
+    }
+
+    fn publish(&mut self) {
+        self.info.seats = self.names();
+        let message = self.update();
+        for remote in self.seats.iter_mut().flatten() {
+            remote.send(&message);
+        }
+    }
+
+    /// Removes the room from the lobby, unless a client turned up at the
+    /// last moment.
+    fn close(&mut self) -> bool {
+        let mut locked = self.shared.rooms.lock().unwrap();
+        match self.arrivals.try_recv() {
+            Ok((connection, seat)) => {
+                drop(locked);
+                self.sit(connection, seat);
+                self.publish();
+                false
+            }
+            Err(_) => {
+                locked.rooms.remove(&self.info.id);
+                true
+            }
+        }
+    }
+
+    /// Closes the room over `error`, sending its clients back to the lobby.
+    fn abandon(mut self, error: String) {
+        self.shared
+            .rooms
+            .lock()
+            .unwrap()
+            .rooms
+            .remove(&self.info.id);
+        let mut connections = self
+            .seats
+            .iter_mut()
+            .filter_map(|seat| seat.take()?.leave())
+            .collect::<Vec<_>>();
+        // Clients let in before the room left the lobby.
+        connections.extend(self.arrivals.try_iter().map(|(connection, _)| connection));
+        let message = ServerMessage::Error(format!("the room has closed: {}", error));
+        for mut connection in connections {
+            let _ = connection.send(&message);
+            self.to_lobby(connection);
+        }
+    }
+
+    /// Plays a match of back-to-back hands, the lead moving around the
+    /// table. Clients that are away when it ends lose their seats. Fails,
+    /// before any hand, when a bot cannot be made.
+    fn play_match(&mut self) -> Result<(), String> {
+        let empty = self.seats.iter().filter(|seat| seat.is_none()).count();
+        let mut bots = (0..empty)
+            .map(|_| create_room_bot(&self.info.bots))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut table = self
+            .seats
+            .iter_mut()
+            .map(|seat| match seat.take() {
+                Some(remote) => Seat::Remote(remote),
+                None => Seat::Bot(bots.pop().unwrap()),
+            })
+            .collect::<Vec<_>>();
+        self.info.playing = true;
+        self.info.hands_played = 0;
+        self.info.scores = vec![0; 5];
+        let mut message = self.update();
+        for hand in 0..self.info.hands {
+            for seat in table.iter_mut() {
+                if let Seat::Remote(remote) = seat {
+                    remote.send(&message);
+                }
+            }
+            let game = PledgePhase::new(hand % 5, self.info.rules.min_pledge);
+            match host_game(&self.info.rules, self.shared.time_control, &mut table, game) {
+                Ok(end) => {
+                    let payouts = end.payouts(&self.info.rules);
+                    for (score, payout) in self.info.scores.iter_mut().zip(payouts) {
+                        *score += payout;
+                    }
+                    self.info.hands_played += 1;
+                    message = self.update();
+                }
+                Err(_) => break,
+            }
+        }
+        self.info.playing = false;
+        for (i, seat) in table.into_iter().enumerate() {
+            if let Seat::Remote(mut remote) = seat {
+                if remote.connection().is_some() {
+                    self.seats[i] = Some(remote);
+                }
+            }
+        }
+        self.publish();
+        Ok(())
+    }
+}
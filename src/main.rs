@@ -1,64 +1,768 @@
-mod bidding_phase;
-mod card;
-mod common;
-mod extra_phase;
-mod play_phase;
-mod player;
+use mighty_rs::analysis::{mark_mistakes, Advisor};
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::cfr::Cfr;
+use mighty_rs::client::Client;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::common::Hand;
+use mighty_rs::console::{HumanPlayer, Narrator, OutputStyle};
+use mighty_rs::dataset::{DatasetFormat, DatasetWriter, GameData};
+use mighty_rs::estimate::Estimator;
+use mighty_rs::event::Event;
+use mighty_rs::evolve::Evolution;
+use mighty_rs::external::run_bot;
+use mighty_rs::game::play_hand_with_events;
+use mighty_rs::lobby::Lobby;
+use mighty_rs::play_phase::EarlyEnd;
+use mighty_rs::player::{check_player, create_player, Player};
+use mighty_rs::record::GameRecord;
+use mighty_rs::rules::RuleSet;
+use mighty_rs::server::{Seat, Server};
+use mighty_rs::session::Takeover;
+use mighty_rs::simulate::{default_threads, write_summaries, OutputFormat, Simulation};
+use mighty_rs::solver::Solver;
+use mighty_rs::train::{TrainConfig, Trainer};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process::exit;
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::bidding_phase::PledgePhase;
-use crate::extra_phase::ExtraPhase;
-use crate::player::{Player, RandomPlayer};
+const USAGE: &str = "usage: mighty-rs <command> [options]
+
+commands:
+  play      [--seats human,random,...] [--seed S] [--record PATH]
+            human seats may answer hint for a suggested bid or card,
+            claim points:N or claim tricks:N for the rest of the hand, and
+            surrender in place of a plan
+  simulate  [--games N] [--seed S] [--lineup a,b,c,d,e] [--threads N]
+            [--format csv|jsonl] [--output PATH] [--early-end]
+            stop each hand once its contract is out of reach or no point card
+            is left to take
+  export    DIR [RECORD...] [--format jsonl|columns] [--shard-games N]
+            write every decision of the records, or of simulated games
+            taking the simulate options, as training data
+  train     [--iterations N] [--batch N] [--hidden 64,64] [--learning-rate F]
+            [--seed S] [--eval-every N] [--eval-hands N] [--opponent BOT]
+            [--checkpoint PATH] [--resume PATH]
+            train a network for the play phase by self-play
+  cfr       OUTPUT [--iterations N] [--seed S] [--playout BOT]
+            solve an abstraction of bidding and save the strategy table
+  evolve    OUTPUT [--population N] [--generations N] [--games N] [--seed S]
+            [--mutation F] [--elite N] [--opponent BOT] [--threads N]
+            tune the weights of the heuristic bot and save the best
+  estimate  CARD... [--samples N] [--depth N] [--seed S] [--playout BOT]
+            odds of making each contract with a ten-card hand
+  replay    FILE [--step]
+  analyze   FILE [--at N]
+  review    FILE [--seats 0,1,...] [--worlds N] [--depth N] [--seed S]
+            [--output PATH]
+            compare each move with the engine's choice from the mover's view
+            and mark inaccuracies and blunders in the record
+  serve     [--port P] [--hands N] [--grace SECONDS] [--takeover BOT]
+  lobby     [--port P] [--grace SECONDS] [--takeover BOT]
+  join      HOST:PORT [--name NAME] [--player human|BOT]
+            [--room ID | --create NAME [--hands N] [--bots BOT]]
+            [--seat S] [--start] [--token TOKEN]
+  bot       [--player BOT]   answer the bot protocol on stdin and stdout
+
+bots: random, mlp:PATH for a network saved by train,
+  cfr:PATH for a strategy table saved by cfr,
+  heuristic, or heuristic:PATH with weights saved by evolve,
+  estimate to bid by the odds of making each contract,
+  or exec:COMMAND to run a program speaking the bot protocol,
+  or with the onnx feature onnx:MODEL or onnx:BIDDING,DECLARING,PLAYING;
+  rooms made with join --create only take random, heuristic or estimate
+
+time options for play, simulate, export, serve and lobby, in milliseconds:
+  --move-time MS  --game-time MS
+
+output option for play, replay, analyze and join:
+  --format plain|korean|json
+
+ruleset options for play, simulate, export, train, cfr, evolve, replay, serve and join --create:
+  --min-pledge N  --run-multiplier N  --back-run-multiplier N
+  --back-run-max-score N  --no-trump-multiplier N  --no-friend-multiplier N
+  --surrender-payment N";
 
 fn main() {
-    let players: Vec<Box<dyn Player>> = vec![
-        Box::new(RandomPlayer {}),
-        Box::new(RandomPlayer {}),
-        Box::new(RandomPlayer {}),
-        Box::new(RandomPlayer {}),
-        Box::new(RandomPlayer {}),
-    ];
-
-    let mut game = PledgePhase::new(0, 13);
-
-    println!("== 공약 ==");
-    while !game.pledge_done() {
-        let player_index = game.turn_player();
-        let player = &players[player_index];
-        let bidding_state = game.bidding_state(player_index);
-        let contract = player.bidding(&bidding_state);
-        println!("Player {}: {:?}", player_index, contract);
-        game.player_bids(player_index, contract);
-    }
-    if game.cancelled() {
-        println!("Pledge cancelled");
-        return;
-    }
-
-    let mut game = ExtraPhase::from_pledge(game);
-    println!("{:?}", game);
-    let declarer_index = game.declarer();
-    let declarer = &players[declarer_index];
-
-    println!("주공: Player {}", declarer_index);
-    let (contract, condition, discards) = declarer.declare_plan(game.extra_state());
-    println!("공약: {:?}", condition);
-
-    println!("{:?}", discards);
-
-    let mut game = game.submit_plan(contract, condition, discards);
-
-    println!("{:?}", game);
-    println!("== 플레이 ==");
-    for round in 0..10 {
-        println!("== Round {} ==", round + 1);
-        for player_index in game.current_round_order() {
-            let player = &players[player_index];
-            let curr_state = game.play_state(player_index);
-            let action = player.play_action(curr_state);
-            println!("Player {} : {:?}", player_index, action);
-            game.player_acts(player_index, action);
-            println!("{:?}", game);
-        }
-    }
-    println!("Result: {:?}", game.result());
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("play") => Args::parse(&args[1..], &[]).and_then(play),
+        Some("simulate") => Args::parse(&args[1..], &["--early-end"]).and_then(simulate),
+        Some("export") => Args::parse(&args[1..], &[]).and_then(export),
+        Some("train") => Args::parse(&args[1..], &[]).and_then(train),
+        Some("cfr") => Args::parse(&args[1..], &[]).and_then(cfr),
+        Some("evolve") => Args::parse(&args[1..], &[]).and_then(evolve),
+        Some("estimate") => Args::parse(&args[1..], &[]).and_then(estimate),
+        Some("replay") => Args::parse(&args[1..], &["--step"]).and_then(replay),
+        Some("analyze") => Args::parse(&args[1..], &[]).and_then(analyze),
+        Some("review") => Args::parse(&args[1..], &[]).and_then(review),
+        Some("serve") => Args::parse(&args[1..], &[]).and_then(serve),
+        Some("lobby") => Args::parse(&args[1..], &[]).and_then(lobby),
+        Some("join") => Args::parse(&args[1..], &["--start"]).and_then(join),
+        Some("bot") => Args::parse(&args[1..], &[]).and_then(bot),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+/// Positional arguments and `--name value` options, taken out one by one
+/// so that anything left over can be reported.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    /// `switches` are options without a value.
+    fn parse(args: &[String], switches: &[&str]) -> Result<Args, String> {
+        let mut parsed = Args {
+            positional: vec![],
+            options: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+            } else if switches.contains(&arg.as_str()) {
+                parsed.options.push((arg.clone(), String::new()));
+            } else {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                parsed.options.push((arg.clone(), value.clone()));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        let i = self.options.iter().position(|(n, _)| n == name)?;
+        Some(self.options.remove(i).1)
+    }
+
+    fn take_parsed<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, String> {
+        match self.take(name) {
+            Some(value) => match value.parse() {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(format!("invalid value for {}: {}", name, value)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn switch(&mut self, name: &str) -> bool {
+        self.take(name).is_some()
+    }
+
+    fn positional(&mut self, what: &str) -> Result<String, String> {
+        if self.positional.is_empty() {
+            return Err(format!("missing {}", what));
+        }
+        Ok(self.positional.remove(0))
+    }
+
+    fn rules(&mut self) -> Result<RuleSet, String> {
+        let mut rules = RuleSet::default();
+        for (name, value) in [
+            ("--run-multiplier", &mut rules.run_multiplier),
+            ("--back-run-multiplier", &mut rules.back_run_multiplier),
+            ("--no-trump-multiplier", &mut rules.no_trump_multiplier),
+            ("--no-friend-multiplier", &mut rules.no_friend_multiplier),
+            ("--surrender-payment", &mut rules.surrender_payment),
+        ] {
+            if let Some(v) = self.take_parsed(name)? {
+                *value = v;
+            }
+        }
+        for (name, value) in [
+            ("--min-pledge", &mut rules.min_pledge),
+            ("--back-run-max-score", &mut rules.back_run_max_score),
+        ] {
+            if let Some(v) = self.take_parsed(name)? {
+                *value = v;
+            }
+        }
+        if !(1..=20).contains(&rules.min_pledge) {
+            return Err("--min-pledge must be between 1 and 20".to_string());
+        }
+        Ok(rules)
+    }
+
+    fn style(&mut self) -> Result<OutputStyle, String> {
+        match self.take("--format").as_deref() {
+            None | Some("plain") => Ok(OutputStyle::Plain),
+            Some("korean") => Ok(OutputStyle::Korean),
+            Some("json") => Ok(OutputStyle::Json),
+            Some(format) => Err(format!("unknown format: {}", format)),
+        }
+    }
+
+    fn time_control(&mut self) -> Result<TimeControl, String> {
+        Ok(TimeControl {
+            per_move: self.take_parsed("--move-time")?.map(Duration::from_millis),
+            per_game: self.take_parsed("--game-time")?.map(Duration::from_millis),
+        })
+    }
+
+    /// How a server covers for clients that drop out.
+    fn takeover(&mut self) -> Result<Takeover, String> {
+        let mut takeover = Takeover::default();
+        if let Some(seconds) = self.take_parsed::<u64>("--grace")? {
+            takeover.grace = Duration::from_secs(seconds);
+        }
+        if let Some(bot) = self.take("--takeover") {
+            check_player(&bot)?;
+            takeover.bot = bot;
+        }
+        Ok(takeover)
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if let Some(arg) = self.positional.first() {
+            return Err(format!("unexpected argument: {}", arg));
+        }
+        if let Some((name, _)) = self.options.first() {
+            return Err(format!("unknown option: {}", name));
+        }
+        Ok(())
+    }
+}
+
+fn read_record(path: &str) -> Result<GameRecord, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    text.parse().map_err(|e| format!("{}: {}", path, e))
+}
+
+fn play(mut args: Args) -> Result<(), String> {
+    let style = args.style()?;
+    let rules = args.rules()?;
+    let time_control = args.time_control()?;
+    let seed = args.take_parsed::<u64>("--seed")?;
+    let record_path = args.take("--record");
+    let seats = args
+        .take("--seats")
+        .map_or(vec!["random".to_string(); 5], |s| {
+            s.split(',').map(str::to_string).collect()
+        });
+    args.finish()?;
+
+    if seats.len() != 5 {
+        return Err(format!("--seats needs 5 players, got {}", seats.len()));
+    }
+    let players = seats
+        .iter()
+        .enumerate()
+        .map(|(seat, name)| match name.as_str() {
+            "human" => Ok(Box::new(HumanPlayer::new(seat, style)) as Box<dyn Player>),
+            _ => create_player(name),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // Hidden cards are only shown when nobody at the table could see them.
+    let open = !seats.iter().any(|s| s == "human");
+
+    let narrator = Narrator::new(style);
+    let game = match seed {
+        Some(seed) => PledgePhase::seeded(0, rules.min_pledge, seed),
+        None => PledgePhase::new(0, rules.min_pledge),
+    };
+    if open {
+        narrator.deal(&game.hands, &game.bottom);
+    }
+    let on_event = |event: &Event, record: &GameRecord| {
+        narrator.event(event);
+        if open && matches!(event, Event::Plan { .. }) {
+            narrator.discards(&record.plan.as_ref().unwrap().2);
+        }
+    };
+    let (end, record) =
+        play_hand_with_events(&players, game, time_control, EarlyEnd::Never, on_event);
+    if let Some(result) = end.result(&rules) {
+        narrator.event(&result);
+    }
+
+    if let Some(path) = record_path {
+        fs::write(&path, record.to_string()).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn simulate(mut args: Args) -> Result<(), String> {
+    let rules = args.rules()?;
+    let simulation = Simulation {
+        games: args.take_parsed("--games")?.unwrap_or(1000),
+        seed: args.take_parsed("--seed")?.unwrap_or(0),
+        lineup: args
+            .take("--lineup")
+            .map_or(vec!["random".to_string(); 5], |s| {
+                s.split(',').map(str::to_string).collect()
+            }),
+        threads: args
+            .take_parsed("--threads")?
+            .unwrap_or_else(default_threads),
+        time_control: args.time_control()?,
+        early_end: match args.switch("--early-end") {
+            true => EarlyEnd::Settled(rules.back_run_max_score),
+            false => EarlyEnd::Never,
+        },
+        rules,
+    };
+    let format = match args.take("--format").as_deref() {
+        None | Some("csv") => OutputFormat::Csv,
+        Some("jsonl") => OutputFormat::Jsonl,
+        Some(format) => return Err(format!("unknown format: {}", format)),
+    };
+    let output = args.take("--output");
+    args.finish()?;
+
+    let summaries = simulation.run()?;
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(&path).map_err(|e| format!("{}: {}", path, e))?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(&mut out);
+    write_summaries(&mut out, &summaries, format).map_err(|e| e.to_string())?;
+    out.flush().map_err(|e| e.to_string())
+}
+
+fn export(mut args: Args) -> Result<(), String> {
+    let rules = args.rules()?;
+    let simulation = Simulation {
+        games: args.take_parsed("--games")?.unwrap_or(1000),
+        seed: args.take_parsed("--seed")?.unwrap_or(0),
+        lineup: args
+            .take("--lineup")
+            .map_or(vec!["random".to_string(); 5], |s| {
+                s.split(',').map(str::to_string).collect()
+            }),
+        rules: rules.clone(),
+        threads: args
+            .take_parsed("--threads")?
+            .unwrap_or_else(default_threads),
+        time_control: args.time_control()?,
+        early_end: EarlyEnd::Never,
+    };
+    let format = match args.take("--format").as_deref() {
+        None | Some("jsonl") => DatasetFormat::Jsonl,
+        Some("columns") => DatasetFormat::Columns,
+        Some(format) => return Err(format!("unknown format: {}", format)),
+    };
+    let shard_games = args.take_parsed::<usize>("--shard-games")?.unwrap_or(1000);
+    if shard_games == 0 {
+        return Err("--shard-games must be positive".to_string());
+    }
+    let dir = args.positional("directory")?;
+    let paths = std::mem::take(&mut args.positional);
+    args.finish()?;
+
+    let mut writer = DatasetWriter::new(&dir, format)?;
+    if paths.is_empty() {
+        for start in (0..simulation.games).step_by(shard_games) {
+            let end = (start + shard_games).min(simulation.games);
+            let games = simulation
+                .records(start..end)?
+                .into_iter()
+                .map(|(seed, record)| GameData::from_record(seed, &record, &rules))
+                .collect::<Result<Vec<_>, _>>()?;
+            writer.write_shard(&games)?;
+        }
+    } else {
+        for (start, chunk) in paths.chunks(shard_games).enumerate() {
+            let mut games = vec![];
+            for (i, path) in chunk.iter().enumerate() {
+                let record = read_record(path)?;
+                let mut rules = rules.clone();
+                rules.min_pledge = record.min_pledge;
+                let game = (start * shard_games + i) as u64;
+                let data = GameData::from_record(game, &record, &rules);
+                games.push(data.map_err(|e| format!("{}: {}", path, e))?);
+            }
+            writer.write_shard(&games)?;
+        }
+    }
+    Ok(())
+}
+
+fn train(mut args: Args) -> Result<(), String> {
+    let defaults = TrainConfig::default();
+    let hidden = match args.take("--hidden") {
+        None => defaults.hidden,
+        Some(widths) if widths.is_empty() => vec![],
+        Some(widths) => widths
+            .split(',')
+            .map(|w| w.parse().map_err(|_| format!("invalid layer width: {}", w)))
+            .collect::<Result<_, _>>()?,
+    };
+    let config = TrainConfig {
+        hidden,
+        learning_rate: args
+            .take_parsed("--learning-rate")?
+            .unwrap_or(defaults.learning_rate),
+        batch: args.take_parsed("--batch")?.unwrap_or(defaults.batch),
+        iterations: args
+            .take_parsed("--iterations")?
+            .unwrap_or(defaults.iterations),
+        seed: args.take_parsed("--seed")?.unwrap_or(defaults.seed),
+        eval_every: args
+            .take_parsed("--eval-every")?
+            .unwrap_or(defaults.eval_every),
+        eval_hands: args
+            .take_parsed("--eval-hands")?
+            .unwrap_or(defaults.eval_hands),
+        eval_seed: defaults.eval_seed,
+        opponent: args.take("--opponent").unwrap_or(defaults.opponent),
+        checkpoint: args.take("--checkpoint").map(Into::into),
+        rules: args.rules()?,
+    };
+    let resume = args.take("--resume");
+    args.finish()?;
+    check_player(&config.opponent)?;
+
+    let mut trainer = match resume {
+        Some(path) => Trainer::load(config, path)?,
+        None => Trainer::new(config),
+    };
+    trainer.run(|progress| {
+        if let Some(evaluation) = progress.evaluation {
+            println!(
+                "iteration {}: entropy {:.3}, win rate {:.3} and mean payout {:.3} over {} hands",
+                progress.iteration,
+                progress.entropy,
+                evaluation.win_rate(),
+                evaluation.mean_payout(),
+                evaluation.hands
+            );
+        }
+    })
+}
+
+fn cfr(mut args: Args) -> Result<(), String> {
+    let rules = args.rules()?;
+    let iterations = args.take_parsed("--iterations")?.unwrap_or(10000);
+    let seed = args.take_parsed("--seed")?.unwrap_or(0);
+    let playout = args.take("--playout").unwrap_or("random".to_string());
+    let output = args.positional("output file")?;
+    args.finish()?;
+
+    let mut solver = Cfr::new(rules, &playout, seed)?;
+    solver.iterate(iterations);
+    solver.strategy().save(&output)?;
+    println!(
+        "{} iterations, {} information sets",
+        solver.iterations(),
+        solver.info_sets()
+    );
+    Ok(())
+}
+
+fn evolve(mut args: Args) -> Result<(), String> {
+    let evolution = Evolution {
+        population: args.take_parsed("--population")?.unwrap_or(16),
+        generations: args.take_parsed("--generations")?.unwrap_or(20),
+        games: args.take_parsed("--games")?.unwrap_or(200),
+        seed: args.take_parsed("--seed")?.unwrap_or(0),
+        mutation: args.take_parsed("--mutation")?.unwrap_or(0.1),
+        elite: args.take_parsed("--elite")?.unwrap_or(2),
+        opponent: args.take("--opponent").unwrap_or("heuristic".to_string()),
+        rules: args.rules()?,
+        threads: args
+            .take_parsed("--threads")?
+            .unwrap_or_else(default_threads),
+    };
+    let output = args.positional("output file")?;
+    args.finish()?;
+
+    let best = evolution.run(|generation| {
+        println!(
+            "generation {}: best payout {:.3}, mean payout {:.3}",
+            generation.number,
+            generation.best().1,
+            generation.mean_fitness()
+        );
+    })?;
+    best.save(&output)
+}
+
+fn estimate(mut args: Args) -> Result<(), String> {
+    let playout = args.take("--playout").unwrap_or("heuristic".to_string());
+    let mut estimator = Estimator::new(&playout)?;
+    if let Some(samples) = args.take_parsed("--samples")? {
+        estimator.samples = samples;
+    }
+    if let Some(depth) = args.take_parsed("--depth")? {
+        estimator.depth = depth;
+    }
+    if let Some(seed) = args.take_parsed("--seed")? {
+        estimator.seed = seed;
+    }
+    let hand = std::mem::take(&mut args.positional)
+        .iter()
+        .map(|card| card.parse())
+        .collect::<Result<Hand, _>>()?;
+    args.finish()?;
+
+    println!("{}", estimator.estimate(&hand)?);
+    Ok(())
+}
+
+fn replay(mut args: Args) -> Result<(), String> {
+    let style = args.style()?;
+    let mut rules = args.rules()?;
+    let step = args.switch("--step");
+    let path = args.positional("file")?;
+    args.finish()?;
+
+    let record = read_record(&path)?;
+    rules.min_pledge = record.min_pledge;
+    // Check the whole record before narrating any of it.
+    let surrender = if record.surrendered {
+        Some(record.extra_phase()?.surrender())
+    } else {
+        record.play_phase(record.actions.len())?;
+        None
+    };
+
+    let narrator = Narrator::new(style);
+    let wait = || {
+        if step {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line).unwrap();
+        }
+    };
+    // Timeouts go before the move made for the player.
+    let timeouts = |moves: usize| {
+        for &(player, _) in record.timeouts.iter().filter(|(_, m)| *m == moves) {
+            narrator.event(&Event::Timeout { player });
+        }
+    };
+    narrator.deal(&record.hands, &record.bottom);
+    for (i, &(player, bid)) in record.bids.iter().enumerate() {
+        timeouts(i);
+        narrator.event(&Event::Bid {
+            player,
+            contract: bid,
+        });
+    }
+    timeouts(record.bids.len());
+    if let Some(surrender) = surrender {
+        narrator.event(&Event::Surrender {
+            declarer: surrender.declarer,
+        });
+        narrator.event(&Event::surrender_result(
+            &rules.settle_surrender(&surrender),
+        ));
+        return Ok(());
+    }
+    let mut game = record.play_phase(0)?;
+    narrator.event(&Event::Plan {
+        declarer: game.declarer,
+        contract: game.contract,
+        partner_condition: game.partner_condition,
+    });
+    narrator.discards(&game.discarded);
+    wait();
+
+    for (i, &(player, action)) in record.actions.iter().enumerate() {
+        timeouts(record.bids.len() + 1 + i);
+        let before = game.clone();
+        game.player_acts(player, action);
+        for event in Event::after_action(&before, &game, player, action) {
+            narrator.event(&event);
+        }
+        wait();
+    }
+    if game.is_done() {
+        narrator.event(&Event::result(&game, &rules.settle(&game)));
+    }
+    Ok(())
+}
+
+fn analyze(mut args: Args) -> Result<(), String> {
+    let style = args.style()?;
+    let at = args.take_parsed("--at")?;
+    let path = args.positional("file")?;
+    args.finish()?;
+
+    let record = read_record(&path)?;
+    let game = record.play_phase(at.unwrap_or(record.actions.len()))?;
+    let player = game.turn_player().ok_or("the hand is already over")?;
+    let values = Solver::new().action_values(&game);
+    Narrator::new(style).action_values(player, &values);
+    Ok(())
+}
+
+fn review(mut args: Args) -> Result<(), String> {
+    let mut advisor = Advisor::default();
+    if let Some(worlds) = args.take_parsed("--worlds")? {
+        advisor.worlds = worlds;
+    }
+    if let Some(depth) = args.take_parsed("--depth")? {
+        advisor.depth = depth;
+    }
+    if let Some(seed) = args.take_parsed("--seed")? {
+        advisor.seed = seed;
+    }
+    let seats = match args.take("--seats") {
+        Some(seats) => seats
+            .split(',')
+            .map(|s| match s.parse() {
+                Ok(seat @ 0..=4) => Ok(seat),
+                _ => Err(format!("invalid seat: {}", s)),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => (0..5).collect(),
+    };
+    let output = args.take("--output");
+    let path = args.positional("file")?;
+    args.finish()?;
+
+    let mut record = read_record(&path)?;
+    let annotations = advisor.review(&record, &seats)?;
+    for annotation in &annotations {
+        eprintln!(
+            "move {}, player {}: {}",
+            annotation.action + 1,
+            annotation.player,
+            annotation
+        );
+    }
+    mark_mistakes(&mut record, &annotations);
+    match output {
+        Some(output) => {
+            fs::write(&output, record.to_string()).map_err(|e| format!("{}: {}", output, e))
+        }
+        None => {
+            print!("{}", record);
+            Ok(())
+        }
+    }
+}
+
+fn serve(mut args: Args) -> Result<(), String> {
+    let rules = args.rules()?;
+    let port = args.take_parsed::<u16>("--port")?.unwrap_or(4000);
+    let hands = args.take_parsed::<usize>("--hands")?.unwrap_or(1);
+    let takeover = args.takeover()?;
+    let time_control = args.time_control()?;
+    args.finish()?;
+
+    let mut server = Server::bind(("0.0.0.0", port), rules).map_err(|e| e.to_string())?;
+    server.takeover = takeover;
+    server.time_control = time_control;
+    eprintln!(
+        "Waiting for five players on port {}; browsers can join at http://localhost:{}/",
+        port, port
+    );
+    let mut seats = server.accept_table().map_err(|e| e.to_string())?;
+    for (i, seat) in seats.iter().enumerate() {
+        if let Seat::Remote(remote) = seat {
+            eprintln!("Player {}: {}", i, remote.name);
+        }
+    }
+    for hand in 0..hands {
+        // The lead of the pledge moves around the table.
+        let game = PledgePhase::new(hand % 5, server.rules.min_pledge);
+        server
+            .host_game(&mut seats, game)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn lobby(mut args: Args) -> Result<(), String> {
+    let port = args.take_parsed::<u16>("--port")?.unwrap_or(4000);
+    let takeover = args.takeover()?;
+    let time_control = args.time_control()?;
+    args.finish()?;
+
+    let mut lobby = Lobby::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+    lobby.takeover = takeover;
+    lobby.time_control = time_control;
+    eprintln!(
+        "Lobby open on port {}; browsers can join at http://localhost:{}/",
+        port, port
+    );
+    lobby.run().map_err(|e| e.to_string())
+}
+
+fn join(mut args: Args) -> Result<(), String> {
+    let style = args.style()?;
+    let addr = args.positional("server address")?;
+    let name = args.take("--name").unwrap_or("player".to_string());
+    let kind = args.take("--player").unwrap_or("human".to_string());
+    let room = args.take_parsed::<usize>("--room")?;
+    let create = args.take("--create");
+    let rules = match create {
+        Some(_) => args.rules()?,
+        None => RuleSet::default(),
+    };
+    let hands = args.take_parsed::<usize>("--hands")?.unwrap_or(5);
+    let bots = args.take("--bots").unwrap_or("random".to_string());
+    let seat = args.take_parsed::<usize>("--seat")?;
+    let start = args.switch("--start");
+    let token = args.take("--token");
+    args.finish()?;
+
+    let error = |e: io::Error| format!("{}: {}", addr, e);
+    let in_lobby = room.is_some() || create.is_some();
+    let mut client = if let Some(token) = token {
+        // Back to a seat lost mid-hand; the rest is played hand by hand.
+        if in_lobby {
+            return Err("--token takes the seat back; leave out --room and --create".to_string());
+        }
+        Client::rejoin(&addr, &name, &token).map_err(error)?
+    } else if in_lobby {
+        let (mut client, _) = Client::enter_lobby(&addr, &name).map_err(error)?;
+        match (room, create) {
+            (Some(room), None) => client.join_room(room, seat),
+            (None, Some(room)) => client.create_room(&room, rules, hands, &bots, seat),
+            _ => return Err("use either --room or --create".to_string()),
+        }
+        .map_err(error)?;
+        if start {
+            client.start().map_err(error)?;
+        }
+        client
+    } else {
+        Client::connect(&addr, &name).map_err(error)?
+    };
+    let player: Box<dyn Player> = match kind.as_str() {
+        "human" => Box::new(HumanPlayer::new(client.seat, style)),
+        _ => create_player(&kind)?,
+    };
+    eprintln!("Seated as player {} (token {})", client.seat, client.token);
+    let narrator = Narrator::new(style);
+    if in_lobby {
+        let room = client
+            .play_match(player.as_ref(), |event| narrator.event(event))
+            .map_err(error)?;
+        eprintln!(
+            "Match over after {} hands, scores {:?}",
+            room.hands_played, room.scores
+        );
+    } else {
+        // Play until the server closes the table.
+        loop {
+            match client.play_hand(player.as_ref(), |event| narrator.event(event)) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(error(e)),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn bot(mut args: Args) -> Result<(), String> {
+    let kind = args.take("--player").unwrap_or("random".to_string());
+    args.finish()?;
+
+    let player = create_player(&kind)?;
+    run_bot(
+        player.as_ref(),
+        &kind,
+        io::stdin().lock(),
+        io::stdout().lock(),
+    )
 }
@@ -0,0 +1,298 @@
+//! A small multilayer perceptron for the play phase, trained on the CPU by
+//! `train`. It maps a seat's view in an `encoding::Layout` to a logit for
+//! every index of the layout's action space, through hidden layers of
+//! rectified units.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::Card;
+use crate::clock::default_plan;
+use crate::common::{Contract, PartnerCondition, PlayAction};
+use crate::encoding::{Action, Layout};
+use crate::extra_phase::ExtraExposedState;
+use crate::play_phase::ExposedGameState;
+use crate::player::{Player, RandomPlayer};
+use rand::Rng;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Starts every checkpoint file.
+const MAGIC: &[u8; 8] = b"MIGHTYNN";
+
+#[derive(Clone, Debug)]
+pub struct Mlp {
+    pub layout: Layout,
+    /// The width of each layer, from the input to the logits.
+    sizes: Vec<usize>,
+    /// For each layer after the input, its weights row by row of outputs,
+    /// then its biases.
+    weights: Vec<Vec<f32>>,
+    biases: Vec<Vec<f32>>,
+}
+
+/// The gradient of a loss by every weight and bias of an `Mlp`.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    weights: Vec<Vec<f32>>,
+    biases: Vec<Vec<f32>>,
+}
+
+impl Mlp {
+    /// A network with the `hidden` layer widths, its hidden weights drawn at
+    /// random and its output weights zero, so that it starts out choosing
+    /// uniformly.
+    pub fn new(layout: Layout, hidden: &[usize], rng: &mut impl Rng) -> Self {
+        let mut sizes = vec![layout.observation_size()];
+        sizes.extend(hidden);
+        sizes.push(layout.action_count());
+        let layers = sizes.len() - 1;
+        let weights = (0..layers)
+            .map(|l| {
+                let count = sizes[l] * sizes[l + 1];
+                if l + 1 == layers {
+                    return vec![0.0; count];
+                }
+                let bound = (6.0 / sizes[l] as f32).sqrt();
+                (0..count)
+                    .map(|_| rng.random_range(-bound..bound))
+                    .collect()
+            })
+            .collect();
+        let biases = sizes[1..].iter().map(|&n| vec![0.0; n]).collect();
+        Mlp {
+            layout,
+            sizes,
+            weights,
+            biases,
+        }
+    }
+
+    pub fn hidden(&self) -> &[usize] {
+        &self.sizes[1..self.sizes.len() - 1]
+    }
+
+    /// The output of every layer, the input first and the logits last.
+    fn activations(&self, features: &[f32]) -> Vec<Vec<f32>> {
+        assert_eq!(features.len(), self.sizes[0]);
+        let mut activations = vec![features.to_vec()];
+        let layers = self.weights.len();
+        for l in 0..layers {
+            let input = &activations[l];
+            let mut output = self.biases[l].clone();
+            for (o, row) in output.iter_mut().zip(self.weights[l].chunks(input.len())) {
+                *o += row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>();
+                if l + 1 < layers {
+                    *o = o.max(0.0);
+                }
+            }
+            activations.push(output);
+        }
+        activations
+    }
+
+    /// The logits for a view.
+    pub fn forward(&self, features: &[f32]) -> Vec<f32> {
+        self.activations(features).pop().unwrap()
+    }
+
+    pub fn zero_gradient(&self) -> Gradient {
+        Gradient {
+            weights: self.weights.iter().map(|w| vec![0.0; w.len()]).collect(),
+            biases: self.biases.iter().map(|b| vec![0.0; b.len()]).collect(),
+        }
+    }
+
+    /// Adds to `gradient` the gradient of `-weight * log p(action)`, where
+    /// `p` is the softmax of the logits over the `legal` indices. Returns
+    /// the probability the network gave the action.
+    pub fn accumulate(
+        &self,
+        gradient: &mut Gradient,
+        features: &[f32],
+        legal: &[usize],
+        action: usize,
+        weight: f32,
+    ) -> f32 {
+        let activations = self.activations(features);
+        let probabilities = softmax(activations.last().unwrap(), legal);
+        let mut delta = vec![0.0; self.sizes[self.sizes.len() - 1]];
+        let mut chosen = 0.0;
+        for (&i, &p) in legal.iter().zip(&probabilities) {
+            delta[i] = weight * p;
+            if i == action {
+                delta[i] -= weight;
+                chosen = p;
+            }
+        }
+        for l in (0..self.weights.len()).rev() {
+            let input = &activations[l];
+            let rows = self.weights[l].chunks(input.len());
+            let mut below = vec![0.0; input.len()];
+            let weights = gradient.weights[l].chunks_mut(input.len());
+            for (((d, row), grad), bias) in delta
+                .iter()
+                .zip(rows)
+                .zip(weights)
+                .zip(&mut gradient.biases[l])
+            {
+                if *d == 0.0 {
+                    continue;
+                }
+                *bias += d;
+                for ((g, b), (w, x)) in grad.iter_mut().zip(&mut below).zip(row.iter().zip(input)) {
+                    *g += d * x;
+                    *b += d * w;
+                }
+            }
+            // Through the rectifier of the layer below.
+            for (b, x) in below.iter_mut().zip(input) {
+                if *x <= 0.0 {
+                    *b = 0.0;
+                }
+            }
+            delta = below;
+        }
+        chosen
+    }
+
+    /// Takes a step of gradient descent.
+    pub fn apply(&mut self, gradient: &Gradient, learning_rate: f32) {
+        let params = self.weights.iter_mut().chain(&mut self.biases);
+        let grads = gradient.weights.iter().chain(&gradient.biases);
+        for (param, grad) in params.zip(grads) {
+            for (p, g) in param.iter_mut().zip(grad) {
+                *p -= learning_rate * g;
+            }
+        }
+    }
+
+    /// The legal index with the highest logit.
+    pub fn best(&self, features: &[f32], legal: &[usize]) -> Option<usize> {
+        let logits = self.forward(features);
+        legal
+            .iter()
+            .copied()
+            .max_by(|&a, &b| logits[a].total_cmp(&logits[b]))
+    }
+
+    /// Writes the network: a header, the layout version and the layer
+    /// widths as little-endian `u32`s, then the weights and biases of each
+    /// layer as little-endian `f32`s.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(self.layout.version().to_le_bytes());
+        bytes.extend((self.sizes.len() as u32).to_le_bytes());
+        for &size in &self.sizes {
+            bytes.extend((size as u32).to_le_bytes());
+        }
+        for (weights, biases) in self.weights.iter().zip(&self.biases) {
+            for value in weights.iter().chain(biases) {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+        let path = path.as_ref();
+        fs::write(path, bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Mlp, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Mlp::from_bytes(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Mlp, String> {
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or("not a network checkpoint")?;
+        let mut words = rest.chunks_exact(4).map(|w| [w[0], w[1], w[2], w[3]]);
+        let mut word = || words.next().ok_or("truncated checkpoint".to_string());
+        let version = u32::from_le_bytes(word()?);
+        let layout =
+            Layout::from_version(version).ok_or(format!("unknown encoding layout {}", version))?;
+        let layers = u32::from_le_bytes(word()?) as usize;
+        let sizes = (0..layers)
+            .map(|_| Ok(u32::from_le_bytes(word()?) as usize))
+            .collect::<Result<Vec<_>, String>>()?;
+        if layers < 2
+            || sizes[0] != layout.observation_size()
+            || sizes[layers - 1] != layout.action_count()
+        {
+            return Err("the layers do not fit the layout".to_string());
+        }
+        let mut weights = vec![];
+        let mut biases = vec![];
+        for l in 0..layers - 1 {
+            let mut floats = |count: usize| {
+                (0..count)
+                    .map(|_| Ok(f32::from_le_bytes(word()?)))
+                    .collect::<Result<Vec<_>, String>>()
+            };
+            weights.push(floats(sizes[l] * sizes[l + 1])?);
+            biases.push(floats(sizes[l + 1])?);
+        }
+        if word().is_ok() {
+            return Err("trailing data in checkpoint".to_string());
+        }
+        Ok(Mlp {
+            layout,
+            sizes,
+            weights,
+            biases,
+        })
+    }
+}
+
+/// The softmax of `logits` over the `legal` indices, in their order.
+pub fn softmax(logits: &[f32], legal: &[usize]) -> Vec<f32> {
+    let max = legal
+        .iter()
+        .map(|&i| logits[i])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let exps = legal
+        .iter()
+        .map(|&i| (logits[i] - max).exp())
+        .collect::<Vec<_>>();
+    let sum = exps.iter().sum::<f32>();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+/// Plays cards by the best legal action of a network. Networks only learn
+/// the play phase, so bids are those of `RandomPlayer` and the declarer's
+/// plan is `default_plan`, as during training.
+pub struct MlpPlayer {
+    pub network: Mlp,
+}
+
+impl MlpPlayer {
+    pub fn new(network: Mlp) -> Self {
+        MlpPlayer { network }
+    }
+}
+
+impl Player for MlpPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        RandomPlayer {}.bidding(state)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        default_plan(&state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        let layout = self.network.layout;
+        let mut legal = state
+            .legal_actions()
+            .into_iter()
+            .map(|a| layout.encode_action(Action::Play(a)))
+            .collect::<Vec<_>>();
+        legal.sort();
+        let best = self.network.best(&layout.encode_play(&state), &legal);
+        match layout.decode_action(best.unwrap()) {
+            Some(Action::Play(action)) => action,
+            _ => unreachable!("legal indices are play actions"),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Bots that play by a trained policy, run as an ONNX model on the CPU.
+//!
+//! A model takes one input, a `1 × n` float tensor of a seat's view in an
+//! `encoding::Layout`, and gives the policy as its first output: a logit
+//! for every index of the layout's action space. A second output, when
+//! there is one, is read as the value of the position. Illegal actions are
+//! never chosen, whatever their logits.
+
+use crate::bidding_phase::BiddingState;
+use crate::card::Card;
+use crate::clock::{default_action, default_bid, default_plan};
+use crate::common::{Contract, PartnerCondition, PlayAction};
+use crate::encoding::{bid_actions, Action, Layout, PartialPlan};
+use crate::extra_phase::ExtraExposedState;
+use crate::play_phase::ExposedGameState;
+use crate::player::Player;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+use std::rc::Rc;
+use tract_onnx::prelude::*;
+
+/// A loaded model, for views in `layout`.
+pub struct OnnxModel {
+    pub layout: Layout,
+    plan: TypedRunnableModel<TypedModel>,
+}
+
+impl OnnxModel {
+    /// Loads the model at `path`. Fails when it cannot be read or does not
+    /// fit the layout.
+    pub fn load(path: impl AsRef<Path>, layout: Layout) -> Result<OnnxModel, String> {
+        let path = path.as_ref();
+        let error = |e: TractError| format!("{}: {}", path.display(), e);
+        let plan = tract_onnx::onnx()
+            .model_for_path(path)
+            .and_then(|model| {
+                model.with_input_fact(0, f32::fact([1, layout.observation_size()]).into())
+            })
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(error)?;
+        let model = OnnxModel { layout, plan };
+        let (policy, _) = model.evaluate(&vec![0.0; layout.observation_size()])?;
+        if policy.len() != layout.action_count() {
+            return Err(format!(
+                "{}: the policy has {} logits, not {}",
+                path.display(),
+                policy.len(),
+                layout.action_count()
+            ));
+        }
+        Ok(model)
+    }
+
+    /// The policy logits for a view, and its value when the model has one.
+    pub fn evaluate(&self, features: &[f32]) -> Result<(Vec<f32>, Option<f32>), String> {
+        let input =
+            Tensor::from_shape(&[1, features.len()], features).map_err(|e| e.to_string())?;
+        let outputs = self
+            .plan
+            .run(tvec!(input.into()))
+            .map_err(|e| e.to_string())?;
+        let floats = |i: usize| -> Result<Vec<f32>, String> {
+            let output = outputs[i].as_slice::<f32>().map_err(|e| e.to_string())?;
+            Ok(output.to_vec())
+        };
+        let policy = floats(0)?;
+        let value = match outputs.len() {
+            1 => None,
+            _ => floats(1)?.first().copied(),
+        };
+        Ok((policy, value))
+    }
+}
+
+/// How a player picks among the legal actions.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Selection {
+    /// The action with the highest logit.
+    Argmax,
+    /// An action drawn from the softmax of the logits divided by the
+    /// temperature.
+    Sample { temperature: f32 },
+}
+
+/// Plays by a model for each phase. The declarer's plan is taken in the
+/// steps of a `PartialPlan`, and the declarer surrenders when the model
+/// picks that over the plan's first step. When a model fails the error
+/// goes to stderr and the default move of the clock is made.
+pub struct OnnxPlayer {
+    bidding: Rc<OnnxModel>,
+    declaring: Rc<OnnxModel>,
+    playing: Rc<OnnxModel>,
+    pub selection: Selection,
+    rng: RefCell<StdRng>,
+}
+
+impl OnnxPlayer {
+    /// A player with one model for every phase.
+    pub fn new(model: OnnxModel) -> Self {
+        let model = Rc::new(model);
+        OnnxPlayer::with_models(model.clone(), model.clone(), model)
+    }
+
+    pub fn with_models(
+        bidding: Rc<OnnxModel>,
+        declaring: Rc<OnnxModel>,
+        playing: Rc<OnnxModel>,
+    ) -> Self {
+        OnnxPlayer {
+            bidding,
+            declaring,
+            playing,
+            selection: Selection::Argmax,
+            rng: RefCell::new(StdRng::from_rng(&mut rand::rng())),
+        }
+    }
+
+    /// Seeds the sampling, for games that can be played again.
+    pub fn with_seed(self, seed: u64) -> Self {
+        *self.rng.borrow_mut() = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn choose(
+        &self,
+        model: &OnnxModel,
+        features: Vec<f32>,
+        mut legal: Vec<Action>,
+    ) -> Option<Action> {
+        let (policy, _) = match model.evaluate(&features) {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("onnx: {}", e);
+                return None;
+            }
+        };
+        legal.retain(|&action| model.layout.encodes(action));
+        // In index order, so that a seeded player draws the same each game.
+        legal.sort_by_key(|&action| model.layout.encode_action(action));
+        let logits = legal
+            .iter()
+            .map(|&action| policy[model.layout.encode_action(action)])
+            .collect::<Vec<_>>();
+        let best = (0..legal.len()).max_by(|&a, &b| logits[a].total_cmp(&logits[b]))?;
+        let chosen = match self.selection {
+            Selection::Argmax => best,
+            Selection::Sample { temperature } => {
+                let weights = logits
+                    .iter()
+                    .map(|l| ((l - logits[best]) / temperature).exp())
+                    .collect::<Vec<_>>();
+                let mut point = self.rng.borrow_mut().random::<f32>() * weights.iter().sum::<f32>();
+                weights
+                    .iter()
+                    .position(|w| {
+                        point -= w;
+                        point < 0.0
+                    })
+                    .unwrap_or(best)
+            }
+        };
+        Some(legal[chosen])
+    }
+
+    fn plan(
+        &self,
+        state: &ExtraExposedState,
+    ) -> Option<(Contract, PartnerCondition, HashSet<Card>)> {
+        let model = &*self.declaring;
+        let mut plan = PartialPlan::default();
+        loop {
+            if let Some(plan) = plan.complete() {
+                return Some(plan);
+            }
+            let features = model.layout.encode_plan(state, &plan);
+            let mut legal = plan.legal_actions(state);
+            legal.retain(|&action| action != Action::Surrender);
+            plan.take(self.choose(model, features, legal)?);
+        }
+    }
+}
+
+impl Player for OnnxPlayer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        let model = &*self.bidding;
+        let legal = bid_actions(state);
+        match self.choose(model, model.layout.encode_bidding(state), legal) {
+            Some(Action::Bid(bid)) => bid,
+            _ => default_bid(),
+        }
+    }
+
+    fn surrender(&self, state: &ExtraExposedState) -> bool {
+        let model = &*self.declaring;
+        let plan = PartialPlan::default();
+        let features = model.layout.encode_plan(state, &plan);
+        self.choose(model, features, plan.legal_actions(state)) == Some(Action::Surrender)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        self.plan(&state).unwrap_or_else(|| default_plan(&state))
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        let model = &*self.playing;
+        let legal = state
+            .legal_actions()
+            .into_iter()
+            .map(Action::Play)
+            .collect();
+        match self.choose(model, model.layout.encode_play(&state), legal) {
+            Some(Action::Play(action)) => action,
+            _ => default_action(&state),
+        }
+    }
+}
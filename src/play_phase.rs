@@ -2,11 +2,15 @@ use crate::card::{Card, Suit};
 use crate::common::{Contract, Hand, PartnerCondition, PlayAction, PlayerIndex};
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct RoundResult {
     pub winner: PlayerIndex,
     pub submitted: Vec<Card>,
+    pub round_starter: PlayerIndex,
+    pub round_suit: Option<Suit>,
+    pub joker_called: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -32,6 +36,47 @@ pub struct ExposedGameState {
     pub round_results: Vec<RoundResult>,
 }
 
+/// When a hand stops before all ten rounds are played, checked as each
+/// round starts.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum EarlyEnd {
+    /// Every round is played.
+    #[default]
+    Never,
+    /// Once the settlement can no longer change, given the back-run limit:
+    /// the friend is certain and either the declarer's side can no longer
+    /// reach the contract, with the back run decided, or no point card is
+    /// left to take. A hand stopped on a loss is settled on the points
+    /// taken until then.
+    Settled(u8),
+}
+
+/// `never` or `settled:10`.
+impl fmt::Display for EarlyEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EarlyEnd::Never => write!(f, "never"),
+            EarlyEnd::Settled(max) => write!(f, "settled:{}", max),
+        }
+    }
+}
+
+impl FromStr for EarlyEnd {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid early end: {}", s);
+        match s.split_once(':') {
+            None if s == "never" => Ok(EarlyEnd::Never),
+            Some(("settled", max)) => match max.parse() {
+                Ok(max @ 0..=20) => Ok(EarlyEnd::Settled(max)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq)]
 pub struct PlayPhase {
     // Static state
@@ -53,6 +98,10 @@ pub struct PlayPhase {
 
     // History
     pub round_results: Vec<RoundResult>,
+
+    /// When the hand may stop before its last round, set by
+    /// `set_early_end`.
+    pub early_end: EarlyEnd,
 }
 
 impl fmt::Debug for PlayPhase {
@@ -81,6 +130,113 @@ impl fmt::Debug for PlayPhase {
     }
 }
 
+/// How strongly `card` takes a round: the highest value played wins it.
+pub(crate) fn card_value(
+    card: Card,
+    trump: Option<Suit>,
+    round_suit: Option<Suit>,
+    round: u8,
+    joker_called: bool,
+) -> u8 {
+    match card {
+        Card::Joker => {
+            if joker_called || round == 0 || round == 9 {
+                0
+            } else {
+                100
+            }
+        }
+        Card::Shaped(s, n) => {
+            if card.is_mighty(trump) {
+                200
+            } else if trump == Some(s) {
+                70 + n
+            } else if round_suit == Some(s) {
+                30 + n
+            } else {
+                n
+            }
+        }
+    }
+}
+
+impl ExposedGameState {
+    /// How strongly `card` would take the current round, were it played
+    /// now; the highest value played wins the round.
+    pub fn card_value(&self, card: Card) -> u8 {
+        card_value(
+            card,
+            self.contract.suit,
+            self.round_suit,
+            self.round,
+            self.joker_called,
+        )
+    }
+
+    /// The player taking the current round so far, once a card is down.
+    pub fn round_leader(&self) -> Option<PlayerIndex> {
+        (0..5)
+            .filter_map(|p| self.submitted[p].map(|card| (p, self.card_value(card))))
+            .max_by_key(|&(_, value)| value)
+            .map(|(p, _)| p)
+    }
+
+    /// Legal actions of the observing player, when it is their turn.
+    pub fn legal_actions(&self) -> Vec<PlayAction> {
+        let starts = self.submitted.iter().all(|c| c.is_none());
+        legal_actions(
+            self.hand.iter().copied(),
+            starts,
+            self.joker_called,
+            self.round_suit,
+            self.contract.suit,
+        )
+    }
+}
+
+/// Legal actions for the cards of `hand`, sorted.
+pub(crate) fn legal_actions(
+    hand: impl Iterator<Item = Card> + Clone,
+    starts: bool,
+    joker_called: bool,
+    round_suit: Option<Suit>,
+    trump: Option<Suit>,
+) -> Vec<PlayAction> {
+    let mut actions = Vec::with_capacity(16);
+    if starts {
+        for card in hand {
+            match card {
+                Card::Joker => {
+                    for suit in Suit::ALL {
+                        actions.push(PlayAction::JokerStart(suit));
+                    }
+                }
+                Card::Shaped(_, _) => {
+                    actions.push(PlayAction::Hand(card));
+                    if card.is_joker_call(trump) {
+                        actions.push(PlayAction::JokerCall(card));
+                    }
+                }
+            }
+        }
+    } else if joker_called && hand.clone().any(|c| c == Card::Joker) {
+        actions.push(PlayAction::Hand(Card::Joker));
+    } else {
+        let has_suit = |c: &Card| match c {
+            Card::Shaped(s, _) => Some(*s) == round_suit,
+            Card::Joker => false,
+        };
+        let must_follow = hand.clone().any(|c| has_suit(&c));
+        for card in hand {
+            if !must_follow || has_suit(&card) || card == Card::Joker || card.is_mighty(trump) {
+                actions.push(PlayAction::Hand(card));
+            }
+        }
+    }
+    actions.sort();
+    actions
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Copy)]
 pub enum PartyType {
     Leading,
@@ -89,16 +245,97 @@ pub enum PartyType {
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct GameResult {
-    scores: Vec<u8>,
-    win: Vec<bool>,
-    win_side: PartyType,
+    pub scores: Vec<u8>,
+    pub win: Vec<bool>,
+    pub win_side: PartyType,
 }
 
 impl PlayPhase {
     pub fn current_round_order(&self) -> Vec<PlayerIndex> {
         let mut players_queue = (0..5).collect::<Vec<PlayerIndex>>();
         players_queue.rotate_left(self.round_starter);
-        return players_queue;
+        players_queue
+    }
+
+    /// Whether the hand is over, after ten rounds or early as `early_end`
+    /// allows.
+    pub fn is_done(&self) -> bool {
+        self.round >= 10 || self.decided()
+    }
+
+    /// Lets the hand stop early as `early_end` allows, which may end it
+    /// right away.
+    pub fn set_early_end(&mut self, early_end: EarlyEnd) {
+        self.early_end = early_end;
+        self.reveal_if_done();
+    }
+
+    /// Whether, at the start of a round, the early end allows the hand to
+    /// stop. A won hand goes on while a point card is left, since each one
+    /// still moves the margin.
+    fn decided(&self) -> bool {
+        let EarlyEnd::Settled(max) = self.early_end else {
+            return false;
+        };
+        if self.submitted.iter().any(Option::is_some) {
+            return false;
+        }
+        let Some(partner) = self.final_partner() else {
+            return false;
+        };
+        let scores = self.scores();
+        let partner = partner.filter(|&p| p != self.declarer);
+        let least = scores[self.declarer] + partner.map_or(0, |p| scores[p]);
+        let left = self.hands.iter().flatten().map(|c| c.score()).sum::<u8>();
+        let most = least + left;
+
+        let lost = most < self.contract.count;
+        let back_run_settled = least > max || most <= max;
+        left == 0 || (lost && back_run_settled)
+    }
+
+    /// The friend the hand ends up revealing, when already certain: a friend
+    /// by card is whoever holds it, if anyone. `None` while a friend by
+    /// round is still open.
+    fn final_partner(&self) -> Option<Option<PlayerIndex>> {
+        if self.partner_revealed.is_some() {
+            return Some(self.partner_revealed);
+        }
+        match self.partner_condition {
+            PartnerCondition::CardCondition(card) => {
+                Some((0..5).find(|&p| self.hands[p].contains(&card)))
+            }
+            PartnerCondition::Player(partner) => Some(Some(partner)),
+            PartnerCondition::Round(_) => None,
+            PartnerCondition::None => Some(None),
+        }
+    }
+
+    /// Reveals the friend of a hand that ended before their card was
+    /// played, so that the result counts them in.
+    fn reveal_if_done(&mut self) {
+        if self.is_done() && self.partner_revealed.is_none() {
+            self.partner_revealed = self.final_partner().flatten();
+        }
+    }
+
+    pub fn turn_player(&self) -> Option<PlayerIndex> {
+        if self.is_done() {
+            return None;
+        }
+        self.current_round_order()
+            .into_iter()
+            .find(|&i| self.submitted[i].is_none())
+    }
+
+    pub fn legal_actions(&self, player_index: PlayerIndex) -> Vec<PlayAction> {
+        legal_actions(
+            self.hands[player_index].iter().copied(),
+            player_index == self.round_starter,
+            self.joker_called,
+            self.round_suit,
+            self.contract.suit,
+        )
     }
 
     pub fn play_state(&self, player_index: PlayerIndex) -> ExposedGameState {
@@ -175,6 +412,9 @@ impl PlayPhase {
                 assert!(self.hands[player_index].contains(&card));
 
                 self.joker_called = true;
+                if let Card::Shaped(s, _) = card {
+                    self.round_suit = Some(s);
+                }
                 self.submitted[player_index] = Some(card);
                 self.hands[player_index].remove(&card);
 
@@ -211,6 +451,9 @@ impl PlayPhase {
             self.round_results.push(RoundResult {
                 winner,
                 submitted: self.submitted.iter().map(|v| v.unwrap()).collect(),
+                round_starter: self.round_starter,
+                round_suit: self.round_suit,
+                joker_called: self.joker_called,
             });
 
             if self.partner_revealed.is_none() {
@@ -226,6 +469,7 @@ impl PlayPhase {
             self.submitted = vec![None; 5];
             self.round_starter = winner;
             self.joker_called = false;
+            self.reveal_if_done();
         }
     }
 
@@ -239,30 +483,17 @@ impl PlayPhase {
     }
 
     fn card_value(&self, card: Card) -> u8 {
-        return match card {
-            Card::Joker => {
-                if self.joker_called || self.round == 0 || self.round == 9 {
-                    0
-                } else {
-                    100
-                }
-            }
-            Card::Shaped(s, n) => {
-                if card.is_mighty(self.contract.suit) {
-                    200
-                } else if self.contract.suit == Some(s) {
-                    70 + n
-                } else if self.round_suit == Some(s) {
-                    30 + n
-                } else {
-                    n
-                }
-            }
-        };
+        card_value(
+            card,
+            self.contract.suit,
+            self.round_suit,
+            self.round,
+            self.joker_called,
+        )
     }
 
-    pub fn result(&self) -> GameResult {
-        let scores: Vec<u8> = (0..5 as PlayerIndex)
+    pub fn scores(&self) -> Vec<u8> {
+        (0..5 as PlayerIndex)
             .map(|i| {
                 self.round_results
                     .iter()
@@ -270,11 +501,39 @@ impl PlayPhase {
                     .map(|r| r.submitted.iter().map(|c| c.score()).sum::<u8>())
                     .sum()
             })
-            .collect();
-        let declarer_score = scores[self.declarer];
-        let partner_score = self.partner_revealed.map_or(0, |partner| scores[partner]);
+            .collect()
+    }
+
+    /// Round in which the friend became known, counting a friend named
+    /// before play as revealed in round 0.
+    pub fn partner_revealed_round(&self) -> Option<u8> {
+        let partner = self.partner_revealed?;
+        match self.partner_condition {
+            PartnerCondition::CardCondition(card) => Some(
+                self.round_results
+                    .iter()
+                    .position(|r| r.submitted[partner] == card)
+                    .map_or(self.round, |round| round as u8),
+            ),
+            PartnerCondition::Round(n) => Some(n),
+            PartnerCondition::Player(_) | PartnerCondition::None => Some(0),
+        }
+    }
+
+    /// Points taken by the declarer and the revealed friend. A declarer who
+    /// is their own friend is counted once.
+    pub fn declarer_team_score(&self) -> u8 {
+        let scores = self.scores();
+        let partner = self.partner_revealed.filter(|&p| p != self.declarer);
+        scores[self.declarer] + partner.map_or(0, |p| scores[p])
+    }
+
+    /// The outcome of a finished hand. In a hand that ended early, `scores`
+    /// are the points taken until then.
+    pub fn result(&self) -> GameResult {
+        let scores = self.scores();
 
-        let (win, win_side) = if declarer_score + partner_score >= self.contract.count {
+        let (win, win_side) = if self.declarer_team_score() >= self.contract.count {
             (
                 (0..5 as PlayerIndex)
                     .map(|i| i == self.declarer || Some(i) == self.partner_revealed)
@@ -1,15 +1,170 @@
 use crate::bidding_phase::BiddingState;
 use crate::card::{Card, Suit};
-use crate::common::{Contract, PartnerCondition, PlayAction};
+use crate::cfr::{CfrPlayer, Strategy};
+use crate::common::{Claim, Contract, PartnerCondition, PlayAction};
+use crate::estimate::{EstimatePlayer, Estimator};
+use crate::external::ExternalPlayer;
 use crate::extra_phase::ExtraExposedState;
+use crate::heuristic::{HeuristicPlayer, Weights};
+use crate::mlp::{Mlp, MlpPlayer};
 use crate::play_phase::ExposedGameState;
 use std::collections::HashSet;
+use std::fs;
+use std::time::Instant;
 
 pub trait Player {
     fn bidding(&self, state: &BiddingState) -> Option<Contract>;
     fn declare_plan(&self, state: ExtraExposedState)
         -> (Contract, PartnerCondition, HashSet<Card>);
     fn play_action(&self, state: ExposedGameState) -> PlayAction;
+
+    /// Asked of the declarer after taking the kitty, before the plan. A
+    /// declarer who surrenders pays the fixed `RuleSet::surrender_payment`
+    /// and the hand is not played.
+    fn surrender(&self, _state: &ExtraExposedState) -> bool {
+        false
+    }
+
+    /// Asked before each move of the play phase. An accepted claim settles
+    /// the rest of the hand; see `claim::check_claim`.
+    fn claim(&self, _state: &ExposedGameState) -> Option<Claim> {
+        None
+    }
+
+    /// Called for every seat after each action of the play phase, so that a
+    /// player can keep incremental beliefs such as a `FriendInference`.
+    fn observe(&self, _state: &ExposedGameState) {}
+
+    /// Called before each hand the player sits down to.
+    fn new_hand(&self) {}
+
+    /// Called before each request under a time control with when the answer
+    /// is due, so that a player waiting on another process or the network
+    /// can stop waiting in time.
+    fn set_deadline(&self, _deadline: Option<Instant>) {}
+
+    /// Whether the last answer was made up because none came in time. It
+    /// is then replaced by the default move, as on a timeout.
+    fn failed(&self) -> bool {
+        false
+    }
+}
+
+/// Bots selectable by name, e.g. from the command line. `exec:COMMAND`
+/// starts an external bot, with the words of the command as its arguments.
+/// With the `onnx` feature, `onnx:MODEL` plays by a model for every phase
+/// and `onnx:BIDDING,DECLARING,PLAYING` by a model for each. `mlp:PATH`
+/// plays cards by a network saved by `train`, and `cfr:PATH` bids by a
+/// strategy table saved by `cfr`. `heuristic` plays by the default weights
+/// of `HeuristicPlayer`, and `heuristic:PATH` by weights read from a file.
+/// `estimate` bids by the odds of making each contract, as the
+/// `Estimator` works them out with heuristic play-outs.
+pub fn create_player(name: &str) -> Result<Box<dyn Player>, String> {
+    #[cfg(feature = "onnx")]
+    if let Some(paths) = name.strip_prefix("onnx:") {
+        return Ok(Box::new(onnx_player(paths)?));
+    }
+    if let Some(path) = name.strip_prefix("mlp:") {
+        return Ok(Box::new(MlpPlayer::new(Mlp::load(path)?)));
+    }
+    if let Some(path) = name.strip_prefix("cfr:") {
+        return Ok(Box::new(CfrPlayer::new(Strategy::load(path)?)));
+    }
+    if let Some(path) = name.strip_prefix("heuristic:") {
+        return Ok(Box::new(HeuristicPlayer::new(Weights::load(path)?)));
+    }
+    if let Some(command) = name.strip_prefix("exec:") {
+        let command = command.split_whitespace().map(str::to_string).collect();
+        return Ok(Box::new(ExternalPlayer::spawn(command)?));
+    }
+    match name {
+        "random" => Ok(Box::new(RandomPlayer {})),
+        "heuristic" => Ok(Box::new(HeuristicPlayer::new(Weights::default()))),
+        "estimate" => Ok(Box::new(EstimatePlayer::new(Estimator::new("heuristic")?))),
+        _ => Err(format!("unknown player: {}", name)),
+    }
+}
+
+/// Checks that `create_player` takes `name`, without making the player: no
+/// process is started and no model loaded, though the files named must
+/// exist.
+pub fn check_player(name: &str) -> Result<(), String> {
+    let exist = |paths: &str| {
+        paths.split(',').try_for_each(|path| {
+            fs::metadata(path)
+                .map(|_| ())
+                .map_err(|e| format!("{}: {}", path, e))
+        })
+    };
+    #[cfg(feature = "onnx")]
+    if let Some(paths) = name.strip_prefix("onnx:") {
+        return match paths.split(',').count() {
+            1 | 3 => exist(paths),
+            _ => Err("expected one model, or three for bidding, declaring and playing".to_string()),
+        };
+    }
+    for prefix in ["mlp:", "cfr:", "heuristic:"] {
+        if let Some(path) = name.strip_prefix(prefix) {
+            return exist(path);
+        }
+    }
+    if let Some(command) = name.strip_prefix("exec:") {
+        return match command.split_whitespace().next() {
+            Some(_) => Ok(()),
+            None => Err("no bot command".to_string()),
+        };
+    }
+    match name {
+        "random" | "heuristic" | "estimate" => Ok(()),
+        _ => Err(format!("unknown player: {}", name)),
+    }
+}
+
+/// The bots a network client may fill a room with. Names that read files
+/// or start processes are only for whoever runs the engine.
+pub const ROOM_BOTS: [&str; 3] = ["random", "heuristic", "estimate"];
+
+/// A bot of `ROOM_BOTS`, by name.
+pub fn create_room_bot(name: &str) -> Result<Box<dyn Player>, String> {
+    if !ROOM_BOTS.contains(&name) {
+        return Err(format!(
+            "unknown room bot: {} (one of {})",
+            name,
+            ROOM_BOTS.join(", ")
+        ));
+    }
+    create_player(name)
+}
+
+#[cfg(feature = "onnx")]
+fn onnx_player(paths: &str) -> Result<crate::onnx::OnnxPlayer, String> {
+    use crate::encoding::Layout;
+    use crate::onnx::{OnnxModel, OnnxPlayer};
+    use std::rc::Rc;
+
+    let models = paths
+        .split(',')
+        .map(|path| {
+            // A model of the first layout, from before surrenders, is fed
+            // that layout.
+            OnnxModel::load(path, Layout::LATEST)
+                .or_else(|e| OnnxModel::load(path, Layout::V1).map_err(|_| e))
+                .map(Rc::new)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match &models[..] {
+        [model] => Ok(OnnxPlayer::with_models(
+            model.clone(),
+            model.clone(),
+            model.clone(),
+        )),
+        [bidding, declaring, playing] => Ok(OnnxPlayer::with_models(
+            bidding.clone(),
+            declaring.clone(),
+            playing.clone(),
+        )),
+        _ => Err("expected one model, or three for bidding, declaring and playing".to_string()),
+    }
 }
 
 pub struct RandomPlayer {}
@@ -56,13 +211,14 @@ impl RandomPlayer {
 
 impl Player for RandomPlayer {
     fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        // The lowest no-trump bid the table allows.
         if state.curr_contract.is_none() {
             return Some(Contract {
                 suit: None,
-                count: 12,
+                count: state.min_effective_count,
             });
         }
-        return None;
+        None
     }
 
     fn declare_plan(
@@ -83,7 +239,7 @@ impl Player for RandomPlayer {
     }
 
     fn play_action(&self, state: ExposedGameState) -> PlayAction {
-        let random_card = self.playable_cards(&state).iter().next().unwrap().clone();
+        let random_card = *self.playable_cards(&state).iter().next().unwrap();
         match random_card {
             Card::Shaped(_, _) => PlayAction::Hand(random_card),
             Card::Joker => {
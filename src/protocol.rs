@@ -0,0 +1,608 @@
+//! Messages between the game server and its clients, one JSON object per
+//! line. Every message has a `type`; cards, contracts, partner conditions
+//! and actions are written in their plain text forms (`SA`, `S14`,
+//! `card:DA`, `call:C3`).
+
+use crate::bidding_phase::BiddingState;
+use crate::card::{Card, Suit};
+use crate::common::{Claim, Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::event::Event;
+use crate::extra_phase::ExtraExposedState;
+use crate::json::Json;
+use crate::lobby::RoomInfo;
+use crate::play_phase::{ExposedGameState, RoundResult};
+use crate::player::Player;
+use crate::rules::RuleSet;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+pub enum ServerMessage {
+    /// Seated, with the token for taking the seat back after a disconnect.
+    Welcome {
+        seat: PlayerIndex,
+        token: String,
+    },
+    /// The hand so far, for a client taking its seat back: the public events
+    /// and, once the play has begun, the seat's view.
+    Resume {
+        events: Vec<Event>,
+        state: Option<ExposedGameState>,
+    },
+    Event(Event),
+    /// The seat's turn to bid.
+    Bidding(BiddingState),
+    /// The declarer's turn to choose the final contract, friend and discards.
+    Declare(ExtraExposedState),
+    /// The seat's turn to play. When `claims` is set, the seat may claim
+    /// the rest of the hand instead; it is not set again after a rejected
+    /// claim until the next move.
+    Turn {
+        state: ExposedGameState,
+        claims: bool,
+    },
+    /// The seat's view after every action of the play phase.
+    State(ExposedGameState),
+    /// The last message was rejected; the request stands.
+    Error(String),
+    /// The rooms of a lobby, on entering it and when asked.
+    Rooms(Vec<RoomInfo>),
+    /// The room of the seat, whenever it changes.
+    Room(RoomInfo),
+}
+
+#[derive(Clone, Debug)]
+pub enum ClientMessage {
+    /// Joins the server, or with a session token takes a seat back.
+    Join {
+        name: String,
+        token: Option<String>,
+    },
+    Bid(Option<Contract>),
+    Plan {
+        contract: Contract,
+        partner_condition: PartnerCondition,
+        discards: HashSet<Card>,
+    },
+    /// The declarer gives up, as an answer to a declare request.
+    Surrender,
+    Play(PlayAction),
+    /// Claims the rest of the hand, as an answer to a turn request that
+    /// allows it.
+    Claim(Claim),
+    ListRooms,
+    /// Opens a room and sits down in it, at `seat` or the first free one.
+    CreateRoom {
+        name: String,
+        rules: RuleSet,
+        hands: usize,
+        bots: String,
+        seat: Option<PlayerIndex>,
+    },
+    JoinRoom {
+        room: usize,
+        seat: Option<PlayerIndex>,
+    },
+    LeaveRoom,
+    /// Starts a match, with bots in the empty seats.
+    Start,
+}
+
+pub(crate) fn text<T: ToString>(value: T) -> Json {
+    Json::String(value.to_string())
+}
+
+pub(crate) fn parse<T: FromStr<Err = String>>(json: &Json) -> Result<T, String> {
+    json.as_str()?.parse()
+}
+
+pub(crate) fn optional<T>(
+    json: &Json,
+    f: impl Fn(&Json) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    if json.is_null() {
+        Ok(None)
+    } else {
+        f(json).map(Some)
+    }
+}
+
+pub(crate) fn player(json: &Json) -> Result<PlayerIndex, String> {
+    match json.as_u64()? {
+        p @ 0..=4 => Ok(p as PlayerIndex),
+        p => Err(format!("invalid player: {}", p)),
+    }
+}
+
+pub(crate) fn round(json: &Json) -> Result<u8, String> {
+    match json.as_u64()? {
+        n @ 0..=10 => Ok(n as u8),
+        n => Err(format!("invalid round: {}", n)),
+    }
+}
+
+pub fn cards_to_json(cards: &HashSet<Card>) -> Json {
+    let mut cards = cards.iter().collect::<Vec<_>>();
+    cards.sort();
+    Json::Array(cards.into_iter().map(text).collect())
+}
+
+pub fn cards_from_json(json: &Json) -> Result<HashSet<Card>, String> {
+    json.as_array()?.iter().map(parse).collect()
+}
+
+fn round_result_to_json(result: &RoundResult) -> Json {
+    Json::object(vec![
+        ("winner", result.winner.into()),
+        (
+            "submitted",
+            Json::Array(result.submitted.iter().map(text).collect()),
+        ),
+        ("round_starter", result.round_starter.into()),
+        ("round_suit", result.round_suit.map(text).into()),
+        ("joker_called", result.joker_called.into()),
+    ])
+}
+
+fn round_result_from_json(json: &Json) -> Result<RoundResult, String> {
+    let submitted = json
+        .field("submitted")?
+        .as_array()?
+        .iter()
+        .map(parse)
+        .collect::<Result<Vec<Card>, _>>()?;
+    if submitted.len() != 5 {
+        return Err("a round needs 5 cards".to_string());
+    }
+    Ok(RoundResult {
+        winner: player(json.field("winner")?)?,
+        submitted,
+        round_starter: player(json.field("round_starter")?)?,
+        round_suit: optional(json.field("round_suit")?, parse::<Suit>)?,
+        joker_called: json.field("joker_called")?.as_bool()?,
+    })
+}
+
+pub fn rules_to_json(rules: &RuleSet) -> Json {
+    Json::object(vec![
+        ("min_pledge", rules.min_pledge.into()),
+        ("run_multiplier", rules.run_multiplier.into()),
+        ("back_run_multiplier", rules.back_run_multiplier.into()),
+        ("back_run_max_score", rules.back_run_max_score.into()),
+        ("no_trump_multiplier", rules.no_trump_multiplier.into()),
+        ("no_friend_multiplier", rules.no_friend_multiplier.into()),
+        ("surrender_payment", rules.surrender_payment.into()),
+    ])
+}
+
+/// Fields left out keep their default values. Fails on a value out of the
+/// field's range.
+pub fn rules_from_json(json: &Json) -> Result<RuleSet, String> {
+    let out_of_range =
+        |name: &str, low: i64, high: i64| format!("{} must be between {} and {}", name, low, high);
+    let mut rules = RuleSet::default();
+    for (name, value, low, high) in [
+        ("min_pledge", &mut rules.min_pledge, 1, 20),
+        ("back_run_max_score", &mut rules.back_run_max_score, 0, 20),
+    ] {
+        if let Some(v) = json.get(name) {
+            *value = u8::try_from(v.as_u64()?)
+                .ok()
+                .filter(|v| (low..=high).contains(v))
+                .ok_or_else(|| out_of_range(name, low.into(), high.into()))?;
+        }
+    }
+    for (name, value, low, high) in [
+        ("run_multiplier", &mut rules.run_multiplier, 1, 100),
+        (
+            "back_run_multiplier",
+            &mut rules.back_run_multiplier,
+            1,
+            100,
+        ),
+        (
+            "no_trump_multiplier",
+            &mut rules.no_trump_multiplier,
+            1,
+            100,
+        ),
+        (
+            "no_friend_multiplier",
+            &mut rules.no_friend_multiplier,
+            1,
+            100,
+        ),
+        ("surrender_payment", &mut rules.surrender_payment, 0, 100),
+    ] {
+        if let Some(v) = json.get(name) {
+            *value = i32::try_from(v.as_i64()?)
+                .ok()
+                .filter(|v| (low..=high).contains(v))
+                .ok_or_else(|| out_of_range(name, low.into(), high.into()))?;
+        }
+    }
+    Ok(rules)
+}
+
+pub fn bidding_state_to_json(state: &BiddingState) -> Json {
+    Json::object(vec![
+        ("hand", cards_to_json(&state.hand)),
+        ("contract", state.curr_contract.map(text).into()),
+        ("min_effective_count", state.min_effective_count.into()),
+        ("players_remaining", state.players_remaining.into()),
+    ])
+}
+
+pub fn bidding_state_from_json(json: &Json) -> Result<BiddingState, String> {
+    Ok(BiddingState {
+        hand: cards_from_json(json.field("hand")?)?,
+        curr_contract: optional(json.field("contract")?, parse)?,
+        min_effective_count: u8::try_from(json.field("min_effective_count")?.as_u64()?)
+            .map_err(|_| "min_effective_count out of range".to_string())?,
+        players_remaining: json.field("players_remaining")?.as_u64()? as usize,
+    })
+}
+
+pub fn extra_state_to_json(state: &ExtraExposedState) -> Json {
+    Json::object(vec![
+        ("hand", cards_to_json(&state.hand)),
+        ("contract", text(state.contract)),
+    ])
+}
+
+pub fn extra_state_from_json(json: &Json) -> Result<ExtraExposedState, String> {
+    Ok(ExtraExposedState {
+        hand: cards_from_json(json.field("hand")?)?,
+        contract: parse(json.field("contract")?)?,
+    })
+}
+
+pub fn play_state_to_json(state: &ExposedGameState) -> Json {
+    Json::object(vec![
+        ("hand", cards_to_json(&state.hand)),
+        ("declarer", state.declarer.into()),
+        ("contract", text(state.contract)),
+        ("partner_condition", text(state.partner_condition)),
+        (
+            "discarded",
+            state.discarded.as_ref().map_or(Json::Null, cards_to_json),
+        ),
+        ("partner_revealed", state.partner_revealed.into()),
+        ("round", state.round.into()),
+        ("joker_called", state.joker_called.into()),
+        (
+            "submitted",
+            Json::Array(state.submitted.iter().map(|c| c.map(text).into()).collect()),
+        ),
+        ("round_starter", state.round_starter.into()),
+        ("round_suit", state.round_suit.map(text).into()),
+        (
+            "round_results",
+            Json::Array(
+                state
+                    .round_results
+                    .iter()
+                    .map(round_result_to_json)
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+pub fn play_state_from_json(json: &Json) -> Result<ExposedGameState, String> {
+    let submitted = json
+        .field("submitted")?
+        .as_array()?
+        .iter()
+        .map(|c| optional(c, parse))
+        .collect::<Result<Vec<Option<Card>>, _>>()?;
+    if submitted.len() != 5 {
+        return Err("submitted needs 5 entries".to_string());
+    }
+    Ok(ExposedGameState {
+        hand: cards_from_json(json.field("hand")?)?,
+        declarer: player(json.field("declarer")?)?,
+        contract: parse(json.field("contract")?)?,
+        partner_condition: parse(json.field("partner_condition")?)?,
+        discarded: optional(json.field("discarded")?, cards_from_json)?,
+        partner_revealed: optional(json.field("partner_revealed")?, player)?,
+        round: round(json.field("round")?)?,
+        joker_called: json.field("joker_called")?.as_bool()?,
+        submitted,
+        round_starter: player(json.field("round_starter")?)?,
+        round_suit: optional(json.field("round_suit")?, parse)?,
+        round_results: json
+            .field("round_results")?
+            .as_array()?
+            .iter()
+            .map(round_result_from_json)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// The bid in an answer to a bidding request, if it is a valid one.
+pub fn accept_bid(
+    state: &BiddingState,
+    message: ClientMessage,
+) -> Result<Option<Contract>, String> {
+    match message {
+        ClientMessage::Bid(Some(contract))
+            if contract.effective_count() <= state.min_effective_count || contract.count > 20 =>
+        {
+            Err(format!("invalid bid: {}", contract))
+        }
+        ClientMessage::Bid(bid) => Ok(bid),
+        _ => Err("expected a bid".to_string()),
+    }
+}
+
+/// The plan in an answer to a declare request, if it is a valid one, or
+/// `None` when the declarer surrenders.
+pub fn accept_plan(
+    state: &ExtraExposedState,
+    message: ClientMessage,
+) -> Result<Option<(Contract, PartnerCondition, HashSet<Card>)>, String> {
+    match message {
+        ClientMessage::Plan {
+            contract,
+            partner_condition,
+            discards,
+        } => {
+            if contract.effective_count() < state.contract.effective_count() || contract.count > 20
+            {
+                Err(format!("invalid contract: {}", contract))
+            } else if discards.len() != 3 || !state.hand.is_superset(&discards) {
+                Err("discard three cards of your hand".to_string())
+            } else {
+                Ok(Some((contract, partner_condition, discards)))
+            }
+        }
+        ClientMessage::Surrender => Ok(None),
+        _ => Err("expected a plan".to_string()),
+    }
+}
+
+/// The action in an answer to a turn request, if it is a legal one.
+pub fn accept_action(
+    state: &ExposedGameState,
+    message: ClientMessage,
+) -> Result<PlayAction, String> {
+    match message {
+        ClientMessage::Play(action) if state.legal_actions().contains(&action) => Ok(action),
+        ClientMessage::Play(action) => Err(format!("illegal action: {}", action)),
+        _ => Err("expected an action".to_string()),
+    }
+}
+
+impl ServerMessage {
+    /// How `player` answers this message, if it is a request.
+    pub fn answer(&self, player: &dyn Player) -> Option<ClientMessage> {
+        match self {
+            ServerMessage::Bidding(state) => Some(ClientMessage::Bid(player.bidding(state))),
+            ServerMessage::Declare(state) if player.surrender(state) => {
+                Some(ClientMessage::Surrender)
+            }
+            ServerMessage::Declare(state) => {
+                let (contract, partner_condition, discards) = player.declare_plan(state.clone());
+                Some(ClientMessage::Plan {
+                    contract,
+                    partner_condition,
+                    discards,
+                })
+            }
+            ServerMessage::Turn { state, claims } => match player.claim(state) {
+                Some(claim) if *claims => Some(ClientMessage::Claim(claim)),
+                _ => Some(ClientMessage::Play(player.play_action(state.clone()))),
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        match self {
+            ServerMessage::Welcome { seat, token } => Json::object(vec![
+                ("type", "welcome".into()),
+                ("seat", (*seat).into()),
+                ("token", token.as_str().into()),
+            ]),
+            ServerMessage::Resume { events, state } => Json::object(vec![
+                ("type", "resume".into()),
+                (
+                    "events",
+                    Json::Array(events.iter().map(Event::to_json).collect()),
+                ),
+                (
+                    "state",
+                    state.as_ref().map_or(Json::Null, play_state_to_json),
+                ),
+            ]),
+            ServerMessage::Event(event) => {
+                Json::object(vec![("type", "event".into()), ("event", event.to_json())])
+            }
+            ServerMessage::Bidding(state) => Json::object(vec![
+                ("type", "bidding".into()),
+                ("state", bidding_state_to_json(state)),
+            ]),
+            ServerMessage::Declare(state) => Json::object(vec![
+                ("type", "declare".into()),
+                ("state", extra_state_to_json(state)),
+            ]),
+            // Legal actions are included for clients that do not know the rules.
+            ServerMessage::Turn { state, claims } => Json::object(vec![
+                ("type", "turn".into()),
+                ("state", play_state_to_json(state)),
+                ("claims", (*claims).into()),
+                (
+                    "legal_actions",
+                    Json::Array(state.legal_actions().into_iter().map(text).collect()),
+                ),
+            ]),
+            ServerMessage::State(state) => Json::object(vec![
+                ("type", "state".into()),
+                ("state", play_state_to_json(state)),
+            ]),
+            ServerMessage::Error(message) => Json::object(vec![
+                ("type", "error".into()),
+                ("message", message.as_str().into()),
+            ]),
+            ServerMessage::Rooms(rooms) => Json::object(vec![
+                ("type", "rooms".into()),
+                (
+                    "rooms",
+                    Json::Array(rooms.iter().map(RoomInfo::to_json).collect()),
+                ),
+            ]),
+            ServerMessage::Room(room) => {
+                Json::object(vec![("type", "room".into()), ("room", room.to_json())])
+            }
+        }
+    }
+
+    pub fn from_json(json: &Json) -> Result<ServerMessage, String> {
+        match json.field("type")?.as_str()? {
+            "welcome" => Ok(ServerMessage::Welcome {
+                seat: player(json.field("seat")?)?,
+                token: json.field("token")?.as_str()?.to_string(),
+            }),
+            "resume" => Ok(ServerMessage::Resume {
+                events: json
+                    .field("events")?
+                    .as_array()?
+                    .iter()
+                    .map(Event::from_json)
+                    .collect::<Result<_, _>>()?,
+                state: optional(json.field("state")?, play_state_from_json)?,
+            }),
+            "event" => Ok(ServerMessage::Event(Event::from_json(
+                json.field("event")?,
+            )?)),
+            "bidding" => Ok(ServerMessage::Bidding(bidding_state_from_json(
+                json.field("state")?,
+            )?)),
+            "declare" => Ok(ServerMessage::Declare(extra_state_from_json(
+                json.field("state")?,
+            )?)),
+            "turn" => Ok(ServerMessage::Turn {
+                state: play_state_from_json(json.field("state")?)?,
+                claims: json.get("claims").map_or(Ok(false), Json::as_bool)?,
+            }),
+            "state" => Ok(ServerMessage::State(play_state_from_json(
+                json.field("state")?,
+            )?)),
+            "error" => Ok(ServerMessage::Error(
+                json.field("message")?.as_str()?.to_string(),
+            )),
+            "rooms" => Ok(ServerMessage::Rooms(
+                json.field("rooms")?
+                    .as_array()?
+                    .iter()
+                    .map(RoomInfo::from_json)
+                    .collect::<Result<_, _>>()?,
+            )),
+            "room" => Ok(ServerMessage::Room(RoomInfo::from_json(
+                json.field("room")?,
+            )?)),
+            kind => Err(format!("unknown message type: {}", kind)),
+        }
+    }
+}
+
+impl ClientMessage {
+    pub fn to_json(&self) -> Json {
+        match self {
+            ClientMessage::Join { name, token } => Json::object(vec![
+                ("type", "join".into()),
+                ("name", name.as_str().into()),
+                ("token", token.clone().into()),
+            ]),
+            ClientMessage::Bid(contract) => Json::object(vec![
+                ("type", "bid".into()),
+                ("contract", contract.map(text).into()),
+            ]),
+            ClientMessage::Plan {
+                contract,
+                partner_condition,
+                discards,
+            } => Json::object(vec![
+                ("type", "plan".into()),
+                ("contract", text(contract)),
+                ("partner_condition", text(partner_condition)),
+                ("discards", cards_to_json(discards)),
+            ]),
+            ClientMessage::Surrender => Json::object(vec![("type", "surrender".into())]),
+            ClientMessage::Play(action) => {
+                Json::object(vec![("type", "play".into()), ("action", text(action))])
+            }
+            ClientMessage::Claim(claim) => {
+                Json::object(vec![("type", "claim".into()), ("claim", text(claim))])
+            }
+            ClientMessage::ListRooms => Json::object(vec![("type", "list_rooms".into())]),
+            ClientMessage::CreateRoom {
+                name,
+                rules,
+                hands,
+                bots,
+                seat,
+            } => Json::object(vec![
+                ("type", "create_room".into()),
+                ("name", name.as_str().into()),
+                ("rules", rules_to_json(rules)),
+                ("hands", (*hands).into()),
+                ("bots", bots.as_str().into()),
+                ("seat", (*seat).into()),
+            ]),
+            ClientMessage::JoinRoom { room, seat } => Json::object(vec![
+                ("type", "join_room".into()),
+                ("room", (*room).into()),
+                ("seat", (*seat).into()),
+            ]),
+            ClientMessage::LeaveRoom => Json::object(vec![("type", "leave_room".into())]),
+            ClientMessage::Start => Json::object(vec![("type", "start".into())]),
+        }
+    }
+
+    pub fn from_json(json: &Json) -> Result<ClientMessage, String> {
+        match json.field("type")?.as_str()? {
+            "join" => Ok(ClientMessage::Join {
+                name: json.field("name")?.as_str()?.to_string(),
+                token: optional(json.get("token").unwrap_or(&Json::Null), |t| {
+                    t.as_str().map(str::to_string)
+                })?,
+            }),
+            "bid" => Ok(ClientMessage::Bid(optional(
+                json.field("contract")?,
+                parse,
+            )?)),
+            "plan" => Ok(ClientMessage::Plan {
+                contract: parse(json.field("contract")?)?,
+                partner_condition: parse(json.field("partner_condition")?)?,
+                discards: cards_from_json(json.field("discards")?)?,
+            }),
+            "surrender" => Ok(ClientMessage::Surrender),
+            "play" => Ok(ClientMessage::Play(parse(json.field("action")?)?)),
+            "claim" => Ok(ClientMessage::Claim(parse(json.field("claim")?)?)),
+            "list_rooms" => Ok(ClientMessage::ListRooms),
+            // Everything but the name may be left to the server.
+            "create_room" => Ok(ClientMessage::CreateRoom {
+                name: json.field("name")?.as_str()?.to_string(),
+                rules: match json.get("rules") {
+                    Some(rules) => rules_from_json(rules)?,
+                    None => RuleSet::default(),
+                },
+                hands: json.get("hands").map_or(Ok(5), |h| h.as_u64())? as usize,
+                bots: json
+                    .get("bots")
+                    .map_or(Ok("random"), |b| b.as_str())?
+                    .to_string(),
+                seat: optional(json.get("seat").unwrap_or(&Json::Null), player)?,
+            }),
+            "join_room" => Ok(ClientMessage::JoinRoom {
+                room: json.field("room")?.as_u64()? as usize,
+                seat: optional(json.get("seat").unwrap_or(&Json::Null), player)?,
+            }),
+            "leave_room" => Ok(ClientMessage::LeaveRoom),
+            "start" => Ok(ClientMessage::Start),
+            kind => Err(format!("unknown message type: {}", kind)),
+        }
+    }
+}
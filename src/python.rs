@@ -0,0 +1,257 @@
+//! Python bindings, built with the `python` feature:
+//!
+//! ```python
+//! from mighty_rs import MightyEnv, ACTION_COUNT
+//!
+//! env = MightyEnv({"min_pledge": 13})
+//! observations, mask = env.reset(seed=7)
+//! while True:
+//!     action = next(i for i, legal in enumerate(mask) if legal)
+//!     observations, mask, rewards, done = env.step(action)
+//!     if done:
+//!         break
+//! ```
+//!
+//! Observations are dicts, one per seat, as given by `MightyEnv::observation`;
+//! `features` gives a seat's view as floats in the layout of `encoding`,
+//! `OBSERVATION_SIZE` long.
+//! Rules are a dict in the form of the network protocol.
+
+use crate::encoding::Layout;
+use crate::env::{MightyEnv, VecEnv};
+use crate::json::Json;
+use crate::protocol::rules_from_json;
+use crate::rules::RuleSet;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+
+fn to_py<'py>(py: Python<'py>, json: &Json) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match json {
+        Json::Null => py.None().into_bound(py),
+        Json::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Json::Number(n) if n.fract() == 0.0 => (*n as i64).into_pyobject(py)?.into_any(),
+        Json::Number(n) => n.into_pyobject(py)?.into_any(),
+        Json::String(s) => PyString::new(py, s).into_any(),
+        Json::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| to_py(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any()
+        }
+        Json::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key, to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+fn from_py(value: &Bound<'_, PyAny>) -> PyResult<Json> {
+    if value.is_none() {
+        Ok(Json::Null)
+    } else if let Ok(b) = value.cast::<PyBool>() {
+        Ok(Json::Bool(b.is_true()))
+    } else if value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>() {
+        Ok(Json::Number(value.extract()?))
+    } else if let Ok(s) = value.cast::<PyString>() {
+        Ok(Json::String(s.to_str()?.to_string()))
+    } else if let Ok(dict) = value.cast::<PyDict>() {
+        let fields = dict
+            .iter()
+            .map(|(k, v)| Ok((k.extract::<String>()?, from_py(&v)?)))
+            .collect::<PyResult<_>>()?;
+        Ok(Json::Object(fields))
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        let items = value
+            .try_iter()?
+            .map(|item| from_py(&item?))
+            .collect::<PyResult<_>>()?;
+        Ok(Json::Array(items))
+    } else {
+        Err(PyValueError::new_err(format!("not JSON data: {}", value)))
+    }
+}
+
+fn rules(rules: Option<&Bound<'_, PyDict>>) -> PyResult<RuleSet> {
+    match rules {
+        Some(rules) => rules_from_json(&from_py(rules.as_any())?).map_err(PyValueError::new_err),
+        None => Ok(RuleSet::default()),
+    }
+}
+
+fn observations<'py>(py: Python<'py>, env: &MightyEnv) -> PyResult<Bound<'py, PyList>> {
+    let seats = (0..5)
+        .map(|seat| to_py(py, &env.observation(seat)))
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, seats)
+}
+
+/// One hand of Mighty, stepped one decision at a time.
+#[pyclass(name = "MightyEnv", module = "mighty_rs")]
+struct PyMightyEnv {
+    env: MightyEnv,
+}
+
+#[pymethods]
+impl PyMightyEnv {
+    #[new]
+    #[pyo3(signature = (rules=None))]
+    fn new(rules: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        Ok(PyMightyEnv {
+            env: MightyEnv::new(self::rules(rules)?),
+        })
+    }
+
+    /// Deals a hand. Returns the observations and the legal action mask.
+    #[pyo3(signature = (seed=0))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: u64) -> PyResult<Bound<'py, PyTuple>> {
+        self.env.reset(seed);
+        (observations(py, &self.env)?, self.env.legal_mask()).into_pyobject(py)
+    }
+
+    /// Takes a decision for the player to move. Returns the observations,
+    /// the legal action mask, each seat's reward and whether the hand is
+    /// over.
+    fn step<'py>(&mut self, py: Python<'py>, action: usize) -> PyResult<Bound<'py, PyTuple>> {
+        self.env.step(action).map_err(PyValueError::new_err)?;
+        let rewards = self
+            .env
+            .payouts()
+            .iter()
+            .map(|&p| p as f64)
+            .collect::<Vec<_>>();
+        (
+            observations(py, &self.env)?,
+            self.env.legal_mask(),
+            rewards,
+            self.env.done(),
+        )
+            .into_pyobject(py)
+    }
+
+    fn legal_actions(&self) -> Vec<usize> {
+        self.env.legal_actions()
+    }
+
+    fn observation<'py>(&self, py: Python<'py>, seat: usize) -> PyResult<Bound<'py, PyAny>> {
+        if seat >= 5 {
+            return Err(PyValueError::new_err(format!("invalid seat: {}", seat)));
+        }
+        to_py(py, &self.env.observation(seat))
+    }
+
+    /// The seat's view in the latest encoding layout.
+    fn features(&self, seat: usize) -> PyResult<Vec<f32>> {
+        if seat >= 5 {
+            return Err(PyValueError::new_err(format!("invalid seat: {}", seat)));
+        }
+        Ok(self.env.features(seat))
+    }
+
+    #[getter]
+    fn to_move(&self) -> Option<usize> {
+        self.env.to_move()
+    }
+
+    #[getter]
+    fn done(&self) -> bool {
+        self.env.done()
+    }
+}
+
+/// Many hands stepped together. A finished hand is dealt again at once, so
+/// the observations after it are those of the next hand. Masks come as one
+/// `bytes` of 0s and 1s, a row of `ACTION_COUNT` per environment.
+/// `features` gives every seat's view as little-endian float32s, one row of
+/// `OBSERVATION_SIZE` per seat and five rows per environment, ready for
+/// `numpy.frombuffer`.
+#[pyclass(name = "VecEnv", module = "mighty_rs")]
+struct PyVecEnv {
+    envs: VecEnv,
+}
+
+impl PyVecEnv {
+    fn observations<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let envs = self
+            .envs
+            .envs
+            .iter()
+            .map(|env| observations(py, env))
+            .collect::<PyResult<Vec<_>>>()?;
+        PyList::new(py, envs)
+    }
+
+    fn masks<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let masks = self
+            .envs
+            .envs
+            .iter()
+            .flat_map(|env| env.legal_mask())
+            .map(u8::from)
+            .collect::<Vec<_>>();
+        PyBytes::new(py, &masks)
+    }
+}
+
+#[pymethods]
+impl PyVecEnv {
+    #[new]
+    #[pyo3(signature = (count, rules=None))]
+    fn new(count: usize, rules: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        Ok(PyVecEnv {
+            envs: VecEnv::new(count, self::rules(rules)?),
+        })
+    }
+
+    fn features<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let features = self
+            .envs
+            .envs
+            .iter()
+            .flat_map(|env| (0..5).flat_map(|seat| env.features(seat)))
+            .flat_map(f32::to_le_bytes)
+            .collect::<Vec<_>>();
+        PyBytes::new(py, &features)
+    }
+
+    fn __len__(&self) -> usize {
+        self.envs.envs.len()
+    }
+
+    /// Deals environment `i` from `seed + i`. Returns the observations and
+    /// the masks.
+    #[pyo3(signature = (seed=0))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: u64) -> PyResult<Bound<'py, PyTuple>> {
+        self.envs.reset(seed);
+        (self.observations(py)?, self.masks(py)).into_pyobject(py)
+    }
+
+    /// Steps every environment. Returns the observations, the masks, the
+    /// rewards and whether each hand ended.
+    fn step<'py>(&mut self, py: Python<'py>, actions: Vec<usize>) -> PyResult<Bound<'py, PyTuple>> {
+        let envs = &mut self.envs;
+        let results = py
+            .detach(|| envs.step(&actions))
+            .map_err(PyValueError::new_err)?;
+        let (rewards, dones): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .map(|(payouts, done)| (payouts.into_iter().map(f64::from).collect::<Vec<_>>(), done))
+            .unzip();
+        (self.observations(py)?, self.masks(py), rewards, dones).into_pyobject(py)
+    }
+}
+
+#[pymodule]
+fn mighty_rs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    let layout = Layout::LATEST;
+    module.add("ACTION_COUNT", layout.action_count())?;
+    module.add("OBSERVATION_SIZE", layout.observation_size())?;
+    module.add("LAYOUT_VERSION", layout.version())?;
+    module.add_class::<PyMightyEnv>()?;
+    module.add_class::<PyVecEnv>()?;
+    Ok(())
+}
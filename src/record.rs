@@ -0,0 +1,310 @@
+use crate::bidding_phase::PledgePhase;
+use crate::card::Card;
+use crate::common::{Claim, Contract, Hand, PartnerCondition, PlayAction, PlayerIndex};
+use crate::extra_phase::ExtraPhase;
+use crate::play_phase::{EarlyEnd, PlayPhase};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// A hand from the deal to the last card played, in a line-based text form:
+///
+/// ```text
+/// start 0
+/// min_pledge 13
+/// early_end settled:10
+/// hand 0 SA SK ...
+/// bottom D2 H5 C9
+/// bid 0 S13
+/// bid 1 pass
+/// plan S14 card:DA H2 H3 D4
+/// timeout 1
+/// note blunder: S2 loses 3.0 points against SA
+/// play 1 S2
+/// ```
+///
+/// A declarer who gives up has a `surrender` line in place of the plan, and
+/// the record ends there. A `timeout` line marks the move after it as made
+/// for a player who ran out of time, and a `note` line comments on the move
+/// after it. A record may stop anywhere, which makes it a saved position
+/// too. The `early_end` line is left out when every round is played.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GameRecord {
+    pub start_player: PlayerIndex,
+    pub min_pledge: u8,
+    pub early_end: EarlyEnd,
+    /// Dealt hands, before the declarer takes the bottom cards.
+    pub hands: Vec<Hand>,
+    pub bottom: HashSet<Card>,
+    pub bids: Vec<(PlayerIndex, Option<Contract>)>,
+    pub plan: Option<(Contract, PartnerCondition, HashSet<Card>)>,
+    /// Whether the declarer surrendered instead of making a plan.
+    pub surrendered: bool,
+    pub actions: Vec<(PlayerIndex, PlayAction)>,
+    /// Players who ran out of time, each with the number of moves recorded
+    /// before the move made for them.
+    pub timeouts: Vec<(PlayerIndex, usize)>,
+    /// Comments, each with the number of moves recorded before the move it
+    /// is about.
+    pub notes: Vec<(usize, String)>,
+}
+
+impl GameRecord {
+    /// Starts a record of a freshly dealt hand.
+    pub fn new(game: &PledgePhase) -> Self {
+        assert!(game.call_history.is_empty());
+        GameRecord {
+            start_player: game.turn_player(),
+            min_pledge: game.min_effective_count + 1,
+            early_end: EarlyEnd::Never,
+            hands: game.hands.clone(),
+            bottom: game.bottom.clone(),
+            bids: vec![],
+            plan: None,
+            surrendered: false,
+            actions: vec![],
+            timeouts: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Bids, plan and card plays recorded so far.
+    pub fn moves(&self) -> usize {
+        self.bids.len()
+            + self.plan.is_some() as usize
+            + self.surrendered as usize
+            + self.actions.len()
+    }
+
+    /// Notes that the next move of `player` is made for them.
+    pub fn timeout(&mut self, player: PlayerIndex) {
+        self.timeouts.push((player, self.moves()));
+    }
+
+    /// Notes a claim by `player` before their next move.
+    pub fn claim(&mut self, player: PlayerIndex, claim: Claim, accepted: bool) {
+        let outcome = if accepted { "accepted" } else { "rejected" };
+        let note = format!("player {} claims {}, {}", player, claim, outcome);
+        self.notes.push((self.moves(), note));
+    }
+
+    pub fn pledge_phase(&self) -> PledgePhase {
+        PledgePhase::from_deal(
+            self.start_player,
+            self.min_pledge,
+            self.hands.clone(),
+            self.bottom.clone(),
+        )
+    }
+
+    /// The hand after the pledge, with the kitty taken. Fails on an illegal
+    /// bid, or when the pledge was cancelled or is not over.
+    pub fn extra_phase(&self) -> Result<ExtraPhase, String> {
+        let dealt = self.hands.iter().flatten().chain(&self.bottom);
+        if self.hands.iter().any(|h| h.len() != 10)
+            || self.bottom.len() != 3
+            || dealt.collect::<HashSet<_>>().len() != 53
+        {
+            return Err("invalid deal".to_string());
+        }
+
+        let mut game = self.pledge_phase();
+        for &(player, bid) in &self.bids {
+            if game.pledge_done() || game.turn_player() != player {
+                return Err(format!("bid out of turn by player {}", player));
+            }
+            if bid.is_some_and(|c| c.effective_count() <= game.min_effective_count) {
+                return Err(format!("bid too low by player {}", player));
+            }
+            game.player_bids(player, bid);
+        }
+        if game.cancelled() {
+            return Err("the pledge was cancelled".to_string());
+        }
+        if !game.pledge_done() {
+            return Err("the record ends during the pledge".to_string());
+        }
+        Ok(ExtraPhase::from_pledge(game))
+    }
+
+    /// The play phase after the first `actions` recorded actions. Fails on
+    /// an illegal move, or when the record ends before play starts.
+    pub fn play_phase(&self, actions: usize) -> Result<PlayPhase, String> {
+        let mut game = self.extra_phase()?;
+        if self.surrendered {
+            return Err("the declarer surrendered".to_string());
+        }
+        let (contract, condition, discards) =
+            self.plan.clone().ok_or("the record ends before the plan")?;
+        if contract.effective_count() < game.contract.effective_count()
+            || discards.len() != 3
+            || !game.hands[game.declarer].is_superset(&discards)
+        {
+            return Err("invalid plan".to_string());
+        }
+        let mut game = game.submit_plan(contract, condition, discards);
+        game.set_early_end(self.early_end);
+
+        for &(player, action) in self.actions.iter().take(actions) {
+            if game.turn_player() != Some(player) || !game.legal_actions(player).contains(&action) {
+                return Err(format!(
+                    "illegal action in round {} by player {}: {}",
+                    game.round + 1,
+                    player,
+                    action
+                ));
+            }
+            game.player_acts(player, action);
+        }
+        Ok(game)
+    }
+}
+
+pub(crate) fn cards_text(cards: &HashSet<Card>) -> String {
+    let mut cards = cards.iter().collect::<Vec<_>>();
+    cards.sort();
+    cards
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut moves = 0;
+        let mut before_move = |f: &mut fmt::Formatter<'_>| {
+            for (player, _) in self.timeouts.iter().filter(|(_, m)| *m == moves) {
+                writeln!(f, "timeout {}", player)?;
+            }
+            for (_, note) in self.notes.iter().filter(|(m, _)| *m == moves) {
+                writeln!(f, "note {}", note)?;
+            }
+            moves += 1;
+            Ok(())
+        };
+        writeln!(f, "start {}", self.start_player)?;
+        writeln!(f, "min_pledge {}", self.min_pledge)?;
+        if self.early_end != EarlyEnd::Never {
+            writeln!(f, "early_end {}", self.early_end)?;
+        }
+        for (player, hand) in self.hands.iter().enumerate() {
+            writeln!(f, "hand {} {}", player, cards_text(hand))?;
+        }
+        writeln!(f, "bottom {}", cards_text(&self.bottom))?;
+        for (player, bid) in &self.bids {
+            before_move(f)?;
+            match bid {
+                Some(contract) => writeln!(f, "bid {} {}", player, contract)?,
+                None => writeln!(f, "bid {} pass", player)?,
+            }
+        }
+        if let Some((contract, condition, discards)) = &self.plan {
+            before_move(f)?;
+            writeln!(
+                f,
+                "plan {} {} {}",
+                contract,
+                condition,
+                cards_text(discards)
+            )?;
+        }
+        if self.surrendered {
+            before_move(f)?;
+            writeln!(f, "surrender")?;
+        }
+        for (player, action) in &self.actions {
+            before_move(f)?;
+            writeln!(f, "play {} {}", player, action)?;
+        }
+        before_move(f)
+    }
+}
+
+fn parse_player(s: Option<&str>) -> Result<PlayerIndex, String> {
+    match s.map(str::parse) {
+        Some(Ok(p @ 0..=4)) => Ok(p),
+        _ => Err(format!("invalid player: {}", s.unwrap_or(""))),
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    /// Parses a record, checking only its syntax; moves are checked when
+    /// the record is played back.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord {
+            start_player: 0,
+            min_pledge: 13,
+            early_end: EarlyEnd::Never,
+            hands: vec![Hand::new(); 5],
+            bottom: HashSet::new(),
+            bids: vec![],
+            plan: None,
+            surrendered: false,
+            actions: vec![],
+            timeouts: vec![],
+            notes: vec![],
+        };
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            let mut words = line.split_whitespace();
+            let cards = |words: std::str::SplitWhitespace| {
+                words.map(str::parse).collect::<Result<HashSet<Card>, _>>()
+            };
+            match words.next().unwrap() {
+                "start" => record.start_player = parse_player(words.next()).map_err(error)?,
+                "min_pledge" => {
+                    record.min_pledge = words
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| (1..=20).contains(n))
+                        .ok_or_else(|| error("invalid minimum pledge".to_string()))?
+                }
+                "early_end" => {
+                    record.early_end = words.next().unwrap_or("").parse().map_err(error)?
+                }
+                "hand" => {
+                    let player = parse_player(words.next()).map_err(error)?;
+                    record.hands[player] = cards(words).map_err(error)?;
+                }
+                "bottom" => record.bottom = cards(words).map_err(error)?,
+                "bid" => {
+                    let player = parse_player(words.next()).map_err(error)?;
+                    let bid = match words.next() {
+                        Some("pass") => None,
+                        Some(contract) => Some(contract.parse().map_err(error)?),
+                        None => return Err(error("missing bid".to_string())),
+                    };
+                    record.bids.push((player, bid));
+                }
+                "plan" => {
+                    let contract = words.next().unwrap_or("").parse().map_err(error)?;
+                    let condition = words.next().unwrap_or("").parse().map_err(error)?;
+                    record.plan = Some((contract, condition, cards(words).map_err(error)?));
+                }
+                "surrender" => record.surrendered = true,
+                "play" => {
+                    let player = parse_player(words.next()).map_err(error)?;
+                    let action = words.next().unwrap_or("").parse().map_err(error)?;
+                    record.actions.push((player, action));
+                }
+                "timeout" => {
+                    let player = parse_player(words.next()).map_err(error)?;
+                    record.timeout(player);
+                }
+                "note" => {
+                    let note = line["note".len()..].trim().to_string();
+                    record.notes.push((record.moves(), note));
+                }
+                word => return Err(error(format!("unknown entry: {}", word))),
+            }
+        }
+        Ok(record)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::common::{PartnerCondition, PlayerIndex};
+use crate::extra_phase::Surrender;
+use crate::play_phase::PlayPhase;
+
+/// Table rules that are not fixed by the engine itself.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RuleSet {
+    pub min_pledge: u8,
+    /// Declarer team taking every point card.
+    pub run_multiplier: i32,
+    /// Declarer team failing with at most `back_run_max_score` points.
+    pub back_run_multiplier: i32,
+    pub back_run_max_score: u8,
+    pub no_trump_multiplier: i32,
+    pub no_friend_multiplier: i32,
+    /// Paid by a declarer who surrenders to each other player.
+    pub surrender_payment: i32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            min_pledge: 13,
+            run_multiplier: 2,
+            back_run_multiplier: 2,
+            back_run_max_score: 10,
+            no_trump_multiplier: 2,
+            no_friend_multiplier: 2,
+            surrender_payment: 2,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Settlement {
+    /// Chips won (positive) or paid (negative) by each player, summing to 0.
+    pub payouts: Vec<i32>,
+    pub run: bool,
+    pub back_run: bool,
+}
+
+impl RuleSet {
+    /// Settles a finished hand.
+    ///
+    /// Each opponent pays (or receives) one unit: on a win, the points over
+    /// the contract plus twice its effective count over the minimum pledge,
+    /// plus one; on a loss, the points missing from the contract. The friend
+    /// takes one unit and the declarer the rest, so that the table sums to
+    /// zero.
+    pub fn settle(&self, game: &PlayPhase) -> Settlement {
+        let count = game.contract.count;
+        let score = game.declarer_team_score();
+        let won = score >= count;
+        let run = score >= 20;
+        let back_run = !won && score <= self.back_run_max_score;
+
+        let mut unit = if won {
+            let over_minimum = game.contract.effective_count() as i32 - self.min_pledge as i32;
+            (score - count) as i32 + 2 * over_minimum + 1
+        } else {
+            (count - score) as i32
+        };
+        if run {
+            unit *= self.run_multiplier;
+        }
+        if back_run {
+            unit *= self.back_run_multiplier;
+        }
+        if game.contract.suit.is_none() {
+            unit *= self.no_trump_multiplier;
+        }
+        if game.partner_condition == PartnerCondition::None {
+            unit *= self.no_friend_multiplier;
+        }
+        if !won {
+            unit = -unit;
+        }
+
+        let friend = game.partner_revealed.filter(|&p| p != game.declarer);
+        let opponents = (0..5 as PlayerIndex)
+            .filter(|&p| p != game.declarer && Some(p) != friend)
+            .count() as i32;
+        let payouts = (0..5 as PlayerIndex)
+            .map(|p| {
+                if p == game.declarer {
+                    unit * (opponents - friend.is_some() as i32)
+                } else if Some(p) == friend {
+                    unit
+                } else {
+                    -unit
+                }
+            })
+            .collect();
+        Settlement {
+            payouts,
+            run,
+            back_run,
+        }
+    }
+
+    /// Settles a hand the declarer gave up: they pay the fixed
+    /// `surrender_payment` to each other player.
+    pub fn settle_surrender(&self, surrender: &Surrender) -> Settlement {
+        let payouts = (0..5 as PlayerIndex)
+            .map(|p| {
+                if p == surrender.declarer {
+                    -4 * self.surrender_payment
+                } else {
+                    self.surrender_payment
+                }
+            })
+            .collect();
+        Settlement {
+            payouts,
+            run: false,
+            back_run: false,
+        }
+    }
+}
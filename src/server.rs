@@ -0,0 +1,573 @@
+use crate::bidding_phase::{BiddingState, PledgePhase};
+use crate::card::Card;
+use crate::clock::{default_action, default_bid, default_plan, TimeControl};
+use crate::common::{Claim, Contract, PartnerCondition, PlayAction, PlayerIndex};
+use crate::event::Event;
+use crate::extra_phase::ExtraExposedState;
+use crate::game::{play_hand_with_events, HandEnd};
+use crate::json::Json;
+use crate::play_phase::{EarlyEnd, ExposedGameState};
+use crate::player::{create_player, Player};
+use crate::protocol::{accept_action, accept_bid, accept_plan, ClientMessage, ServerMessage};
+use crate::record::GameRecord;
+use crate::rules::RuleSet;
+use crate::session::{Remote, Sessions, Takeover};
+use crate::web;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::Instant;
+
+/// The longest message a client may send. A client that sends more
+/// without ending the line is dropped.
+pub const MAX_LINE: usize = 64 * 1024;
+
+/// The next line without its line ending, or `None` at the end of the
+/// stream. Fails on a line longer than `MAX_LINE`.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = vec![];
+    reader
+        .by_ref()
+        .take(MAX_LINE as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    } else if line.len() > MAX_LINE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+enum Outgoing {
+    Lines(TcpStream),
+    WebSocket(Sender<String>),
+}
+
+/// A client connection speaking the JSON protocol, either as raw lines or
+/// from a browser over a WebSocket. Messages are read on a thread of their
+/// own, so that a connection can be polled and handed between threads.
+pub struct Connection {
+    incoming: Receiver<String>,
+    outgoing: Outgoing,
+    pub name: String,
+    /// The session token the client came back with, if any.
+    pub token: Option<String>,
+}
+
+impl Connection {
+    /// Waits for the client to join. Returns `None` when the client only
+    /// came for the table page.
+    pub fn accept(stream: TcpStream) -> io::Result<Option<Connection>> {
+        stream.set_nodelay(true)?;
+        let (incoming_sender, incoming) = mpsc::channel();
+        let outgoing = if web::is_http(&stream)? {
+            let Some(socket) = web::accept(stream)? else {
+                return Ok(None);
+            };
+            let (sender, outgoing) = mpsc::channel();
+            thread::spawn(move || web::pump(socket, incoming_sender, outgoing));
+            Outgoing::WebSocket(sender)
+        } else {
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let socket = stream.try_clone()?;
+            thread::spawn(move || {
+                while let Ok(Some(line)) = read_line(&mut reader) {
+                    if incoming_sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                let _ = socket.shutdown(Shutdown::Both);
+            });
+            Outgoing::Lines(stream)
+        };
+        let mut connection = Connection {
+            incoming,
+            outgoing,
+            name: String::new(),
+            token: None,
+        };
+        loop {
+            match connection.receive()? {
+                Ok(ClientMessage::Join { name, token }) => {
+                    connection.name = name;
+                    connection.token = token;
+                    return Ok(Some(connection));
+                }
+                Ok(_) => connection.send(&ServerMessage::Error("join first".to_string()))?,
+                Err(e) => connection.send(&ServerMessage::Error(e))?,
+            }
+        }
+    }
+
+    pub fn send(&mut self, message: &ServerMessage) -> io::Result<()> {
+        let text = message.to_json().to_string();
+        match &mut self.outgoing {
+            Outgoing::Lines(writer) => writer.write_all(format!("{}\n", text).as_bytes()),
+            Outgoing::WebSocket(sender) => sender
+                .send(text)
+                .map_err(|_| io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    /// The next message, or why it could not be understood. Fails when the
+    /// client goes away.
+    pub fn receive(&mut self) -> io::Result<Result<ClientMessage, String>> {
+        match self.incoming.recv() {
+            Ok(line) => Ok(parse_message(&line)),
+            Err(_) => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    /// Like `receive`, but returns `None` at once when no message is waiting.
+    pub fn poll(&mut self) -> io::Result<Option<Result<ClientMessage, String>>> {
+        match self.incoming.try_recv() {
+            Ok(line) => Ok(Some(parse_message(&line))),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    /// Sends `request` until `accept` takes the reply, telling the client
+    /// what was wrong with each rejected one. Fails with `TimedOut` when no
+    /// acceptable reply comes by `deadline`. Replies to earlier requests
+    /// that came too late are dropped.
+    fn ask<T>(
+        &mut self,
+        request: &ServerMessage,
+        accept: impl Fn(ClientMessage) -> Result<T, String>,
+        deadline: Option<Instant>,
+    ) -> io::Result<T> {
+        while self.poll()?.is_some() {}
+        loop {
+            self.send(request)?;
+            let line = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    self.incoming.recv_timeout(timeout).map_err(|e| match e {
+                        RecvTimeoutError::Timeout => io::ErrorKind::TimedOut,
+                        RecvTimeoutError::Disconnected => io::ErrorKind::UnexpectedEof,
+                    })?
+                }
+                None => self
+                    .incoming
+                    .recv()
+                    .map_err(|_| io::Error::from(io::ErrorKind::UnexpectedEof))?,
+            };
+            match parse_message(&line).and_then(&accept) {
+                Ok(answer) => return Ok(answer),
+                Err(e) => self.send(&ServerMessage::Error(e))?,
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // The reader thread holds a clone of the stream; hang up on both.
+        if let Outgoing::Lines(writer) = &self.outgoing {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn parse_message(line: &str) -> Result<ClientMessage, String> {
+    line.trim()
+        .parse::<Json>()
+        .and_then(|json| ClientMessage::from_json(&json))
+}
+
+/// Someone sitting at a table: a client, or a bot played by the server.
+pub enum Seat {
+    Remote(Remote),
+    Bot(Box<dyn Player>),
+}
+
+/// Hosts games for clients on the network. The first five clients to join
+/// take seats 0 to 4. Browsers opening the server's address get the table
+/// page and play from there.
+pub struct Server {
+    listener: TcpListener,
+    sessions: Sessions,
+    pub rules: RuleSet,
+    pub takeover: Takeover,
+    pub time_control: TimeControl,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs, rules: RuleSet) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            sessions: Sessions::default(),
+            rules,
+            takeover: Takeover::default(),
+            time_control: TimeControl::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits for five clients to join, and welcomes each to its seat. From
+    /// then on, only clients taking their seats back are let in.
+    pub fn accept_table(&self) -> io::Result<Vec<Seat>> {
+        let (joined, arrivals) = mpsc::channel();
+        let listener = self.listener.try_clone()?;
+        thread::spawn(move || accept_connections(listener, joined));
+        let mut seats = vec![];
+        while seats.len() < 5 {
+            let connection = arrivals
+                .recv()
+                .map_err(|_| io::Error::other("the server stopped listening"))?;
+            let mut remote = Remote::new(connection, &self.sessions, &self.takeover);
+            let welcome = ServerMessage::Welcome {
+                seat: seats.len(),
+                token: remote.token.clone(),
+            };
+            // A client that fails to join just loses its seat.
+            if remote.send(&welcome) {
+                seats.push(Seat::Remote(remote));
+            }
+        }
+        let sessions = self.sessions.clone();
+        thread::spawn(move || {
+            for connection in arrivals {
+                let token = connection.token.clone().unwrap_or_default();
+                if let Err(mut connection) = sessions.rejoin(&token, connection) {
+                    let _ = connection.send(&ServerMessage::Error("the table is full".to_string()));
+                }
+            }
+        });
+        Ok(seats)
+    }
+
+    pub fn host_game(&self, seats: &mut [Seat], game: PledgePhase) -> io::Result<HandEnd> {
+        host_game(&self.rules, self.time_control, seats, game)
+    }
+}
+
+/// Lets clients in, each joining on a thread of its own so that one slow to
+/// join holds up no one, and hands over those that join. Clients that only
+/// fetch the table page are done once they have it.
+fn accept_connections(listener: TcpListener, joined: Sender<Connection>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let joined = joined.clone();
+        thread::spawn(move || {
+            if let Ok(Some(connection)) = Connection::accept(stream) {
+                let _ = joined.send(connection);
+            }
+        });
+    }
+}
+
+/// A hand in progress, as far as the seats need to know.
+struct Hand<'a> {
+    seats: &'a mut [Seat],
+    /// The public events so far, for clients coming back.
+    history: Vec<Event>,
+    /// The record of the hand so far, for clients coming back during play.
+    record: Option<GameRecord>,
+    /// The bots playing for clients while they are away.
+    stand_ins: Vec<Option<Box<dyn Player>>>,
+}
+
+impl Hand<'_> {
+    fn broadcast(&mut self, event: Event) {
+        self.history.push(event.clone());
+        let message = ServerMessage::Event(event);
+        for i in 0..self.seats.len() {
+            self.send(i, &message);
+        }
+    }
+
+    /// Sends a message to a seat. A client that cannot be reached is away
+    /// until it comes back.
+    fn send(&mut self, i: PlayerIndex, message: &ServerMessage) {
+        self.catch_up(i);
+        let observer = match &mut self.seats[i] {
+            Seat::Bot(player) => &**player,
+            Seat::Remote(remote) => {
+                if remote.send(message) {
+                    return;
+                }
+                self.stand_ins[i].as_deref().unwrap()
+            }
+        };
+        if let ServerMessage::State(state) = message {
+            observer.observe(state);
+        }
+    }
+
+    /// Asks the client at a seat until it gives an acceptable answer or
+    /// `deadline` passes.
+    fn ask<T>(
+        &mut self,
+        i: PlayerIndex,
+        request: &ServerMessage,
+        accept: impl Fn(ClientMessage) -> Result<T, String>,
+        deadline: Option<Instant>,
+    ) -> Reply<T> {
+        loop {
+            self.catch_up(i);
+            let Seat::Remote(remote) = &mut self.seats[i] else {
+                return Reply::Away;
+            };
+            if let Some(connection) = remote.connection() {
+                match connection.ask(request, &accept, deadline) {
+                    Ok(answer) => return Reply::Answer(answer),
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => return Reply::Late,
+                    Err(_) => remote.lose(),
+                }
+            } else if let Some(connection) = remote.wait_for_return() {
+                self.resume(i, connection);
+            } else {
+                if !remote.standing_in {
+                    remote.standing_in = true;
+                    self.broadcast(Event::Away { player: i });
+                }
+                return Reply::Away;
+            }
+        }
+    }
+
+    fn catch_up(&mut self, i: PlayerIndex) {
+        if let Seat::Remote(remote) = &mut self.seats[i] {
+            if let Some(connection) = remote.returning() {
+                self.resume(i, connection);
+            }
+        }
+    }
+
+    /// Seats a client that came back, bringing it up to date with the hand.
+    fn resume(&mut self, i: PlayerIndex, mut connection: Connection) {
+        let Seat::Remote(remote) = &mut self.seats[i] else {
+            return;
+        };
+        let welcome = ServerMessage::Welcome {
+            seat: i,
+            token: remote.token.clone(),
+        };
+        let game = self
+            .record
+            .as_ref()
+            .and_then(|record| record.play_phase(record.actions.len()).ok());
+        let resume = ServerMessage::Resume {
+            events: self.history.clone(),
+            state: game.map(|game| game.play_state(i)),
+        };
+        if connection.send(&welcome).is_err() || connection.send(&resume).is_err() {
+            return;
+        }
+        let was_standing_in = remote.standing_in;
+        remote.resume(connection);
+        if was_standing_in {
+            self.broadcast(Event::Back { player: i });
+        }
+    }
+}
+
+/// What came of asking a client.
+enum Reply<T> {
+    Answer(T),
+    /// No acceptable answer came in time.
+    Late,
+    /// The client stayed away past the grace period, and its stand-in
+    /// answers instead.
+    Away,
+}
+
+/// An answer to a turn that allows claims.
+enum Move {
+    Play(PlayAction),
+    Claim(Claim),
+}
+
+/// A seat of a hosted hand as the game loop sees it: requests go to the
+/// client sitting there, or to the bot playing for it.
+struct SeatPlayer<'h, 'a> {
+    hand: &'h RefCell<Hand<'a>>,
+    seat: PlayerIndex,
+    /// The plan given in place of surrendering, until it is asked for.
+    plan: RefCell<Option<(Contract, PartnerCondition, HashSet<Card>)>>,
+    /// The action given in place of a claim, until it is asked for.
+    action: Cell<Option<PlayAction>>,
+    deadline: Cell<Option<Instant>>,
+    /// Whether the last answer came too late.
+    late: Cell<bool>,
+}
+
+impl SeatPlayer<'_, '_> {
+    /// Sends `request` to the client at the seat, or has the bot there or
+    /// standing in `play` instead. A late client gets the `default` move.
+    fn ask<T>(
+        &self,
+        request: ServerMessage,
+        accept: impl Fn(ClientMessage) -> Result<T, String>,
+        play: impl FnOnce(&dyn Player) -> T,
+        default: impl FnOnce() -> T,
+    ) -> T {
+        let play = |player: &dyn Player| {
+            player.set_deadline(self.deadline.get());
+            let answer = play(player);
+            self.late.set(player.failed());
+            answer
+        };
+        let mut hand = self.hand.borrow_mut();
+        if let Seat::Bot(player) = &hand.seats[self.seat] {
+            return play(player.as_ref());
+        }
+        match hand.ask(self.seat, &request, accept, self.deadline.get()) {
+            Reply::Answer(answer) => answer,
+            Reply::Late => {
+                self.late.set(true);
+                default()
+            }
+            Reply::Away => play(hand.stand_ins[self.seat].as_deref().unwrap()),
+        }
+    }
+}
+
+impl Player for SeatPlayer<'_, '_> {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        let request = ServerMessage::Bidding(state.clone());
+        let accept = |m| accept_bid(state, m);
+        self.ask(request, accept, |p| p.bidding(state), default_bid)
+    }
+
+    /// Clients answer whether to surrender and the plan at once.
+    fn surrender(&self, state: &ExtraExposedState) -> bool {
+        let request = ServerMessage::Declare(state.clone());
+        let accept = |m| accept_plan(state, m);
+        let play = |p: &dyn Player| (!p.surrender(state)).then(|| p.declare_plan(state.clone()));
+        let plan = self.ask(request, accept, play, || Some(default_plan(state)));
+        *self.plan.borrow_mut() = plan;
+        self.plan.borrow().is_none()
+    }
+
+    fn declare_plan(
+        &self,
+        _state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        self.plan.take().expect("asked to surrender first")
+    }
+
+    /// Clients answer their turn with a claim or a card. After a rejected
+    /// claim, they are asked again for a card only.
+    fn claim(&self, state: &ExposedGameState) -> Option<Claim> {
+        self.action.set(None);
+        let request = ServerMessage::Turn {
+            state: state.clone(),
+            claims: true,
+        };
+        let accept = |m| match m {
+            ClientMessage::Claim(claim) => Ok(Move::Claim(claim)),
+            m => accept_action(state, m).map(Move::Play),
+        };
+        let play = |p: &dyn Player| match p.claim(state) {
+            Some(claim) => Move::Claim(claim),
+            None => Move::Play(p.play_action(state.clone())),
+        };
+        match self.ask(request, accept, play, || Move::Play(default_action(state))) {
+            Move::Claim(claim) => Some(claim),
+            Move::Play(action) => {
+                self.action.set(Some(action));
+                None
+            }
+        }
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        if let Some(action) = self.action.take() {
+            return action;
+        }
+        let request = ServerMessage::Turn {
+            state: state.clone(),
+            claims: false,
+        };
+        let accept = |m| accept_action(&state, m);
+        let play = |p: &dyn Player| p.play_action(state.clone());
+        self.ask(request, accept, play, || default_action(&state))
+    }
+
+    fn observe(&self, state: &ExposedGameState) {
+        let message = ServerMessage::State(state.clone());
+        self.hand.borrow_mut().send(self.seat, &message);
+    }
+
+    fn new_hand(&self) {
+        if let Seat::Bot(player) = &self.hand.borrow().seats[self.seat] {
+            player.new_hand();
+        }
+    }
+
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+        self.late.set(false);
+    }
+
+    fn failed(&self) -> bool {
+        self.late.get()
+    }
+}
+
+/// Plays a dealt hand at a table under a time control, sending each seat
+/// only its own view and every seat the public events, and tells how the
+/// hand ended. Fails only when a stand-in cannot be created.
+pub fn host_game(
+    rules: &RuleSet,
+    control: TimeControl,
+    seats: &mut [Seat],
+    game: PledgePhase,
+) -> io::Result<HandEnd> {
+    assert_eq!(seats.len(), 5);
+    let stand_ins = seats
+        .iter()
+        .map(|seat| match seat {
+            Seat::Remote(remote) => create_player(&remote.bot).map(Some),
+            Seat::Bot(_) => Ok(None),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(io::Error::other)?;
+    let hand = RefCell::new(Hand {
+        seats,
+        history: vec![],
+        record: None,
+        stand_ins,
+    });
+    let players = (0..5)
+        .map(|seat| {
+            Box::new(SeatPlayer {
+                hand: &hand,
+                seat,
+                plan: RefCell::new(None),
+                action: Cell::new(None),
+                deadline: Cell::new(None),
+                late: Cell::new(false),
+            }) as Box<dyn Player>
+        })
+        .collect::<Vec<_>>();
+    let on_event = |event: &Event, record: &GameRecord| {
+        let mut hand = hand.borrow_mut();
+        hand.record = Some(record.clone());
+        hand.broadcast(event.clone());
+    };
+    let (end, _) = play_hand_with_events(&players, game, control, EarlyEnd::Never, on_event);
+    if let Some(result) = end.result(rules) {
+        hand.borrow_mut().broadcast(result);
+    }
+    Ok(end)
+}
@@ -0,0 +1,140 @@
+//! Seats that outlive their connections. Each seated client gets a session
+//! token; when its connection drops, a bot stands in after a grace period,
+//! and a client joining again with the token takes the seat back.
+
+use crate::protocol::ServerMessage;
+use crate::server::Connection;
+use rand::{rng, Rng};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How a table copes with a client dropping out.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Takeover {
+    /// The bot playing the seat while its client is away.
+    pub bot: String,
+    /// How long a request waits for the client to come back before the bot
+    /// answers it.
+    pub grace: Duration,
+}
+
+impl Default for Takeover {
+    fn default() -> Self {
+        Takeover {
+            bot: "random".to_string(),
+            grace: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Open sessions by token, shared by everything accepting clients.
+#[derive(Clone, Default)]
+pub struct Sessions(Arc<Mutex<HashMap<String, Sender<Connection>>>>);
+
+impl Sessions {
+    /// Hands a returning client to its seat, or gives it back when the token
+    /// is not known.
+    pub fn rejoin(&self, token: &str, connection: Connection) -> Result<(), Connection> {
+        match self.0.lock().unwrap().get(token) {
+            Some(seat) => seat.send(connection).map_err(|e| e.0),
+            None => Err(connection),
+        }
+    }
+
+    fn open(&self) -> (String, Receiver<Connection>) {
+        let token = format!("{:016x}", rng().random::<u64>());
+        let (sender, returns) = mpsc::channel();
+        self.0.lock().unwrap().insert(token.clone(), sender);
+        (token, returns)
+    }
+
+    fn close(&self, token: &str) {
+        self.0.lock().unwrap().remove(token);
+    }
+}
+
+/// A seat held by a client, connected or not.
+pub struct Remote {
+    connection: Option<Connection>,
+    pub name: String,
+    pub token: String,
+    returns: Receiver<Connection>,
+    sessions: Sessions,
+    away_since: Instant,
+    /// The bot playing the seat while the client is away.
+    pub bot: String,
+    /// Whether the stand-in has played for the client since it left.
+    pub standing_in: bool,
+    grace: Duration,
+}
+
+impl Remote {
+    pub fn new(connection: Connection, sessions: &Sessions, takeover: &Takeover) -> Remote {
+        let (token, returns) = sessions.open();
+        Remote {
+            name: connection.name.clone(),
+            connection: Some(connection),
+            token,
+            returns,
+            sessions: sessions.clone(),
+            away_since: Instant::now(),
+            bot: takeover.bot.clone(),
+            standing_in: false,
+            grace: takeover.grace,
+        }
+    }
+
+    pub fn connection(&mut self) -> Option<&mut Connection> {
+        self.connection.as_mut()
+    }
+
+    /// Sends a message if the client is there. Returns whether it was.
+    pub fn send(&mut self, message: &ServerMessage) -> bool {
+        let sent = self
+            .connection
+            .as_mut()
+            .is_some_and(|c| c.send(message).is_ok());
+        if !sent {
+            self.lose();
+        }
+        sent
+    }
+
+    /// Gives up the seat, keeping the client if it is still there.
+    pub fn leave(mut self) -> Option<Connection> {
+        self.connection.take()
+    }
+
+    /// Forgets a connection that failed.
+    pub fn lose(&mut self) {
+        if self.connection.take().is_some() {
+            self.away_since = Instant::now();
+        }
+    }
+
+    /// A client that came back with the token, if one did.
+    pub fn returning(&mut self) -> Option<Connection> {
+        self.returns.try_recv().ok()
+    }
+
+    /// Waits out what is left of the grace period for the client to come
+    /// back.
+    pub fn wait_for_return(&mut self) -> Option<Connection> {
+        let left = self.grace.saturating_sub(self.away_since.elapsed());
+        self.returns.recv_timeout(left).ok()
+    }
+
+    /// Seats a returning client in place of the old connection.
+    pub fn resume(&mut self, connection: Connection) {
+        self.connection = Some(connection);
+        self.standing_in = false;
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        self.sessions.close(&self.token);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::bidding_phase::PledgePhase;
+use crate::clock::TimeControl;
+use crate::common::{Contract, PartnerCondition, PlayerIndex};
+use crate::game::{play_hand, HandEnd};
+use crate::play_phase::EarlyEnd;
+use crate::player::{check_player, create_player, Player};
+use crate::record::GameRecord;
+use crate::rules::RuleSet;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OutputFormat {
+    Csv,
+    Jsonl,
+}
+
+/// Headless batch of seeded games, spread over worker threads. Game `i` is
+/// dealt from seed `seed + i`, so a batch can be reproduced or extended,
+/// whatever the number of threads. Only the deals are seeded: a lineup with
+/// bots playing by chance, such as `random`, plays them differently each time.
+pub struct Simulation {
+    pub games: usize,
+    pub seed: u64,
+    pub lineup: Vec<String>,
+    pub rules: RuleSet,
+    pub threads: usize,
+    pub time_control: TimeControl,
+    /// When each hand may stop before its last round.
+    pub early_end: EarlyEnd,
+}
+
+/// One finished (or cancelled) game, flattened for analysis.
+#[derive(Clone, Debug)]
+pub struct GameSummary {
+    pub seed: u64,
+    /// Whether the declarer gave up before play, with no plan made.
+    pub surrendered: bool,
+    pub declarer: Option<PlayerIndex>,
+    pub contract: Option<Contract>,
+    pub partner_condition: Option<PartnerCondition>,
+    pub friend: Option<PlayerIndex>,
+    pub friend_revealed_round: Option<u8>,
+    pub leading_points: u8,
+    pub opposing_points: u8,
+    pub run: bool,
+    pub back_run: bool,
+    pub payouts: Vec<i32>,
+    /// Moves made for each seat because it ran out of time.
+    pub timeouts: Vec<usize>,
+}
+
+impl Simulation {
+    pub fn run(&self) -> Result<Vec<GameSummary>, String> {
+        self.check_lineup()?;
+        Ok(self.each_game(0..self.games, |seed| self.play(seed)))
+    }
+
+    /// Plays the games numbered `games` of the batch, for their seeds and
+    /// records.
+    pub fn records(&self, games: Range<usize>) -> Result<Vec<(u64, GameRecord)>, String> {
+        self.check_lineup()?;
+        Ok(self.each_game(games, |seed| (seed, self.record(seed).1)))
+    }
+
+    fn check_lineup(&self) -> Result<(), String> {
+        if self.lineup.len() != 5 {
+            return Err(format!("lineup needs 5 players, got {}", self.lineup.len()));
+        }
+        self.lineup.iter().try_for_each(|name| check_player(name))
+    }
+
+    /// Runs `f` on the seed of each game in `games` over the worker threads,
+    /// returning the results in the order of the games.
+    fn each_game<T: Send>(&self, games: Range<usize>, f: impl Fn(u64) -> T + Sync) -> Vec<T> {
+        let next = AtomicUsize::new(games.start);
+        let results = Mutex::new(Vec::with_capacity(games.len()));
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= games.end {
+                        break;
+                    }
+                    let result = f(self.seed.wrapping_add(i as u64));
+                    results.lock().unwrap().push((i, result));
+                });
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    fn record(&self, seed: u64) -> (HandEnd, GameRecord) {
+        let players = self
+            .lineup
+            .iter()
+            .map(|n| create_player(n).unwrap())
+            .collect::<Vec<Box<dyn Player>>>();
+        let deal = PledgePhase::seeded(0, self.rules.min_pledge, seed);
+        play_hand(&players, deal, self.time_control, self.early_end)
+    }
+
+    fn play(&self, seed: u64) -> GameSummary {
+        let (game, record) = self.record(seed);
+        let mut timeouts = vec![0; 5];
+        for &(seat, _) in &record.timeouts {
+            timeouts[seat] += 1;
+        }
+        let unplayed = GameSummary {
+            seed,
+            surrendered: false,
+            declarer: None,
+            contract: None,
+            partner_condition: None,
+            friend: None,
+            friend_revealed_round: None,
+            leading_points: 0,
+            opposing_points: 0,
+            run: false,
+            back_run: false,
+            payouts: vec![0; 5],
+            timeouts,
+        };
+        let game = match game {
+            HandEnd::Cancelled => return unplayed,
+            HandEnd::Surrendered(surrender) => {
+                return GameSummary {
+                    surrendered: true,
+                    declarer: Some(surrender.declarer),
+                    contract: Some(surrender.contract),
+                    payouts: self.rules.settle_surrender(&surrender).payouts,
+                    ..unplayed
+                }
+            }
+            HandEnd::Played(game) => game,
+        };
+
+        let settlement = self.rules.settle(&game);
+        let leading_points = game.declarer_team_score();
+        let opposing_points = game.scores().iter().sum::<u8>() - leading_points;
+        GameSummary {
+            seed,
+            surrendered: false,
+            declarer: Some(game.declarer),
+            contract: Some(game.contract),
+            partner_condition: Some(game.partner_condition),
+            friend: game.partner_revealed,
+            friend_revealed_round: game.partner_revealed_round(),
+            leading_points,
+            opposing_points,
+            run: settlement.run,
+            back_run: settlement.back_run,
+            payouts: settlement.payouts,
+            timeouts: unplayed.timeouts,
+        }
+    }
+}
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or(String::new(), |v| v.to_string())
+}
+
+fn json_optional<T: ToString>(value: Option<T>, quoted: bool) -> String {
+    match value {
+        Some(v) if quoted => format!("\"{}\"", v.to_string()),
+        Some(v) => v.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+fn joined<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+const CSV_HEADER: &str =
+    "seed,cancelled,surrendered,declarer,count,trump,partner_condition,friend,\
+friend_revealed_round,leading_points,opposing_points,run,back_run,\
+payout_0,payout_1,payout_2,payout_3,payout_4,\
+timeouts_0,timeouts_1,timeouts_2,timeouts_3,timeouts_4";
+
+impl GameSummary {
+    pub fn cancelled(&self) -> bool {
+        self.declarer.is_none()
+    }
+
+    fn trump(&self) -> Option<String> {
+        self.contract
+            .map(|c| c.suit.map_or("NT".to_string(), |s| s.to_string()))
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.seed,
+            self.cancelled(),
+            self.surrendered,
+            optional(self.declarer),
+            optional(self.contract.map(|c| c.count)),
+            optional(self.trump()),
+            optional(self.partner_condition),
+            optional(self.friend),
+            optional(self.friend_revealed_round),
+            self.leading_points,
+            self.opposing_points,
+            self.run,
+            self.back_run,
+            joined(&self.payouts),
+            joined(&self.timeouts),
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"seed\":{},\"cancelled\":{},\"surrendered\":{},\"declarer\":{},\"count\":{},\"trump\":{},\
+\"partner_condition\":{},\"friend\":{},\"friend_revealed_round\":{},\
+\"leading_points\":{},\"opposing_points\":{},\"run\":{},\"back_run\":{},\"payouts\":[{}],\
+\"timeouts\":[{}]}}",
+            self.seed,
+            self.cancelled(),
+            self.surrendered,
+            json_optional(self.declarer, false),
+            json_optional(self.contract.map(|c| c.count), false),
+            json_optional(self.trump(), true),
+            json_optional(self.partner_condition, true),
+            json_optional(self.friend, false),
+            json_optional(self.friend_revealed_round, false),
+            self.leading_points,
+            self.opposing_points,
+            self.run,
+            self.back_run,
+            joined(&self.payouts),
+            joined(&self.timeouts),
+        )
+    }
+}
+
+pub fn write_summaries(
+    out: &mut impl Write,
+    summaries: &[GameSummary],
+    format: OutputFormat,
+) -> io::Result<()> {
+    if format == OutputFormat::Csv {
+        writeln!(out, "{}", CSV_HEADER)?;
+    }
+    for summary in summaries {
+        match format {
+            OutputFormat::Csv => writeln!(out, "{}", summary.to_csv())?,
+            OutputFormat::Jsonl => writeln!(out, "{}", summary.to_json())?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,490 @@
+use crate::card::{Card, Suit};
+use crate::common::{PartnerCondition, PlayAction, PlayerIndex};
+use crate::play_phase::{card_value, legal_actions, PlayPhase};
+use std::collections::HashMap;
+
+/// Exact double-dummy result for a fully known `PlayPhase`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Solution {
+    /// Final points of the declarer team under optimal play by everyone,
+    /// or its rounds won for a solver counting tricks.
+    pub points: u8,
+    /// Optimal action of the player to move, `None` once the hand is over.
+    pub action: Option<PlayAction>,
+}
+
+// The 20 points in the deck.
+const MAX_POINTS: u8 = 20;
+
+/// Everything the rest of the hand depends on at the start of a round, so
+/// that one table can serve positions from any number of hands.
+#[derive(Clone, Eq, PartialEq, Hash)]
+struct Key {
+    hands: [u64; 5],
+    round_starter: PlayerIndex,
+    declarer: PlayerIndex,
+    trump: Option<Suit>,
+    partner_condition: PartnerCondition,
+    partner_revealed: Option<PlayerIndex>,
+    // Only kept while a `Round` friend is undecided, since then the points
+    // already taken by the future friend are still in play.
+    scores: Option<[u8; 5]>,
+}
+
+#[derive(Copy, Clone)]
+struct Bound {
+    lower: u8,
+    upper: u8,
+    best: Option<PlayAction>,
+}
+
+/// Alpha-beta search with a transposition table over trick boundaries.
+///
+/// The declarer team maximizes its final points and everybody else minimizes
+/// them. The friend is known to everyone, as it is with all hands open: the
+/// holder of the condition card, or the revealed partner. A friend chosen by
+/// `PartnerCondition::Round` plays for the opposition until its round ends.
+///
+/// Solved positions are kept between calls, keyed on the whole of what is
+/// left to play, so one solver can be reused across positions and hands.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<Key, Bound>,
+    tricks: bool,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver::default()
+    }
+
+    /// A solver valuing every round won as one point, whatever the cards
+    /// taken in it are worth.
+    pub fn counting_tricks() -> Self {
+        Solver {
+            tricks: true,
+            ..Solver::default()
+        }
+    }
+
+    pub(crate) fn position(&self, game: &PlayPhase) -> Position {
+        let mut position = Position::from(game);
+        if self.tricks {
+            position.tricks = true;
+            position.scores = [0; 5];
+            for result in &game.round_results {
+                position.scores[result.winner] += 1;
+            }
+        }
+        position
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn solve(&mut self, game: &PlayPhase) -> Solution {
+        let position = self.position(game);
+        let points = self.value(&position);
+        let Some(player) = position.turn_player() else {
+            return Solution {
+                points,
+                action: None,
+            };
+        };
+        // Any action keeping the optimal value is optimal; probe them with
+        // null windows instead of solving each one exactly.
+        let maximizing = position.is_leading(player);
+        let action = game.legal_actions(player).into_iter().find(|&action| {
+            let mut next = position;
+            next.apply(player, action);
+            if maximizing {
+                points == 0 || self.search(&next, points - 1, points) >= points
+            } else {
+                self.search(&next, points, points + 1) <= points
+            }
+        });
+        Solution { points, action }
+    }
+
+    /// Exact value of every legal action of the player to move.
+    pub fn action_values(&mut self, game: &PlayPhase) -> Vec<(PlayAction, u8)> {
+        let position = self.position(game);
+        let Some(player) = position.turn_player() else {
+            return vec![];
+        };
+        game.legal_actions(player)
+            .into_iter()
+            .map(|action| {
+                let mut next = position;
+                next.apply(player, action);
+                (action, self.value(&next))
+            })
+            .collect()
+    }
+
+    /// MTD(f): narrows the exact value down with null-window searches, which
+    /// cut far more than a single full-window search.
+    fn value(&mut self, position: &Position) -> u8 {
+        let (mut lower, mut upper) = (position.team_points(), MAX_POINTS);
+        let mut guess = lower;
+        while lower < upper {
+            let beta = if guess == lower { guess + 1 } else { guess };
+            guess = self.search(position, beta - 1, beta);
+            if guess < beta {
+                upper = guess;
+            } else {
+                lower = guess;
+            }
+        }
+        lower
+    }
+
+    fn search(&mut self, position: &Position, mut alpha: u8, mut beta: u8) -> u8 {
+        let Some(player) = position.turn_player() else {
+            return position.team_points();
+        };
+
+        let base = position.team_points();
+        if base >= beta {
+            return base;
+        }
+        let ceiling = position.ceiling();
+        if ceiling <= alpha {
+            return ceiling;
+        }
+
+        let key = (position.played == 0).then(|| position.key());
+        let mut actions = position.distinct_actions(player);
+        if let Some(bound) = key.as_ref().and_then(|k| self.table.get(k)) {
+            let (lower, upper) = (base + bound.lower, base.saturating_add(bound.upper));
+            if lower >= beta {
+                return lower;
+            }
+            if upper <= alpha || lower == upper {
+                return upper;
+            }
+            alpha = alpha.max(lower);
+            beta = beta.min(upper);
+            if let Some(i) = actions.iter().position(|&a| Some(a) == bound.best) {
+                actions[..=i].rotate_right(1);
+            }
+        }
+        let (alpha_orig, beta_orig) = (alpha, beta);
+
+        let maximizing = position.is_leading(player);
+        let mut best = if maximizing { 0 } else { u8::MAX };
+        let mut best_action = None;
+        for action in actions {
+            let mut next = *position;
+            next.apply(player, action);
+            let value = self.search(&next, alpha, beta);
+            if best_action.is_none()
+                || (maximizing && value > best)
+                || (!maximizing && value < best)
+            {
+                best = value;
+                best_action = Some(action);
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(key) = key {
+            let entry = self.table.entry(key).or_insert(Bound {
+                lower: 0,
+                upper: u8::MAX,
+                best: None,
+            });
+            entry.best = best_action;
+            let offset = best - base;
+            if best <= alpha_orig {
+                entry.upper = entry.upper.min(offset);
+            } else if best >= beta_orig {
+                entry.lower = entry.lower.max(offset);
+            } else {
+                entry.lower = offset;
+                entry.upper = offset;
+            }
+        }
+        best
+    }
+}
+
+/// Compact copy of a `PlayPhase` following the same rules, cheap to copy
+/// at every node of the search.
+#[derive(Copy, Clone)]
+pub(crate) struct Position {
+    hands: [u64; 5],
+    declarer: PlayerIndex,
+    trump: Option<Suit>,
+    partner_condition: PartnerCondition,
+    partner_revealed: Option<PlayerIndex>,
+    round: u8,
+    joker_called: bool,
+    submitted: [Option<Card>; 5],
+    played: usize,
+    round_starter: PlayerIndex,
+    round_suit: Option<Suit>,
+    scores: [u8; 5],
+    /// Whether a round is worth one point instead of its cards.
+    tricks: bool,
+}
+
+impl From<&PlayPhase> for Position {
+    fn from(game: &PlayPhase) -> Self {
+        let mut hands = [0; 5];
+        for (bits, hand) in hands.iter_mut().zip(&game.hands) {
+            *bits = mask(hand.iter());
+        }
+        let mut submitted = [None; 5];
+        submitted.copy_from_slice(&game.submitted);
+        let mut scores = [0; 5];
+        scores.copy_from_slice(&game.scores());
+        Position {
+            hands,
+            declarer: game.declarer,
+            trump: game.contract.suit,
+            partner_condition: game.partner_condition,
+            partner_revealed: game.partner_revealed,
+            round: game.round,
+            joker_called: game.joker_called,
+            submitted,
+            played: game.submitted.iter().flatten().count(),
+            round_starter: game.round_starter,
+            round_suit: game.round_suit,
+            scores,
+            tricks: false,
+        }
+    }
+}
+
+impl Position {
+    pub(crate) fn turn_player(&self) -> Option<PlayerIndex> {
+        (self.round < 10).then_some((self.round_starter + self.played) % 5)
+    }
+
+    fn friend(&self) -> Option<PlayerIndex> {
+        self.partner_revealed.or(match self.partner_condition {
+            PartnerCondition::CardCondition(card) => {
+                let bit = 1u64 << card.index();
+                self.hands.iter().position(|&h| h & bit != 0)
+            }
+            PartnerCondition::Player(partner) => Some(partner),
+            PartnerCondition::Round(_) | PartnerCondition::None => None,
+        })
+    }
+
+    fn is_leading(&self, player: PlayerIndex) -> bool {
+        player == self.declarer || Some(player) == self.friend()
+    }
+
+    /// Points of the declarer team, counted as `PlayPhase::result` does.
+    pub(crate) fn team_points(&self) -> u8 {
+        let friend = self.friend().filter(|&friend| friend != self.declarer);
+        self.scores[self.declarer] + friend.map_or(0, |friend| self.scores[friend])
+    }
+
+    /// Points of every player together.
+    pub(crate) fn total_points(&self) -> u8 {
+        self.scores.iter().sum()
+    }
+
+    /// Points still to be taken, in the cards left or the rounds left.
+    pub(crate) fn remaining(&self) -> u8 {
+        if self.tricks {
+            return 10 - self.round;
+        }
+        let live = (0..5)
+            .map(|i| self.hands[i] | self.submitted[i].map_or(0, |c| 1u64 << c.index()))
+            .fold(0, |acc, h| acc | h);
+        (0..53)
+            .filter(|i| live & 1u64 << i != 0)
+            .map(|i| Card::from_index(i).score())
+            .sum::<u8>()
+    }
+
+    /// Upper bound on the final points of the declarer team.
+    fn ceiling(&self) -> u8 {
+        let own = self.scores[self.declarer] + self.remaining();
+        match self.friend() {
+            Some(friend) if friend == self.declarer => own,
+            Some(friend) => own + self.scores[friend],
+            None if matches!(self.partner_condition, PartnerCondition::Round(_)) => {
+                own + self.scores.iter().max().unwrap()
+            }
+            None => own,
+        }
+    }
+
+    fn key(&self) -> Key {
+        let undecided = self.partner_revealed.is_none()
+            && matches!(self.partner_condition, PartnerCondition::Round(_));
+        Key {
+            hands: self.hands,
+            round_starter: self.round_starter,
+            declarer: self.declarer,
+            trump: self.trump,
+            partner_condition: self.partner_condition,
+            partner_revealed: self.partner_revealed,
+            scores: undecided.then_some(self.scores),
+        }
+    }
+
+    pub(crate) fn legal_actions(&self, player: PlayerIndex) -> Vec<PlayAction> {
+        let hand = self.hands[player];
+        let cards = (0..53)
+            .filter(move |i| hand & 1u64 << i != 0)
+            .map(Card::from_index);
+        legal_actions(
+            cards,
+            self.played == 0,
+            self.joker_called,
+            self.round_suit,
+            self.trump,
+        )
+    }
+
+    /// Legal actions with all but one of each run of equivalent cards dropped.
+    ///
+    /// Two cards of a suit are equivalent when every card ranked between them
+    /// is already out of play, they are worth the same points, and neither of
+    /// them carries a special role.
+    fn distinct_actions(&self, player: PlayerIndex) -> Vec<PlayAction> {
+        let live =
+            self.hands.iter().fold(0, |acc, h| acc | h) | mask(self.submitted.iter().flatten());
+        let special = |card: Card| {
+            card.is_mighty(self.trump)
+                || card.is_joker_call(self.trump)
+                || self.partner_condition == PartnerCondition::CardCondition(card)
+        };
+
+        let mut actions = self.legal_actions(player);
+        actions.dedup_by(|next, prev| match (*prev, *next) {
+            (
+                PlayAction::Hand(low @ Card::Shaped(suit, n1)),
+                PlayAction::Hand(high @ Card::Shaped(_, n2)),
+            ) => {
+                high == Card::Shaped(suit, n2)
+                    && low.score() == high.score()
+                    && !special(low)
+                    && !special(high)
+                    && (n1 + 1..n2).all(|n| live & 1u64 << Card::Shaped(suit, n).index() == 0)
+            }
+            _ => false,
+        });
+        self.order_actions(player, &mut actions);
+        actions
+    }
+
+    /// Cheap move ordering: lead high, win tricks as cheaply as possible,
+    /// and feed points to a trick the own side is already taking.
+    fn order_actions(&self, player: PlayerIndex, actions: &mut [PlayAction]) {
+        let card_of = |action: PlayAction| match action {
+            PlayAction::Hand(card) | PlayAction::JokerCall(card) => card,
+            PlayAction::JokerStart(_) => Card::Joker,
+        };
+        if self.played == 0 {
+            actions.sort_by_key(|&a| std::cmp::Reverse(self.card_value(card_of(a))));
+            return;
+        }
+        let (winner, top) = (0..5)
+            .filter_map(|i| self.submitted[i].map(|c| (i, self.card_value(c))))
+            .max_by_key(|&(_, v)| v)
+            .unwrap();
+        let ally_winning = self.is_leading(winner) == self.is_leading(player);
+        actions.sort_by_key(|&a| {
+            let card = card_of(a);
+            let value = self.card_value(card);
+            let points = card.score() as i16;
+            if ally_winning {
+                (value > top, -points, value)
+            } else {
+                (value <= top, points, value)
+            }
+        });
+    }
+
+    pub(crate) fn apply(&mut self, player: PlayerIndex, action: PlayAction) {
+        let card = match action {
+            PlayAction::Hand(card) => {
+                if let (0, Card::Shaped(s, _)) = (self.played, card) {
+                    self.round_suit = Some(s);
+                }
+                card
+            }
+            PlayAction::JokerCall(card) => {
+                self.joker_called = true;
+                if let Card::Shaped(s, _) = card {
+                    self.round_suit = Some(s);
+                }
+                card
+            }
+            PlayAction::JokerStart(s) => {
+                self.round_suit = Some(s);
+                Card::Joker
+            }
+        };
+        self.hands[player] &= !(1u64 << card.index());
+        self.submitted[player] = Some(card);
+        self.played += 1;
+        if self.partner_revealed.is_none()
+            && self.partner_condition == PartnerCondition::CardCondition(card)
+        {
+            self.partner_revealed = Some(player);
+        }
+
+        if self.played == 5 {
+            self.finish_round();
+        }
+    }
+
+    fn finish_round(&mut self) {
+        let mut winner = 0;
+        let mut best = 0;
+        let mut points = 0;
+        for (i, card) in self.submitted.iter().enumerate() {
+            let card = card.unwrap();
+            let value = self.card_value(card);
+            if value >= best {
+                best = value;
+                winner = i;
+            }
+            points += card.score();
+        }
+        self.scores[winner] += if self.tricks { 1 } else { points };
+
+        if self.partner_revealed.is_none()
+            && self.partner_condition == PartnerCondition::Round(self.round)
+        {
+            self.partner_revealed = Some(winner);
+        }
+
+        self.round += 1;
+        self.round_suit = None;
+        self.submitted = [None; 5];
+        self.played = 0;
+        self.round_starter = winner;
+        self.joker_called = false;
+    }
+
+    fn card_value(&self, card: Card) -> u8 {
+        card_value(
+            card,
+            self.trump,
+            self.round_suit,
+            self.round,
+            self.joker_called,
+        )
+    }
+}
+
+fn mask<'a>(cards: impl Iterator<Item = &'a Card>) -> u64 {
+    cards.fold(0, |acc, c| acc | 1u64 << c.index())
+}
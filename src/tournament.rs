@@ -0,0 +1,360 @@
+use crate::bidding_phase::PledgePhase;
+use crate::clock::TimeControl;
+use crate::game::{play_hand, HandEnd};
+use crate::play_phase::{EarlyEnd, PartyType};
+use crate::player::Player;
+use crate::rules::RuleSet;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt;
+
+pub type PlayerFactory = Box<dyn Fn() -> Box<dyn Player> + Send + Sync>;
+
+pub struct Entrant {
+    pub name: String,
+    pub factory: PlayerFactory,
+}
+
+impl Entrant {
+    pub fn new(name: &str, factory: impl Fn() -> Box<dyn Player> + Send + Sync + 'static) -> Self {
+        Entrant {
+            name: name.to_string(),
+            factory: Box::new(factory),
+        }
+    }
+}
+
+/// Self-play between up to five bots. The entrants fill the five seats in
+/// turn, and every distinct seating of that lineup is played in rotation, so
+/// that no bot keeps a favourable seat or neighbour. With fewer than five
+/// bots, which of them sit twice rotates from game to game as well.
+pub struct Tournament {
+    pub entrants: Vec<Entrant>,
+    pub rules: RuleSet,
+    pub games: usize,
+    pub seed: u64,
+    /// A bot over its time has a default move made for it.
+    pub time_control: TimeControl,
+}
+
+#[derive(Clone, Debug)]
+pub struct BotStats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    pub declarer_games: usize,
+    pub declarer_makes: usize,
+    pub payout_sum: i64,
+    pub payout_square_sum: i64,
+    pub rating: Rating,
+    /// Moves made for the bot because it ran out of time.
+    pub timeouts: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentReport {
+    pub games: usize,
+    pub cancelled: usize,
+    pub stats: Vec<BotStats>,
+}
+
+impl Tournament {
+    pub fn run(&self) -> TournamentReport {
+        assert!((1..=5).contains(&self.entrants.len()));
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut report = TournamentReport {
+            games: 0,
+            cancelled: 0,
+            stats: self
+                .entrants
+                .iter()
+                .map(|e| BotStats::new(&e.name))
+                .collect(),
+        };
+        for game in 0..self.games {
+            let seating = &self.seating(game);
+            let players = seating
+                .iter()
+                .map(|&e| (self.entrants[e].factory)())
+                .collect::<Vec<_>>();
+            let deal = PledgePhase::with_rng(0, self.rules.min_pledge, &mut rng);
+            let (end, record) = play_hand(&players, deal, self.time_control, EarlyEnd::Never);
+            for &(seat, _) in &record.timeouts {
+                report.stats[seating[seat]].timeouts += 1;
+            }
+            match end {
+                HandEnd::Cancelled => report.cancelled += 1,
+                end => {
+                    report.record(seating, &end, &self.rules);
+                    report.games += 1;
+                }
+            }
+        }
+        report
+    }
+
+    /// The entrant in each seat in game number `game`. Over every cycle of
+    /// seatings, each entrant sits in each seat equally often.
+    pub fn seating(&self, game: usize) -> Vec<usize> {
+        let n = self.entrants.len();
+        let lineup = (0..5).map(|seat| (seat + game) % n).collect::<Vec<_>>();
+        let seatings = distinct_permutations(&lineup);
+        seatings[game / n % seatings.len()].clone()
+    }
+}
+
+impl TournamentReport {
+    fn record(&mut self, seating: &[usize], end: &HandEnd, rules: &RuleSet) {
+        // A declarer who surrenders loses to everyone else.
+        let (declarer, win, made) = match end {
+            HandEnd::Cancelled => return,
+            HandEnd::Surrendered(surrender) => {
+                let win = (0..5).map(|seat| seat != surrender.declarer).collect();
+                (surrender.declarer, win, false)
+            }
+            HandEnd::Played(game) => {
+                let result = game.result();
+                let made = result.win_side == PartyType::Leading;
+                (game.declarer, result.win, made)
+            }
+        };
+        let payouts = end.payouts(rules);
+        for (seat, &entrant) in seating.iter().enumerate() {
+            let stats = &mut self.stats[entrant];
+            let payout = payouts[seat] as i64;
+            stats.games += 1;
+            stats.wins += win[seat] as usize;
+            stats.payout_sum += payout;
+            stats.payout_square_sum += payout * payout;
+            if seat == declarer {
+                stats.declarer_games += 1;
+                stats.declarer_makes += made as usize;
+            }
+        }
+
+        let teams = seating
+            .iter()
+            .enumerate()
+            .map(|(seat, &entrant)| (entrant, win[seat]))
+            .collect::<Vec<_>>();
+        let ratings = self.stats.iter().map(|s| s.rating).collect::<Vec<_>>();
+        for (stats, rating) in self.stats.iter_mut().zip(update_ratings(&ratings, &teams)) {
+            stats.rating = rating;
+        }
+    }
+}
+
+impl BotStats {
+    fn new(name: &str) -> Self {
+        BotStats {
+            name: name.to_string(),
+            games: 0,
+            wins: 0,
+            declarer_games: 0,
+            declarer_makes: 0,
+            payout_sum: 0,
+            payout_square_sum: 0,
+            rating: Rating::default(),
+            timeouts: 0,
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    /// 95% Wilson score interval of the win rate.
+    pub fn win_rate_interval(&self) -> (f64, f64) {
+        let n = self.games.max(1) as f64;
+        let p = self.win_rate();
+        let z2 = Z95 * Z95;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half = Z95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        (center - half, center + half)
+    }
+
+    pub fn make_rate(&self) -> f64 {
+        self.declarer_makes as f64 / self.declarer_games.max(1) as f64
+    }
+
+    pub fn mean_payout(&self) -> f64 {
+        self.payout_sum as f64 / self.games.max(1) as f64
+    }
+
+    /// 95% normal interval of the mean payout per game.
+    pub fn payout_interval(&self) -> (f64, f64) {
+        mean_interval(
+            self.payout_sum as f64,
+            self.payout_square_sum as f64,
+            self.games,
+        )
+    }
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games, {} cancelled pledges",
+            self.games, self.cancelled
+        )?;
+        writeln!(
+            f,
+            "{:<16} {:>7} {:>21} {:>7} {:>22} {:>18} {:>8}",
+            "bot", "games", "win rate", "make", "payout", "rating", "timeouts"
+        )?;
+        for s in &self.stats {
+            let (win_lo, win_hi) = s.win_rate_interval();
+            let (pay_lo, pay_hi) = s.payout_interval();
+            let (rating_lo, rating_hi) = s.rating.interval();
+            writeln!(
+                f,
+                "{:<16} {:>7} {:>5.1}% [{:>5.1}, {:>5.1}] {:>6.1}% {:>6.2} [{:>6.2}, {:>6.2}] {:>5.1} [{:.1}, {:.1}] {:>8}",
+                s.name,
+                s.games,
+                100.0 * s.win_rate(),
+                100.0 * win_lo,
+                100.0 * win_hi,
+                100.0 * s.make_rate(),
+                s.mean_payout(),
+                pay_lo,
+                pay_hi,
+                s.rating.mu,
+                rating_lo,
+                rating_hi,
+                s.timeouts,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+const Z95: f64 = 1.96;
+
+/// 95% normal interval of a mean, from the sum and sum of squares of samples.
+pub(crate) fn mean_interval(sum: f64, square_sum: f64, n: usize) -> (f64, f64) {
+    let n = n.max(1) as f64;
+    let mean = sum / n;
+    let variance = (square_sum / n - mean * mean).max(0.0);
+    let half = Z95 * (variance / n).sqrt();
+    (mean - half, mean + half)
+}
+
+/// A TrueSkill-style skill estimate: mean and standard deviation.
+#[derive(Copy, Clone, Debug)]
+pub struct Rating {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+const INITIAL_MU: f64 = 25.0;
+const INITIAL_SIGMA: f64 = INITIAL_MU / 3.0;
+const BETA: f64 = INITIAL_SIGMA / 2.0;
+const TAU: f64 = INITIAL_SIGMA / 100.0;
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            mu: INITIAL_MU,
+            sigma: INITIAL_SIGMA,
+        }
+    }
+}
+
+impl Rating {
+    pub fn interval(&self) -> (f64, f64) {
+        (self.mu - Z95 * self.sigma, self.mu + Z95 * self.sigma)
+    }
+}
+
+/// One two-team TrueSkill update for a finished hand.
+///
+/// Teams change every hand and differ in size (declarer and friend against
+/// three, or a lone declarer against four), so a team performs as the average
+/// of its members rather than their sum. A bot seated more than once gets the
+/// combined update of all its seats.
+fn update_ratings(ratings: &[Rating], seats: &[(usize, bool)]) -> Vec<Rating> {
+    let winners = seats.iter().filter(|(_, w)| *w).count() as f64;
+    let losers = seats.len() as f64 - winners;
+    let weight = |won: bool| if won { 1.0 / winners } else { 1.0 / losers };
+    let variance = |e: usize| ratings[e].sigma.powi(2) + TAU * TAU;
+
+    let mut margin = 0.0;
+    let mut c2 = 0.0;
+    for &(e, won) in seats {
+        let w = weight(won);
+        margin += if won { w } else { -w } * ratings[e].mu;
+        c2 += w * w * (variance(e) + BETA * BETA);
+    }
+    let c = c2.sqrt();
+    let t = margin / c;
+    let v = normal_pdf(t) / normal_cdf(t).max(f64::MIN_POSITIVE);
+    let shrink = v * (v + t);
+
+    let mut updated = ratings.to_vec();
+    let mut variances = ratings.iter().map(|r| r.sigma.powi(2)).collect::<Vec<_>>();
+    for &(e, _) in seats {
+        variances[e] = variance(e);
+    }
+    for &(e, won) in seats {
+        let w = weight(won);
+        let sign = if won { 1.0 } else { -1.0 };
+        updated[e].mu += sign * w * variance(e) / c * v;
+        variances[e] *= (1.0 - w * w * variance(e) / c2 * shrink).max(0.0);
+    }
+    for (rating, variance) in updated.iter_mut().zip(variances) {
+        rating.sigma = variance.sqrt();
+    }
+    updated
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// Numerical Recipes' erfc, accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+fn distinct_permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    let mut current = items.to_vec();
+    current.sort();
+    let mut permutations = vec![current.clone()];
+    // Next lexicographic permutation until it wraps around.
+    loop {
+        let Some(i) = (1..current.len())
+            .rev()
+            .find(|&i| current[i - 1] < current[i])
+        else {
+            return permutations;
+        };
+        let j = (i..current.len())
+            .rev()
+            .find(|&j| current[j] > current[i - 1])
+            .unwrap();
+        current.swap(i - 1, j);
+        current[i..].reverse();
+        permutations.push(current.clone());
+    }
+}
@@ -0,0 +1,336 @@
+//! Self-play training of an `Mlp` for the play phase, by the policy
+//! gradient. Each iteration deals a batch of hands, takes them to the play
+//! phase with the bids of `RandomPlayer` and the clock's default plan, and
+//! plays every seat by sampling from the network on copies of the
+//! `PlayPhase`. The reward of a seat is 1 for a won hand and -1 for a lost
+//! one, less a running average of the rewards.
+
+use crate::bidding_phase::PledgePhase;
+use crate::clock::default_plan;
+use crate::encoding::{Action, Layout};
+use crate::extra_phase::ExtraPhase;
+use crate::mlp::{softmax, Mlp};
+use crate::play_phase::PlayPhase;
+use crate::player::{create_player, Player, RandomPlayer};
+use crate::rules::RuleSet;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+pub struct TrainConfig {
+    /// The widths of the hidden layers; none for a linear policy.
+    pub hidden: Vec<usize>,
+    pub learning_rate: f32,
+    /// Hands of self-play per update.
+    pub batch: usize,
+    pub iterations: usize,
+    /// Seeds the weights, the deals and the sampling.
+    pub seed: u64,
+    /// Evaluates, and saves a checkpoint, every this many iterations and
+    /// after the last; 0 for only after the last.
+    pub eval_every: usize,
+    /// Deals to evaluate on. Each is played once with the network in every
+    /// seat, against `opponent` in the others.
+    pub eval_hands: usize,
+    pub eval_seed: u64,
+    pub opponent: String,
+    /// Where the network is saved, with the progress of training next to it
+    /// in a `.state` file, for `Trainer::load` to go on from.
+    pub checkpoint: Option<PathBuf>,
+    pub rules: RuleSet,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            hidden: vec![64],
+            learning_rate: 0.01,
+            batch: 32,
+            iterations: 100,
+            seed: 0,
+            eval_every: 10,
+            eval_hands: 100,
+            eval_seed: 1_000_000,
+            opponent: "heuristic".to_string(),
+            checkpoint: None,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+/// How the network did against the opponent.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Evaluation {
+    /// Hands played, one for each seat of each deal.
+    pub hands: usize,
+    pub wins: usize,
+    pub payout_sum: i64,
+}
+
+impl Evaluation {
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.hands.max(1) as f64
+    }
+
+    pub fn mean_payout(&self) -> f64 {
+        self.payout_sum as f64 / self.hands.max(1) as f64
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub iteration: usize,
+    pub decisions: usize,
+    /// The mean entropy of the network's choices, in nats.
+    pub entropy: f32,
+    pub evaluation: Option<Evaluation>,
+}
+
+pub struct Trainer {
+    pub config: TrainConfig,
+    pub network: Mlp,
+    /// Iterations done so far.
+    pub iteration: usize,
+    /// The seed of the next deal; training deals count up from the seed.
+    hands: u64,
+    baseline: f32,
+}
+
+/// One sampled decision of self-play.
+struct Step {
+    seat: usize,
+    features: Vec<f32>,
+    legal: Vec<usize>,
+    action: usize,
+}
+
+impl Trainer {
+    pub fn new(config: TrainConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let network = Mlp::new(Layout::LATEST, &config.hidden, &mut rng);
+        Trainer {
+            iteration: 0,
+            hands: config.seed,
+            baseline: 0.0,
+            config,
+            network,
+        }
+    }
+
+    /// Goes on from a checkpoint saved by `save`, as if training had never
+    /// stopped.
+    pub fn load(config: TrainConfig, path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let network = Mlp::load(path)?;
+        let state_path = state_path(path);
+        let error = |e: String| format!("{}: {}", state_path.display(), e);
+        let text = fs::read_to_string(&state_path).map_err(|e| error(e.to_string()))?;
+        let mut trainer = Trainer {
+            iteration: 0,
+            hands: config.seed,
+            baseline: 0.0,
+            config,
+            network,
+        };
+        for line in text.lines() {
+            let invalid = || error(format!("invalid line: {}", line));
+            let (key, value) = line.split_once(' ').ok_or_else(invalid)?;
+            match key {
+                "iteration" => trainer.iteration = value.parse().map_err(|_| invalid())?,
+                "hands" => trainer.hands = value.parse().map_err(|_| invalid())?,
+                "baseline" => trainer.baseline = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(trainer)
+    }
+
+    /// Saves the network to `path` and the progress of training next to it.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        self.network.save(path)?;
+        let state = format!(
+            "iteration {}\nhands {}\nbaseline {}\n",
+            self.iteration, self.hands, self.baseline
+        );
+        let state_path = state_path(path);
+        fs::write(&state_path, state).map_err(|e| format!("{}: {}", state_path.display(), e))
+    }
+
+    /// The hand for `seed` at the start of the play phase, bid by
+    /// `RandomPlayer` with the first bid moving round the table and planned
+    /// by `default_plan`. `None` when every player passed.
+    pub fn deal(&self, seed: u64) -> Option<PlayPhase> {
+        let setup = RandomPlayer {};
+        let start = (seed % 5) as usize;
+        let mut pledge = PledgePhase::seeded(start, self.config.rules.min_pledge, seed);
+        while !pledge.pledge_done() {
+            let player = pledge.turn_player();
+            pledge.player_bids(player, setup.bidding(&pledge.bidding_state(player)));
+        }
+        if pledge.cancelled() {
+            return None;
+        }
+        let mut extra = ExtraPhase::from_pledge(pledge);
+        let (contract, friend, discards) = default_plan(&extra.extra_state());
+        Some(extra.submit_plan(contract, friend, discards))
+    }
+
+    /// The legal indices of `player`, in increasing order.
+    fn legal(&self, game: &PlayPhase, player: usize) -> Vec<usize> {
+        let layout = self.network.layout;
+        let mut legal = game
+            .legal_actions(player)
+            .into_iter()
+            .map(|a| layout.encode_action(Action::Play(a)))
+            .collect::<Vec<_>>();
+        legal.sort();
+        legal
+    }
+
+    fn act(game: &mut PlayPhase, layout: Layout, player: usize, index: usize) {
+        match layout.decode_action(index) {
+            Some(Action::Play(action)) => game.player_acts(player, action),
+            _ => unreachable!("legal indices are play actions"),
+        }
+    }
+
+    /// Plays a batch of hands against itself and takes a step of the policy
+    /// gradient. Returns the number of decisions and their mean entropy.
+    pub fn iteration(&mut self) -> (usize, f32) {
+        // Seeded by the iteration, so that training goes on the same after
+        // a checkpoint is loaded.
+        let mut rng = StdRng::seed_from_u64(self.config.seed ^ (self.iteration as u64) << 32);
+        self.iteration += 1;
+        let layout = self.network.layout;
+        let mut gradient = self.network.zero_gradient();
+        let mut hands = vec![];
+        let mut entropy = 0.0;
+        for _ in 0..self.config.batch {
+            let seed = self.hands;
+            self.hands += 1;
+            let Some(mut game) = self.deal(seed) else {
+                continue;
+            };
+            let mut steps = vec![];
+            while let Some(seat) = game.turn_player() {
+                let features = layout.encode_play(&game.play_state(seat));
+                let legal = self.legal(&game, seat);
+                let probabilities = softmax(&self.network.forward(&features), &legal);
+                entropy -= probabilities
+                    .iter()
+                    .map(|&p| if p > 0.0 { p * p.ln() } else { 0.0 })
+                    .sum::<f32>();
+                let mut point = rng.random::<f32>();
+                let chosen = probabilities
+                    .iter()
+                    .position(|p| {
+                        point -= p;
+                        point < 0.0
+                    })
+                    .unwrap_or(legal.len() - 1);
+                let action = legal[chosen];
+                Trainer::act(&mut game, layout, seat, action);
+                steps.push(Step {
+                    seat,
+                    features,
+                    legal,
+                    action,
+                });
+            }
+            hands.push((game.result(), steps));
+        }
+
+        let decisions = hands.iter().map(|(_, steps)| steps.len()).sum::<usize>();
+        if decisions == 0 {
+            return (0, 0.0);
+        }
+        let mut rewards = 0.0;
+        for (result, steps) in &hands {
+            for step in steps {
+                let reward = if result.win[step.seat] { 1.0 } else { -1.0 };
+                rewards += reward;
+                let advantage = (reward - self.baseline) / decisions as f32;
+                self.network.accumulate(
+                    &mut gradient,
+                    &step.features,
+                    &step.legal,
+                    step.action,
+                    advantage,
+                );
+            }
+        }
+        self.network.apply(&gradient, self.config.learning_rate);
+        self.baseline += 0.1 * (rewards / decisions as f32 - self.baseline);
+        (decisions, entropy / decisions as f32)
+    }
+
+    /// Plays the evaluation deals with the network's best action in one
+    /// seat and the opponent in the others.
+    pub fn evaluate(&self) -> Result<Evaluation, String> {
+        let layout = self.network.layout;
+        let opponents = (0..5)
+            .map(|_| create_player(&self.config.opponent))
+            .collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+        let mut evaluation = Evaluation::default();
+        for i in 0..self.config.eval_hands {
+            let Some(dealt) = self.deal(self.config.eval_seed.wrapping_add(i as u64)) else {
+                continue;
+            };
+            for seat in 0..5 {
+                let mut game = dealt.clone();
+                while let Some(player) = game.turn_player() {
+                    let state = game.play_state(player);
+                    if player == seat {
+                        let legal = self.legal(&game, player);
+                        let best = self.network.best(&layout.encode_play(&state), &legal);
+                        Trainer::act(&mut game, layout, player, best.unwrap());
+                    } else {
+                        game.player_acts(player, opponents[player].play_action(state));
+                    }
+                    for (i, opponent) in opponents.iter().enumerate() {
+                        opponent.observe(&game.play_state(i));
+                    }
+                }
+                evaluation.hands += 1;
+                evaluation.wins += game.result().win[seat] as usize;
+                evaluation.payout_sum += self.config.rules.settle(&game).payouts[seat] as i64;
+            }
+        }
+        Ok(evaluation)
+    }
+
+    /// Runs the iterations left, evaluating and saving a checkpoint as
+    /// configured, and reports the progress after each iteration.
+    pub fn run(&mut self, mut report: impl FnMut(&Progress)) -> Result<(), String> {
+        while self.iteration < self.config.iterations {
+            let (decisions, entropy) = self.iteration();
+            let iteration = self.iteration;
+            let due =
+                self.config.eval_every > 0 && iteration.is_multiple_of(self.config.eval_every);
+            let mut evaluation = None;
+            if due || iteration == self.config.iterations {
+                evaluation = Some(self.evaluate()?);
+                if let Some(path) = &self.config.checkpoint {
+                    self.save(path)?;
+                }
+            }
+            report(&Progress {
+                iteration,
+                decisions,
+                entropy,
+                evaluation,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Where the progress of training is saved next to the network at `path`.
+fn state_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".state");
+    PathBuf::from(name)
+}
@@ -0,0 +1,117 @@
+//! The browser table. Browsers reach the game port over HTTP: a plain
+//! request gets the table page, and the page's WebSocket upgrade becomes a
+//! seat speaking the same messages as the raw protocol, one per text frame.
+
+use crate::server::MAX_LINE;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+/// The table client, a single page with no outside resources.
+pub const PAGE: &str = include_str!("web/table.html");
+
+/// Whether the client opened with an HTTP request rather than a message.
+pub fn is_http(stream: &TcpStream) -> io::Result<bool> {
+    let mut first = [0; 1];
+    stream.peek(&mut first)?;
+    Ok(first[0] == b'G')
+}
+
+/// Answers an HTTP request. A WebSocket upgrade is returned to play on; any
+/// other request gets the page, or a 404, and the connection is done.
+pub fn accept(mut stream: TcpStream) -> io::Result<Option<WebSocket<TcpStream>>> {
+    let head = peek_head(&stream)?;
+    let text = String::from_utf8_lossy(&head).to_ascii_lowercase();
+    if text.contains("\r\nupgrade: websocket") {
+        let config = WebSocketConfig::default()
+            .max_message_size(Some(MAX_LINE))
+            .max_frame_size(Some(MAX_LINE));
+        return tungstenite::accept_with_config(stream, Some(config))
+            .map(Some)
+            .map_err(|e| io::Error::other(e.to_string()));
+    }
+    stream.read_exact(&mut vec![0; head.len()])?;
+    let response = if text.starts_with("get / ") || text.starts_with("get /index.html ") {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            PAGE.len(),
+            PAGE
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())?;
+    Ok(None)
+}
+
+/// The request line and headers, left unread on the stream.
+fn peek_head(stream: &TcpStream) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; 8192];
+    loop {
+        let n = stream.peek(&mut buf)?;
+        if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            buf.truncate(end + 4);
+            return Ok(buf);
+        }
+        if n == 0 || n == buf.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad request"));
+        }
+        // The rest of the head is still on its way.
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Carries messages between a WebSocket and a connection until either side
+/// goes away. The socket is read with a short timeout so that outgoing
+/// messages are not held up by a quiet browser.
+pub fn pump(
+    mut socket: WebSocket<TcpStream>,
+    incoming: Sender<String>,
+    outgoing: Receiver<String>,
+) {
+    if socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .is_err()
+    {
+        return;
+    }
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if incoming.send(text.to_string()).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            // Pings are answered by the socket itself.
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+        loop {
+            match outgoing.try_recv() {
+                Ok(text) => {
+                    if socket.send(Message::text(text)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = socket.close(None);
+                    let _ = socket.flush();
+                    return;
+                }
+            }
+        }
+    }
+}
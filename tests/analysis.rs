@@ -0,0 +1,152 @@
+use mighty_rs::analysis::{mark_mistakes, on_declarer_side, sample_world, Advisor, Mistake};
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::Card;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::common::PartnerCondition;
+use mighty_rs::game::play_timed_game;
+use mighty_rs::inference::Knowledge;
+use mighty_rs::player::create_player;
+use mighty_rs::record::GameRecord;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashSet;
+
+fn small_advisor() -> Advisor {
+    Advisor {
+        worlds: 4,
+        depth: 3,
+        ..Advisor::default()
+    }
+}
+
+/// The record of a hand played to the end by random bots.
+fn finished_record() -> GameRecord {
+    let players = (0..5)
+        .map(|_| create_player("random").unwrap())
+        .collect::<Vec<_>>();
+    (0..)
+        .find_map(|seed| {
+            let deal = PledgePhase::seeded(0, 13, seed);
+            let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+            end.played().is_some().then_some(record)
+        })
+        .unwrap()
+}
+
+#[test]
+fn sampled_worlds_agree_with_the_seat() {
+    let record = finished_record();
+    let mut rng = StdRng::seed_from_u64(1);
+    for actions in [0, 12, 27, 44] {
+        let game = record.play_phase(actions).unwrap();
+        for seat in 0..5 {
+            let state = game.play_state(seat);
+            let knowledge = Knowledge::new(seat, &state);
+            let world = sample_world(seat, &state, &mut rng).unwrap();
+            assert_eq!(world.hands[seat], state.hand);
+            assert_eq!(world.submitted, game.submitted);
+            let mut cards = world.discarded.iter().collect::<HashSet<_>>();
+            for (player, hand) in world.hands.iter().enumerate() {
+                assert_eq!(hand.len(), game.hands[player].len());
+                assert!(hand.iter().all(|&c| knowledge.can_hold(player, c)));
+                cards.extend(hand);
+            }
+            cards.extend(knowledge.played.keys());
+            assert_eq!(cards.len(), Card::deck().len());
+            if seat == game.declarer {
+                assert_eq!(world.discarded, game.discarded);
+            }
+        }
+    }
+}
+
+#[test]
+fn hints_are_legal_and_ranked_for_the_mover() {
+    let record = finished_record();
+    let advisor = small_advisor();
+    for actions in [5, 30, 48] {
+        let game = record.play_phase(actions).unwrap();
+        let seat = game.turn_player().unwrap();
+        let state = game.play_state(seat);
+        let values = advisor.evaluate(seat, &state);
+        assert_eq!(values.len(), state.legal_actions().len());
+        let points = values.iter().map(|&(_, p)| p).collect::<Vec<_>>();
+        let ranked = points.windows(2).all(|w| {
+            if on_declarer_side(seat, &state) {
+                w[0] >= w[1]
+            } else {
+                w[0] <= w[1]
+            }
+        });
+        assert!(ranked);
+
+        let hint = advisor.hint(seat, &state).unwrap();
+        assert_eq!(hint.action, values[0].0);
+        assert!(hint.delta >= 0.0);
+        assert_eq!(advisor.hint(seat, &state), Some(hint));
+    }
+}
+
+#[test]
+fn hints_tell_who_may_be_the_friend() {
+    let record = finished_record();
+    let advisor = small_advisor();
+    let mut checked = 0;
+    for actions in 0..5 {
+        let game = record.play_phase(actions).unwrap();
+        let PartnerCondition::CardCondition(card) = game.partner_condition else {
+            unreachable!();
+        };
+        let seat = game.turn_player().unwrap();
+        let state = game.play_state(seat);
+        let hint = advisor.hint(seat, &state).unwrap();
+        if game.partner_revealed.is_some() || state.hand.contains(&card) {
+            assert_eq!(hint.friend, None);
+            continue;
+        }
+        let friend = hint.friend.unwrap();
+        assert_eq!(friend[seat], 0.0);
+        if let Some(holder) = (0..5).find(|&p| game.hands[p].contains(&card)) {
+            assert!(friend[holder] > 0.0);
+        }
+        assert!(friend.iter().sum::<f64>() <= 1.0 + 1e-9);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn reviews_mark_mistakes_in_the_record() {
+    let mut record = finished_record();
+    let advisor = Advisor {
+        inaccuracy: 0.5,
+        blunder: 2.0,
+        ..small_advisor()
+    };
+    let annotations = advisor.review(&record, &[0, 2]).unwrap();
+    assert!(!annotations.is_empty());
+    for annotation in &annotations {
+        assert!([0, 2].contains(&annotation.player));
+        assert_eq!(
+            record.actions[annotation.action],
+            (annotation.player, annotation.played)
+        );
+        let expected = if annotation.loss >= 2.0 {
+            Some(Mistake::Blunder)
+        } else if annotation.loss >= 0.5 {
+            Some(Mistake::Inaccuracy)
+        } else {
+            None
+        };
+        assert_eq!(annotation.mistake, expected);
+    }
+
+    mark_mistakes(&mut record, &annotations);
+    let marked = annotations.iter().filter(|a| a.mistake.is_some()).count();
+    assert_eq!(record.notes.len(), marked);
+    let text = record.to_string();
+    assert_eq!(text.matches("\nnote ").count(), marked);
+    let parsed = text.parse::<GameRecord>().unwrap();
+    assert_eq!(parsed, record);
+    assert!(parsed.play_phase(parsed.actions.len()).unwrap().is_done());
+}
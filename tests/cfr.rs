@@ -0,0 +1,95 @@
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::{Card, Suit};
+use mighty_rs::cfr::{abstract_bid, denomination, Cfr, CfrPlayer, InfoSet, Strategy};
+use mighty_rs::common::Contract;
+use mighty_rs::game::play_game;
+use mighty_rs::player::Player;
+use mighty_rs::rules::RuleSet;
+use std::collections::HashSet;
+
+fn hand(cards: &str) -> HashSet<Card> {
+    cards.split(' ').map(|c| c.parse().unwrap()).collect()
+}
+
+#[test]
+fn hands_are_bucketed_by_trump() {
+    let state = BiddingState {
+        hand: hand("SA SK SQ S10 S9 S3 JK DA H2 C5"),
+        curr_contract: Some(Contract {
+            suit: Some(Suit::H),
+            count: 14,
+        }),
+        min_effective_count: 14,
+        players_remaining: 3,
+    };
+    let info = InfoSet::new(&state);
+    assert_eq!(info.strength, [1, 1, 1, 3, 2]);
+    assert_eq!(info.contract, Some(14));
+    assert_eq!(info.players_remaining, 3);
+
+    assert_eq!(denomination(&state.hand), Some(Suit::S));
+    assert_eq!(abstract_bid(&state, 0), Some(None));
+    let raise = |count| {
+        Some(Some(Contract {
+            suit: Some(Suit::S),
+            count,
+        }))
+    };
+    assert_eq!(abstract_bid(&state, 1), raise(15));
+    assert_eq!(abstract_bid(&state, 3), raise(17));
+
+    let high = BiddingState {
+        min_effective_count: 19,
+        ..state
+    };
+    assert_eq!(abstract_bid(&high, 1), raise(20));
+    assert_eq!(abstract_bid(&high, 2), None);
+}
+
+#[test]
+fn strategies_are_saved_as_text() {
+    let mut solver = Cfr::new(RuleSet::default(), "random", 3).unwrap();
+    solver.iterate(30);
+    assert_eq!(solver.iterations(), 30);
+    let strategy = solver.strategy();
+    assert_eq!(strategy.table.len(), solver.info_sets());
+    for probabilities in strategy.table.values() {
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    let text = strategy.to_string();
+    let parsed = text.parse::<Strategy>().unwrap();
+    assert_eq!(parsed.table.len(), strategy.table.len());
+    assert_eq!(parsed.to_string(), text);
+
+    assert!("".parse::<Strategy>().is_err());
+    let header = text.lines().next().unwrap();
+    assert!(format!("{}\n0 0 0 0 0 - 5 1.0", header)
+        .parse::<Strategy>()
+        .is_err());
+    assert!(format!("{}\n4 0 0 0 0 - 5 1 0 0 0", header)
+        .parse::<Strategy>()
+        .is_err());
+}
+
+#[test]
+fn players_follow_the_table() {
+    let mut solver = Cfr::new(RuleSet::default(), "random", 5).unwrap();
+    solver.iterate(50);
+    let strategy = solver.strategy();
+    let players = (0..5)
+        .map(|seed| Box::new(CfrPlayer::new(strategy.clone()).with_seed(seed)) as Box<dyn Player>)
+        .collect::<Vec<_>>();
+    for seed in 0..5 {
+        // Bids are checked by the pledge phase as they are made.
+        if let Some(game) = play_game(&players, PledgePhase::seeded(0, 13, seed)).played() {
+            assert!(game.is_done());
+        }
+    }
+
+    // An information set missing from the table is passed.
+    let player = CfrPlayer::new(Strategy::default());
+    let deal = PledgePhase::seeded(0, 13, 1);
+    assert_eq!(player.bidding(&deal.bidding_state(0)), None);
+    assert!(Cfr::new(RuleSet::default(), "nobody", 0).is_err());
+}
@@ -0,0 +1,233 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::claim::check_claim;
+use mighty_rs::common::{Claim, PartnerCondition, PlayerIndex};
+use mighty_rs::extra_phase::ExtraExposedState;
+use mighty_rs::game::play_timed_game;
+use mighty_rs::heuristic::{HeuristicPlayer, Weights};
+use mighty_rs::play_phase::{EarlyEnd, ExposedGameState, PlayPhase};
+use mighty_rs::player::Player;
+use mighty_rs::record::GameRecord;
+use mighty_rs::solver::Solver;
+use mighty_rs::{bidding_phase::BiddingState, card::Card, card::Suit, common::Contract};
+use mighty_rs::{clock::TimeControl, common::PlayAction};
+use std::collections::HashSet;
+
+/// The heuristic bot, claiming no more tricks in the last `rounds` rounds.
+struct Claimer {
+    bot: HeuristicPlayer,
+    rounds: u8,
+}
+
+impl Player for Claimer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        // Bid whatever it takes, so that every deal is played.
+        (state.players_remaining == 5).then_some(Contract {
+            suit: None,
+            count: state.min_effective_count,
+        })
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        self.bot.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        self.bot.play_action(state)
+    }
+
+    fn claim(&self, state: &ExposedGameState) -> Option<Claim> {
+        (10 - state.round <= self.rounds).then_some(Claim::Tricks(0))
+    }
+}
+
+fn claimers(rounds: u8) -> Vec<Box<dyn Player>> {
+    (0..5)
+        .map(|_| {
+            Box::new(Claimer {
+                bot: HeuristicPlayer::new(Weights::default()),
+                rounds,
+            }) as Box<dyn Player>
+        })
+        .collect()
+}
+
+fn played(seed: u64) -> (PlayPhase, GameRecord) {
+    let (game, record) = play_timed_game(
+        &claimers(0),
+        PledgePhase::seeded(0, 13, seed),
+        TimeControl::default(),
+    );
+    (game.played().unwrap(), record)
+}
+
+#[test]
+fn claims_are_read_back() {
+    for claim in [Claim::Points(7), Claim::Tricks(3)] {
+        assert_eq!(claim.to_string().parse(), Ok(claim));
+    }
+    assert_eq!("points:20".parse(), Ok(Claim::Points(20)));
+    assert!("points:21".parse::<Claim>().is_err());
+    assert!("tricks:11".parse::<Claim>().is_err());
+    assert!("rounds:2".parse::<Claim>().is_err());
+}
+
+#[test]
+fn claims_hold_however_the_cards_lie() {
+    for seed in 0..4 {
+        let (_, record) = played(seed);
+        // The declarer to move, two rounds before the end.
+        let game = (40..45)
+            .map(|i| record.play_phase(i).unwrap())
+            .find(|g| g.turn_player() == Some(g.declarer))
+            .unwrap();
+        let player = game.declarer;
+        let before = game.declarer_team_score();
+        let exact = Solver::new().solve(&game).points - before;
+        let mut accepted = vec![];
+        for points in 0..=20 {
+            let Ok(line) = check_claim(&game, player, Claim::Points(points)) else {
+                continue;
+            };
+            accepted.push(points);
+            let mut end = game.clone();
+            for (p, action) in line {
+                end.player_acts(p, action);
+            }
+            assert!(end.is_done());
+            assert!(end.declarer_team_score() - before >= points);
+        }
+        // Nothing but a guarantee in every lie of the cards is accepted,
+        // and the real one is among them.
+        assert!(accepted.contains(&0));
+        assert!(accepted.windows(2).all(|w| w[1] == w[0] + 1));
+        assert!(*accepted.last().unwrap() <= exact);
+        assert!(check_claim(&game, player, Claim::Tricks(3)).is_err());
+    }
+}
+
+#[test]
+fn early_and_finished_hands_are_rejected() {
+    let (game, record) = played(5);
+    assert!(check_claim(&game, 0, Claim::Tricks(0)).is_err());
+    let start = record.play_phase(0).unwrap();
+    let error = check_claim(&start, start.declarer, Claim::Tricks(0)).unwrap_err();
+    assert!(error.contains("too early"));
+
+    let undecided = PlayPhase {
+        partner_condition: PartnerCondition::Round(9),
+        partner_revealed: None,
+        ..record.play_phase(45).unwrap()
+    };
+    let player = undecided.turn_player().unwrap();
+    assert!(check_claim(&undecided, player, Claim::Tricks(0)).is_err());
+}
+
+#[test]
+fn accepted_claims_end_the_hand() {
+    let players = claimers(1);
+    for seed in 0..4 {
+        let (game, record) = play_timed_game(
+            &players,
+            PledgePhase::seeded(0, 13, seed),
+            TimeControl::default(),
+        );
+        let game = game.played().unwrap();
+        assert!(game.is_done());
+        assert_eq!(record.play_phase(record.actions.len()).unwrap(), game);
+        let (moves, note) = &record.notes[0];
+        assert!(note.ends_with("claims tricks:0, accepted"));
+        // Claimed before the first card of the last round.
+        assert_eq!(*moves, record.bids.len() + 1 + 45);
+        let text = record.to_string();
+        assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    }
+}
+
+/// A hand two rounds from the end where the declarer, seat 0, holds the
+/// ace and queen of diamonds and the king is out. Seat 4 has led a small
+/// diamond; the king is either behind it in seat 4, or alone in one of the
+/// seats still to play. Both rounds are there for the taking in either
+/// lie, but only with a guess: the queen wins when seat 4 has the king, and
+/// the ace drops it otherwise.
+fn finesse(king: PlayerIndex) -> PlayPhase {
+    let cards = |text: &str| {
+        text.split_whitespace()
+            .map(|c| c.parse::<Card>().unwrap())
+            .collect::<Vec<_>>()
+    };
+    // Seats 0 to 4 in turn, seat 0 leading each.
+    let rounds = [
+        "HA H5 H7 H9 D10",
+        "HK H6 H8 D7 DJ",
+        "HQ D3 D5 D8 CJ",
+        "HJ D4 D6 D9 CQ",
+        "H10 C2 C3 C10 CK",
+        "SA S2 S5 S8 SJ",
+        "SK S3 S6 S9 CA",
+        "SQ S4 S7 S10 JK",
+    ];
+    let mut left = vec![
+        cards("DA DQ"),
+        cards("C4 C5"),
+        cards("C6 C7"),
+        cards("C8 C9"),
+        cards("D2 DK"),
+    ];
+    if king != 4 {
+        let club = left[king].pop().unwrap();
+        left[king].push(Card::Shaped(Suit::D, 13));
+        left[4][1] = club;
+    }
+    let mut hands = left
+        .into_iter()
+        .map(|h| h.into_iter().collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+    for round in rounds {
+        for (hand, card) in hands.iter_mut().zip(cards(round)) {
+            hand.insert(card);
+        }
+    }
+    let mut game = PlayPhase {
+        hands,
+        declarer: 0,
+        contract: Contract {
+            suit: Some(Suit::H),
+            count: 13,
+        },
+        partner_condition: PartnerCondition::None,
+        discarded: cards("H2 H3 H4").into_iter().collect(),
+        partner_revealed: None,
+        round: 0,
+        joker_called: false,
+        submitted: vec![None; 5],
+        round_starter: 0,
+        round_suit: None,
+        round_results: vec![],
+        early_end: EarlyEnd::Never,
+    };
+    for round in rounds {
+        for (player, card) in cards(round).into_iter().enumerate() {
+            game.player_acts(player, PlayAction::Hand(card));
+        }
+    }
+    game.player_acts(4, PlayAction::Hand(Card::Shaped(Suit::D, 2)));
+    game
+}
+
+#[test]
+fn guesses_are_not_claims() {
+    // Open-handed, the declarer takes both rounds wherever the king is.
+    for king in [4, 1] {
+        let game = finesse(king);
+        assert_eq!(game.turn_player(), Some(0));
+        assert_eq!(Solver::counting_tricks().solve(&game).points, 9);
+    }
+    // Blind to where it is, one round is all the declarer can promise.
+    let game = finesse(4);
+    assert!(check_claim(&game, 0, Claim::Tricks(1)).is_ok());
+    let error = check_claim(&game, 0, Claim::Tricks(2)).unwrap_err();
+    assert!(error.contains("guess"), "{}", error);
+}
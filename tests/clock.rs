@@ -0,0 +1,116 @@
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::Card;
+use mighty_rs::client::Client;
+use mighty_rs::clock::{default_action, TimeControl};
+use mighty_rs::common::{Contract, PartnerCondition, PlayAction};
+use mighty_rs::event::Event;
+use mighty_rs::extra_phase::ExtraExposedState;
+use mighty_rs::game::play_hand;
+use mighty_rs::play_phase::{EarlyEnd, ExposedGameState};
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::protocol::ClientMessage;
+use mighty_rs::rules::RuleSet;
+use mighty_rs::server::Server;
+use std::collections::HashSet;
+use std::io::Write;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+/// Thinks over every move for longer than it is given.
+struct Sleeper;
+
+impl Sleeper {
+    const THINKING: Duration = Duration::from_millis(20);
+}
+
+impl Player for Sleeper {
+    fn bidding(&self, _state: &BiddingState) -> Option<Contract> {
+        thread::sleep(Sleeper::THINKING);
+        Some(Contract {
+            suit: None,
+            count: 20,
+        })
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        thread::sleep(Sleeper::THINKING);
+        RandomPlayer {}.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        thread::sleep(Sleeper::THINKING);
+        state.legal_actions().into_iter().max().unwrap()
+    }
+}
+
+#[test]
+fn slow_moves_are_made_for_the_player() {
+    let mut players = (0..4)
+        .map(|_| Box::new(RandomPlayer {}) as Box<dyn Player>)
+        .collect::<Vec<_>>();
+    players.insert(0, Box::new(Sleeper));
+    let control = TimeControl {
+        per_move: Some(Duration::from_millis(5)),
+        per_game: None,
+    };
+    let deal = PledgePhase::seeded(0, 13, 5);
+    let (end, record) = play_hand(&players, deal, control, EarlyEnd::Never);
+    assert!(end.played().is_some());
+
+    // Seat 0 passes in place of its bid, and plays the lowest legal card.
+    assert_eq!(record.bids[0], (0, None));
+    assert!(record.timeouts.contains(&(0, 0)));
+    for (i, &(player, action)) in record.actions.iter().enumerate() {
+        if player == 0 {
+            let game = record.play_phase(i).unwrap();
+            assert_eq!(action, default_action(&game.play_state(0)));
+            let moves = record.bids.len() + 1 + i;
+            assert!(record.timeouts.contains(&(0, moves)));
+        }
+    }
+}
+
+#[test]
+fn silent_clients_run_out_of_time() {
+    let mut server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    server.time_control.per_move = Some(Duration::from_millis(100));
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        server.host_game(&mut seats, game).unwrap()
+    });
+
+    // Seat 0 joins and then never answers.
+    let mut silent = TcpStream::connect(addr).unwrap();
+    let join = ClientMessage::Join {
+        name: "silent".to_string(),
+        token: None,
+    };
+    writeln!(silent, "{}", join.to_json()).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let bots = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, "bot").unwrap();
+                client.play_hand(&RandomPlayer {}, |_| {}).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    host.join().unwrap();
+    for bot in bots {
+        let events = bot.join().unwrap();
+        assert_eq!(events[0], Event::Timeout { player: 0 });
+        let pass = Event::Bid {
+            player: 0,
+            contract: None,
+        };
+        assert_eq!(events[1], pass);
+        assert!(!events.contains(&Event::Away { player: 0 }));
+    }
+}
@@ -0,0 +1,122 @@
+use mighty_rs::clock::TimeControl;
+use mighty_rs::dataset::{DatasetFormat, DatasetWriter, GameData, View};
+use mighty_rs::encoding::Layout;
+use mighty_rs::json::Json;
+use mighty_rs::play_phase::EarlyEnd;
+use mighty_rs::rules::RuleSet;
+use mighty_rs::simulate::Simulation;
+use std::fs;
+use std::path::PathBuf;
+
+fn simulated(games: usize) -> Vec<GameData> {
+    let simulation = Simulation {
+        games,
+        seed: 5,
+        lineup: vec!["random".to_string(); 5],
+        rules: RuleSet::default(),
+        threads: 2,
+        time_control: TimeControl::default(),
+        early_end: EarlyEnd::Never,
+    };
+    simulation
+        .records(0..games)
+        .unwrap()
+        .into_iter()
+        .map(|(seed, record)| GameData::from_record(seed, &record, &simulation.rules).unwrap())
+        .collect()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mighty-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn every_decision_is_kept() {
+    let games = simulated(6);
+    assert_eq!(
+        games.iter().map(|g| g.game).collect::<Vec<_>>(),
+        (5..11).collect::<Vec<_>>()
+    );
+    for game in &games {
+        assert_eq!(game.outcomes.len(), 5);
+        for decision in &game.decisions {
+            assert!(decision.legal.contains(&decision.action));
+            assert!(!decision.timed_out);
+        }
+        let bids = game
+            .decisions
+            .iter()
+            .filter(|d| matches!(d.view, View::Bidding(_)))
+            .count();
+        if game.outcomes[0].won.is_none() {
+            assert_eq!(game.decisions.len(), bids);
+        } else {
+            // The contract, the friend and three discards, then ten rounds.
+            assert_eq!(game.decisions.len(), bids + 5 + 50);
+            let total: u8 = game.outcomes.iter().map(|o| o.points).sum();
+            assert!(total <= 20);
+            let payouts: i32 = game.outcomes.iter().map(|o| o.payout).sum();
+            assert_eq!(payouts, 0);
+        }
+    }
+}
+
+#[test]
+fn jsonl_shards_have_a_line_per_decision() {
+    let games = simulated(3);
+    let dir = temp_dir("jsonl");
+    let mut writer = DatasetWriter::new(&dir, DatasetFormat::Jsonl).unwrap();
+    let first = writer.write_shard(&games[..2]).unwrap();
+    let second = writer.write_shard(&games[2..]).unwrap();
+    assert!(first.ends_with("shard-00000.jsonl"));
+    assert!(second.ends_with("shard-00001.jsonl"));
+
+    let text = fs::read_to_string(&first).unwrap();
+    let rows = games[..2].iter().map(|g| g.decisions.len()).sum::<usize>();
+    assert_eq!(text.lines().count(), rows);
+    let row: Json = text.lines().next().unwrap().parse().unwrap();
+    assert_eq!(row.field("phase").and_then(Json::as_str), Ok("bidding"));
+    assert_eq!(row.field("game").and_then(Json::as_u64), Ok(5));
+    let action = row.field("action").unwrap().clone();
+    let legal = row.field("legal").and_then(Json::as_array).unwrap();
+    assert!(legal.contains(&action));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn column_shards_have_a_row_per_decision() {
+    let games = simulated(2);
+    let layout = Layout::LATEST;
+    let dir = temp_dir("columns");
+    let mut writer = DatasetWriter::new(&dir, DatasetFormat::Columns).unwrap();
+    let shard = writer.write_shard(&games).unwrap();
+    let rows = games.iter().map(|g| g.decisions.len()).sum::<usize>();
+
+    let size = |name: &str| fs::metadata(shard.join(name)).unwrap().len() as usize;
+    assert_eq!(size("features.bin"), rows * layout.observation_size() * 4);
+    assert_eq!(size("legal.bin"), rows * layout.action_count());
+    assert_eq!(size("action.bin"), rows * 2);
+    assert_eq!(size("game.bin"), rows * 8);
+    assert_eq!(size("payout.bin"), rows * 4);
+
+    // Each action is marked legal in its own row.
+    let legal = fs::read(shard.join("legal.bin")).unwrap();
+    let actions = fs::read(shard.join("action.bin")).unwrap();
+    for (row, action) in actions.chunks(2).enumerate() {
+        let action = u16::from_le_bytes([action[0], action[1]]) as usize;
+        assert_eq!(legal[row * layout.action_count() + action], 1);
+    }
+
+    let meta: Json = fs::read_to_string(shard.join("meta.json"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(meta.field("rows").and_then(Json::as_u64), Ok(rows as u64));
+    assert_eq!(
+        meta.field("layout").and_then(Json::as_u64),
+        Ok(layout.version() as u64)
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
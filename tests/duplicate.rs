@@ -0,0 +1,81 @@
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::Card;
+use mighty_rs::common::{Contract, PartnerCondition, PlayAction};
+use mighty_rs::duplicate::Duplicate;
+use mighty_rs::extra_phase::ExtraExposedState;
+use mighty_rs::play_phase::ExposedGameState;
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::rules::RuleSet;
+use mighty_rs::tournament::Entrant;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+type Dealt = Arc<Mutex<Vec<Vec<Card>>>>;
+
+/// Plays at random, noting the hand it was dealt.
+struct Witness {
+    dealt: Dealt,
+    seen: Cell<bool>,
+}
+
+impl Player for Witness {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        if !self.seen.replace(true) {
+            let mut hand = state.hand.iter().copied().collect::<Vec<_>>();
+            hand.sort();
+            self.dealt.lock().unwrap().push(hand);
+        }
+        RandomPlayer {}.bidding(state)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        RandomPlayer {}.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        RandomPlayer {}.play_action(state)
+    }
+}
+
+#[test]
+fn every_deal_is_played_from_every_seat() {
+    let dealt = (0..5).map(|_| Dealt::default()).collect::<Vec<_>>();
+    let duplicate = Duplicate {
+        entrants: dealt
+            .iter()
+            .map(|dealt| {
+                let dealt = dealt.clone();
+                Entrant::new("witness", move || {
+                    Box::new(Witness {
+                        dealt: dealt.clone(),
+                        seen: Cell::new(false),
+                    })
+                })
+            })
+            .collect(),
+        rules: RuleSet::default(),
+        deals: 3,
+        seed: 7,
+    };
+    let report = duplicate.run();
+    assert_eq!(report.scores.len(), 3);
+
+    let mut hands = (0..3)
+        .flat_map(|deal| PledgePhase::seeded(0, 13, 7 + deal).hands)
+        .map(|hand| {
+            let mut hand = hand.into_iter().collect::<Vec<_>>();
+            hand.sort();
+            hand
+        })
+        .collect::<Vec<_>>();
+    hands.sort();
+    for dealt in dealt {
+        let mut dealt = dealt.lock().unwrap().clone();
+        dealt.sort();
+        assert_eq!(dealt, hands);
+    }
+}
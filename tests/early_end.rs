@@ -0,0 +1,93 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::game::{play_hand, play_timed_game};
+use mighty_rs::play_phase::EarlyEnd;
+use mighty_rs::player::{create_player, Player};
+use mighty_rs::record::GameRecord;
+use mighty_rs::rules::RuleSet;
+
+/// Heuristic bots, which bid often enough for most deals to be played.
+fn bots() -> Vec<Box<dyn Player>> {
+    (0..5)
+        .map(|_| create_player("heuristic").unwrap())
+        .collect()
+}
+
+/// Records of hands played to the end, cancelled ones left out.
+fn finished_records(games: u64) -> Vec<GameRecord> {
+    let players = bots();
+    (0..games)
+        .filter_map(|seed| {
+            let deal = PledgePhase::seeded(0, 13, seed);
+            let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+            end.played().map(|_| record)
+        })
+        .collect()
+}
+
+#[test]
+fn early_ends_are_read_back() {
+    for early_end in [EarlyEnd::Never, EarlyEnd::Settled(10)] {
+        assert_eq!(early_end.to_string().parse(), Ok(early_end));
+    }
+    assert!("settled:21".parse::<EarlyEnd>().is_err());
+    assert!("win-side".parse::<EarlyEnd>().is_err());
+    assert!("always".parse::<EarlyEnd>().is_err());
+}
+
+#[test]
+fn hands_ended_early_keep_their_outcome() {
+    let rules = RuleSet::default();
+    let mut points_left = 0;
+    for record in finished_records(200) {
+        let full = record.play_phase(record.actions.len()).unwrap();
+        let mut game = record.play_phase(0).unwrap();
+        game.set_early_end(EarlyEnd::Settled(rules.back_run_max_score));
+        let mut actions = record.actions.iter();
+        while let Some(player) = game.turn_player() {
+            let &(p, action) = actions.next().unwrap();
+            assert_eq!(p, player);
+            game.player_acts(player, action);
+        }
+        assert_eq!(game.result().win, full.result().win);
+        assert_eq!(game.partner_revealed, full.partner_revealed);
+
+        let (early, late) = (rules.settle(&game), rules.settle(&full));
+        assert_eq!((early.run, early.back_run), (late.run, late.back_run));
+        if game.hands.iter().flatten().any(|c| c.score() > 0) {
+            // Only a lost hand stops with points left, and it is settled
+            // on the points its declarer took until then.
+            assert!(game.declarer_team_score() < game.contract.count);
+            assert!(early.payouts[game.declarer] < 0);
+            assert!(early.payouts[game.declarer] <= late.payouts[game.declarer]);
+            points_left += 1;
+        } else {
+            assert_eq!(early, late);
+        }
+    }
+    assert!(points_left > 0);
+}
+
+#[test]
+fn records_of_hands_ended_early_replay() {
+    let players = bots();
+    for seed in 0..10 {
+        let deal = PledgePhase::seeded(0, 13, seed);
+        let (end, record) = play_hand(
+            &players,
+            deal,
+            TimeControl::default(),
+            EarlyEnd::Settled(10),
+        );
+        let Some(game) = end.played() else {
+            continue;
+        };
+        assert!(game.is_done());
+        assert_eq!(record.actions.len(), 5 * game.round as usize);
+        let text = record.to_string();
+        assert!(text.contains("early_end settled:10"));
+        let parsed = text.parse::<GameRecord>().unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.play_phase(record.actions.len()).unwrap(), game);
+    }
+}
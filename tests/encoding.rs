@@ -0,0 +1,111 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::{Card, Suit};
+use mighty_rs::common::{Contract, PartnerCondition, PlayAction};
+use mighty_rs::encoding::{Action, Layout, PartialPlan};
+use mighty_rs::extra_phase::ExtraPhase;
+
+fn ones(features: &[f32]) -> Vec<usize> {
+    (0..features.len())
+        .filter(|&i| features[i] == 1.0)
+        .collect()
+}
+
+#[test]
+fn indices_round_trip() {
+    for card in Card::deck() {
+        assert_eq!(Card::from_index(card.index()), card);
+    }
+    let layout = Layout::LATEST;
+    for index in 0..layout.action_count() {
+        let action = layout.decode_action(index).unwrap();
+        assert_eq!(layout.encode_action(action), index);
+    }
+    assert!(layout.decode_action(layout.action_count()).is_none());
+    assert_eq!(Layout::from_version(layout.version()), Some(layout));
+    assert_eq!(Layout::from_version(0), None);
+}
+
+#[test]
+fn actions_keep_their_indices() {
+    let layout = Layout::V1;
+    let spade_14 = Contract {
+        suit: Some(Suit::S),
+        count: 14,
+    };
+    let no_trump_20 = Contract {
+        suit: None,
+        count: 20,
+    };
+    assert_eq!(layout.encode_action(Action::Bid(None)), 0);
+    assert_eq!(layout.encode_action(Action::Bid(Some(spade_14))), 74);
+    assert_eq!(layout.encode_action(Action::Bid(Some(no_trump_20))), 100);
+    let ace = Card::Shaped(Suit::D, 14);
+    assert_eq!(layout.encode_action(Action::Discard(ace)), 126);
+    let friend = PartnerCondition::CardCondition(Card::Joker);
+    assert_eq!(layout.encode_action(Action::Friend(friend)), 206);
+    let none = PartnerCondition::None;
+    assert_eq!(layout.encode_action(Action::Friend(none)), 222);
+    let call = PlayAction::JokerCall(Card::Shaped(Suit::C, 3));
+    assert_eq!(layout.encode_action(Action::Play(call)), 303);
+    let lead = PlayAction::JokerStart(Suit::S);
+    assert_eq!(layout.encode_action(Action::Play(lead)), 332);
+    assert_eq!(layout.action_count(), 333);
+    assert_eq!(layout.observation_size(), 790);
+
+    // The second layout only adds a surrender.
+    assert!(!layout.encodes(Action::Surrender));
+    assert_eq!(layout.decode_action(333), None);
+    let layout = Layout::V2;
+    assert_eq!(layout.encode_action(Action::Play(lead)), 332);
+    assert_eq!(layout.encode_action(Action::Surrender), 333);
+    assert_eq!(layout.action_count(), 334);
+    assert_eq!(layout.observation_size(), 790);
+}
+
+#[test]
+fn states_encode_into_their_sections() {
+    let layout = Layout::LATEST;
+    let mut pledge = PledgePhase::seeded(0, 13, 9);
+    let bidding = layout.encode_bidding(&pledge.bidding_state(0));
+    // The phase, ten cards and the least bid.
+    assert_eq!(ones(&bidding).len(), 12);
+    assert_eq!(ones(&bidding)[0], 0);
+
+    let contract = Contract {
+        suit: Some(Suit::H),
+        count: 13,
+    };
+    pledge.player_bids(0, Some(contract));
+    for player in 1..5 {
+        pledge.player_bids(player, None);
+    }
+    let mut extra = ExtraPhase::from_pledge(pledge);
+    let state = extra.extra_state();
+    assert_eq!(ones(&layout.encode_extra(&state)).len(), 1 + 13 + 2);
+    let plan = PartialPlan {
+        contract: None,
+        friend: Some(PartnerCondition::None),
+        discards: state.hand.iter().take(2).copied().collect(),
+    };
+    let plan = layout.encode_plan(&state, &plan);
+    assert_eq!(ones(&plan).len(), 1 + 13 + 2 + 1 + 2);
+
+    let discards = state.hand.iter().take(3).copied().collect();
+    let mut game = extra.submit_plan(contract, PartnerCondition::None, discards);
+    let declarer = layout.encode_play(&game.play_state(0));
+    let other = layout.encode_play(&game.play_state(1));
+    // Discards are only seen by the declarer.
+    assert_eq!(ones(&declarer).len(), ones(&other).len() + 3);
+    // The one to move, who leads the first round.
+    assert_eq!(declarer[108], 1.0);
+    assert_eq!(declarer[770], 1.0);
+    assert_eq!(declarer[780], 1.0);
+
+    while !game.is_done() {
+        let player = game.turn_player().unwrap();
+        let action = game.legal_actions(player)[0];
+        game.player_acts(player, action);
+    }
+    let played = &layout.encode_play(&game.play_state(1))[240..505];
+    assert_eq!(ones(played).len(), 50);
+}
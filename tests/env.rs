@@ -0,0 +1,87 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::encoding::{Action, Layout};
+use mighty_rs::env::{MightyEnv, VecEnv};
+use mighty_rs::extra_phase::ExtraPhase;
+use mighty_rs::rules::RuleSet;
+
+#[test]
+fn first_legal_actions_finish_a_hand() {
+    let mut env = MightyEnv::new(RuleSet::default());
+    env.reset(3);
+    let mut steps = 0;
+    while !env.done() {
+        let mask = env.legal_mask();
+        let action = env.legal_actions()[0];
+        assert!(mask[action]);
+        for seat in 0..5 {
+            assert_eq!(env.features(seat).len(), env.layout.observation_size());
+        }
+        env.step(action).unwrap();
+        steps += 1;
+    }
+    assert!(steps < 200);
+    assert_eq!(env.to_move(), None);
+    assert_eq!(env.payouts().iter().sum::<i32>(), 0);
+    assert!(env.step(0).is_err());
+}
+
+#[test]
+fn finished_envs_are_dealt_again() {
+    let mut envs = VecEnv::new(4, RuleSet::default());
+    envs.reset(11);
+    let mut finished = 0;
+    for _ in 0..200 {
+        let actions = envs
+            .envs
+            .iter()
+            .map(|env| *env.legal_actions().last().unwrap())
+            .collect::<Vec<_>>();
+        for (payouts, done) in envs.step(&actions).unwrap() {
+            if done {
+                finished += 1;
+                assert_eq!(payouts.iter().sum::<i32>(), 0);
+            }
+        }
+    }
+    assert!(finished >= 4);
+    assert!(envs.envs.iter().all(|env| !env.done()));
+    assert!(envs.step(&[0]).is_err());
+}
+
+#[test]
+fn declarers_surrender_in_place_of_a_plan() {
+    let rules = RuleSet::default();
+    let mut env = MightyEnv::new(rules.clone());
+    env.reset(3);
+    let surrender = env.layout.encode_action(Action::Surrender);
+    // Player 0 bids a contract and everyone else passes.
+    let mut pledge = PledgePhase::seeded(0, rules.min_pledge, 3);
+    for player in 0..5 {
+        assert!(!env.legal_actions().contains(&surrender));
+        let action = env.legal_actions()[(player == 0) as usize];
+        let bid = env.layout.decode_action(action).unwrap();
+        let Action::Bid(bid) = bid else {
+            panic!("not a bid: {:?}", bid);
+        };
+        pledge.player_bids(env.to_move().unwrap(), bid);
+        env.step(action).unwrap();
+    }
+    let expected = rules
+        .settle_surrender(&ExtraPhase::from_pledge(pledge).surrender())
+        .payouts;
+    assert_eq!(env.to_move(), Some(0));
+    assert!(env.legal_actions().contains(&surrender));
+    env.step(surrender).unwrap();
+    assert!(env.done() && env.game().is_none());
+    assert_eq!(env.payouts(), expected);
+
+    // The first layout has no surrender.
+    env.reset(3);
+    env.layout = Layout::V1;
+    for player in 0..5 {
+        env.step(env.legal_actions()[(player == 0) as usize])
+            .unwrap();
+    }
+    assert_eq!(env.to_move(), Some(0));
+    assert!(env.legal_actions().iter().all(|&a| a < surrender));
+}
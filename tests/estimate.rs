@@ -0,0 +1,88 @@
+use mighty_rs::analysis::Advisor;
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::{Card, Suit};
+use mighty_rs::common::Hand;
+use mighty_rs::estimate::{EstimatePlayer, Estimator, DENOMINATIONS};
+use mighty_rs::game::play_game;
+use mighty_rs::player::Player;
+
+fn hand(cards: &str) -> Hand {
+    cards.split(' ').map(|c| c.parse().unwrap()).collect()
+}
+
+fn small_estimator() -> Estimator {
+    let mut estimator = Estimator::new("heuristic").unwrap();
+    estimator.samples = 8;
+    estimator.depth = 3;
+    estimator
+}
+
+#[test]
+fn odds_fall_with_the_count() {
+    let estimator = small_estimator();
+    let strong = hand("SA SK SQ SJ S10 S9 JK DA HA CA");
+    let odds = estimator.estimate(&strong).unwrap();
+    for (points, trump) in odds.points.iter().zip(DENOMINATIONS) {
+        assert_eq!(points.iter().sum::<usize>(), 8);
+        for count in 1..=20 {
+            let p = odds.probability(trump, count);
+            assert!((0.0..=1.0).contains(&p));
+            assert!(p <= odds.probability(trump, count - 1));
+        }
+        assert_eq!(odds.probability(trump, 0), 1.0);
+    }
+    assert_eq!(
+        estimator.estimate(&strong).unwrap().points,
+        odds.points,
+        "the same seed deals the same hands"
+    );
+
+    let weak = hand("S2 S3 S4 D2 D3 D4 H2 H3 C2 C3");
+    let weak_odds = estimator.estimate(&weak).unwrap();
+    let spades = Some(Suit::S);
+    assert!(odds.probability(spades, 14) > weak_odds.probability(spades, 14));
+
+    let (contract, probability) = odds.best_bid(14).unwrap();
+    assert!(contract.effective_count() == 15);
+    assert_eq!(probability, odds.probability(contract.suit, contract.count));
+    assert!(odds.best_bid(21).is_none());
+}
+
+#[test]
+fn hands_and_bots_are_checked() {
+    let estimator = small_estimator();
+    let short = Card::deck().into_iter().take(9).collect::<Hand>();
+    assert!(estimator.estimate(&short).is_err());
+    assert!(Estimator::new("nobody").is_err());
+}
+
+#[test]
+fn bots_and_hints_bid_legally() {
+    let players = (0..5)
+        .map(|_| Box::new(EstimatePlayer::new(small_estimator())) as Box<dyn Player>)
+        .collect::<Vec<_>>();
+    for seed in 0..3 {
+        // Bids are checked by the pledge phase as they are made.
+        if let Some(game) = play_game(&players, PledgePhase::seeded(0, 13, seed)).played() {
+            assert!(game.is_done());
+        }
+    }
+
+    let cautious = EstimatePlayer::new(small_estimator()).with_threshold(1.1);
+    let state = BiddingState {
+        hand: hand("SA SK SQ SJ S10 S9 JK DA HA CA"),
+        curr_contract: None,
+        min_effective_count: 12,
+        players_remaining: 5,
+    };
+    assert_eq!(cautious.bidding(&state), None);
+
+    let advisor = Advisor {
+        worlds: 4,
+        depth: 3,
+        ..Advisor::default()
+    };
+    let (contract, probability) = advisor.bid_hint(&state).unwrap();
+    assert!(contract.effective_count() > 12);
+    assert!((0.0..=1.0).contains(&probability));
+}
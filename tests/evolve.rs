@@ -0,0 +1,105 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::evolve::Evolution;
+use mighty_rs::game::play_timed_game;
+use mighty_rs::heuristic::{HeuristicPlayer, Weights, PARAMETERS};
+use mighty_rs::player::{create_player, Player};
+use mighty_rs::rules::RuleSet;
+
+fn evolution(threads: usize) -> Evolution {
+    Evolution {
+        population: 4,
+        generations: 2,
+        games: 10,
+        seed: 7,
+        mutation: 0.2,
+        elite: 1,
+        opponent: "heuristic".to_string(),
+        rules: RuleSet::default(),
+        threads,
+    }
+}
+
+#[test]
+fn weights_are_read_back() {
+    let weights = Weights {
+        joker: 3.25,
+        lead_threshold: 120.0,
+        ..Weights::default()
+    };
+    assert_eq!(weights.to_string().parse::<Weights>(), Ok(weights.clone()));
+    assert_eq!(Weights::from_vec(&weights.to_vec()), weights);
+
+    // Weights left out keep their defaults.
+    let partial = "# tuned\n\nmighty = 4\n".parse::<Weights>().unwrap();
+    assert_eq!(partial.mighty, 4.0);
+    assert_eq!(partial.joker, Weights::default().joker);
+    assert!("speed = 1".parse::<Weights>().is_err());
+    assert!("mighty 4".parse::<Weights>().is_err());
+    assert!("mighty = fast".parse::<Weights>().is_err());
+}
+
+#[test]
+fn heuristic_bots_play_the_same_way_every_time() {
+    let path = std::env::temp_dir().join(format!("mighty-weights-{}.txt", std::process::id()));
+    Weights::default().save(&path).unwrap();
+    let name = format!("heuristic:{}", path.display());
+    let play = |name: &str| {
+        let players = (0..5)
+            .map(|_| create_player(name).unwrap())
+            .collect::<Vec<_>>();
+        play_timed_game(
+            &players,
+            PledgePhase::seeded(2, 13, 11),
+            TimeControl::default(),
+        )
+        .1
+    };
+    let record = play(&name);
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(record.to_string(), play("heuristic").to_string());
+    assert!(create_player("heuristic:/no/such/weights.txt").is_err());
+
+    for seed in 0..20 {
+        let players = (0..5)
+            .map(|_| Box::new(HeuristicPlayer::new(Weights::default())) as Box<dyn Player>)
+            .collect::<Vec<_>>();
+        let (end, _) = play_timed_game(
+            &players,
+            PledgePhase::seeded(0, 13, seed),
+            TimeControl::default(),
+        );
+        assert!(end.played().is_none_or(|g| g.is_done()));
+    }
+}
+
+#[test]
+fn generations_are_repeatable_by_seed() {
+    let run = |threads| {
+        let mut fitness = vec![];
+        let best = evolution(threads)
+            .run(|generation| {
+                assert_eq!(generation.ranked.len(), 4);
+                fitness.push(generation.best().1);
+            })
+            .unwrap();
+        (best, fitness)
+    };
+    let (best, fitness) = run(1);
+    assert_eq!(fitness.len(), 2);
+    assert_eq!(run(3), (best.clone(), fitness));
+    for (value, (_, low, high)) in best.to_vec().into_iter().zip(PARAMETERS) {
+        assert!((low..=high).contains(&value));
+    }
+
+    let small = Evolution {
+        population: 1,
+        ..evolution(1)
+    };
+    assert!(small.run(|_| {}).is_err());
+    let unknown = Evolution {
+        opponent: "nobody".to_string(),
+        ..evolution(1)
+    };
+    assert!(unknown.run(|_| {}).is_err());
+}
@@ -0,0 +1,78 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::external::ExternalPlayer;
+use mighty_rs::game::play_timed_game;
+use mighty_rs::player::{Player, RandomPlayer};
+use std::env;
+use std::fs;
+use std::process;
+
+fn command(words: &[&str]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+fn engine_bot() -> Box<dyn Player> {
+    let bot = ExternalPlayer::spawn(command(&[env!("CARGO_BIN_EXE_mighty-rs"), "bot"]));
+    Box::new(bot.unwrap())
+}
+
+#[test]
+fn external_bots_play_a_game() {
+    let players = (0..5).map(|_| engine_bot()).collect::<Vec<_>>();
+    let deal = PledgePhase::seeded(0, 13, 5);
+    let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+    assert!(end.played().unwrap().is_done());
+    assert_eq!(record.actions.len(), 50);
+    assert!(record.timeouts.is_empty());
+}
+
+#[test]
+fn broken_bots_are_refused() {
+    assert!(ExternalPlayer::spawn(vec![]).is_err());
+    assert!(ExternalPlayer::spawn(command(&["mighty-rs-no-such-bot"])).is_err());
+    assert!(ExternalPlayer::spawn(command(&["sh", "-c", "true"])).is_err());
+    let future = r#"read line; echo '{"type":"ready","version":2}'; read line"#;
+    let error = ExternalPlayer::spawn(command(&["sh", "-c", future])).err();
+    assert!(error.unwrap().contains("version 2"));
+}
+
+#[test]
+fn crashed_bot_moves_are_made_for_it() {
+    // Completes the handshake, then exits on the first request.
+    let crashing = r#"read line; echo '{"type":"ready","version":1}'; read line"#;
+    let mut players = (0..4)
+        .map(|_| Box::new(RandomPlayer {}) as Box<dyn Player>)
+        .collect::<Vec<_>>();
+    let bot = ExternalPlayer::spawn(command(&["sh", "-c", crashing])).unwrap();
+    players.insert(0, Box::new(bot));
+    let deal = PledgePhase::seeded(0, 13, 5);
+    let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+    assert!(end.played().unwrap().is_done());
+    // Every move of seat 0 is made for it, starting with a pass.
+    assert_eq!(record.bids[0], (0, None));
+    let moves = record.bids.iter().filter(|(p, _)| *p == 0).count()
+        + record.actions.iter().filter(|(p, _)| *p == 0).count();
+    assert_eq!(record.timeouts.len(), moves);
+    assert!(record.timeouts.iter().all(|(p, _)| *p == 0));
+}
+
+#[test]
+fn bots_start_afresh_every_hand() {
+    let starts = env::temp_dir().join(format!("mighty-rs-starts-{}", process::id()));
+    let script = format!(
+        "echo $$ >> {}; exec {} bot",
+        starts.display(),
+        env!("CARGO_BIN_EXE_mighty-rs")
+    );
+    let bot = ExternalPlayer::spawn(command(&["sh", "-c", &script])).unwrap();
+    let mut players = (0..4).map(|_| engine_bot()).collect::<Vec<_>>();
+    players.insert(0, Box::new(bot));
+    for seed in [5, 6] {
+        let deal = PledgePhase::seeded(0, 13, seed);
+        let (_, record) = play_timed_game(&players, deal, TimeControl::default());
+        assert!(record.timeouts.is_empty());
+    }
+    let text = fs::read_to_string(&starts).unwrap();
+    fs::remove_file(&starts).unwrap();
+    assert_eq!(text.lines().count(), 2);
+}
@@ -0,0 +1,156 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::{Card, Suit};
+use mighty_rs::common::{Contract, PartnerCondition};
+use mighty_rs::extra_phase::ExtraPhase;
+use mighty_rs::friend::FriendInference;
+use mighty_rs::inference::Knowledge;
+use mighty_rs::play_phase::PlayPhase;
+use mighty_rs::player::{Player, RandomPlayer};
+
+/// A seeded deal declared by seat 0 calling the first card of seat 1 as
+/// friend, played out at random up to `round`.
+fn game_at(seed: u64, round: u8) -> PlayPhase {
+    let deal = PledgePhase::seeded(0, 13, seed);
+    let mut hands = deal.hands.clone();
+    hands[0].extend(deal.bottom.iter().copied());
+    let mut cards = hands[0].iter().copied().collect::<Vec<_>>();
+    cards.sort_by_key(|c| c.index());
+    let friend = *hands[1].iter().min_by_key(|c| c.index()).unwrap();
+    let contract = Contract {
+        suit: Some(Suit::S),
+        count: 13,
+    };
+    let mut extra = ExtraPhase {
+        hands,
+        contract,
+        declarer: 0,
+    };
+    let condition = PartnerCondition::CardCondition(friend);
+    let mut game = extra.submit_plan(contract, condition, cards[..3].iter().copied().collect());
+    while game.round < round {
+        let player = game.turn_player().unwrap();
+        let action = RandomPlayer {}.play_action(game.play_state(player));
+        game.player_acts(player, action);
+    }
+    game
+}
+
+#[test]
+fn probabilities_sum_to_one() {
+    // Late enough in the hand for the deals to be counted quickly.
+    for seed in 0..10 {
+        let mut game = game_at(seed, 5);
+        let mut seats = (0..5)
+            .map(|seat| FriendInference::new(seat, &game.play_state(seat)))
+            .collect::<Vec<_>>();
+        while let Some(player) = game.turn_player() {
+            let action = RandomPlayer {}.play_action(game.play_state(player));
+            game.player_acts(player, action);
+            if game.submitted.iter().any(Option::is_some) {
+                continue;
+            }
+            for (seat, inference) in seats.iter_mut().enumerate() {
+                inference.update(&game.play_state(seat));
+                let probabilities = inference.probabilities();
+                assert!(probabilities.iter().all(|p| (0.0..=1.0).contains(p)));
+                let total = probabilities.iter().sum::<f64>() + inference.no_friend();
+                assert!((total - 1.0).abs() < 1e-9);
+                // Only the discards may hide the card from the declarer, and
+                // the friend card was not discarded.
+                if seat == 0 {
+                    assert!(inference.no_friend().abs() < 1e-9);
+                }
+                assert!(probabilities[1] > 0.0, "seed {} seat {}", seed, seat);
+            }
+        }
+    }
+}
+
+/// Weights of each holder of `card`, the hidden discards last, over every
+/// deal of the unknown cards that `knowledge` allows.
+fn enumerate(
+    knowledge: &Knowledge,
+    cards: &[Card],
+    capacities: &mut [usize; 6],
+    card: Card,
+    holder: usize,
+    declarer_weight: f64,
+    weights: &mut [f64; 6],
+) {
+    let Some((&next, rest)) = cards.split_first() else {
+        let weight = if holder == 0 || holder == 5 {
+            declarer_weight
+        } else {
+            1.0
+        };
+        weights[holder] += weight;
+        return;
+    };
+    for h in 0..6 {
+        if capacities[h] == 0 || h < 5 && !knowledge.can_hold(h, next) {
+            continue;
+        }
+        capacities[h] -= 1;
+        let holder = if next == card { h } else { holder };
+        enumerate(
+            knowledge,
+            rest,
+            capacities,
+            card,
+            holder,
+            declarer_weight,
+            weights,
+        );
+        capacities[h] += 1;
+    }
+}
+
+#[test]
+fn probabilities_match_enumerated_deals() {
+    let mut checked = 0;
+    for seed in 0..30 {
+        let game = game_at(seed, 8);
+        if game.partner_revealed.is_some() {
+            continue;
+        }
+        let PartnerCondition::CardCondition(card) = game.partner_condition else {
+            unreachable!();
+        };
+        let seat = 3;
+        let inference = FriendInference::new(seat, &game.play_state(seat));
+        let knowledge = inference.knowledge();
+        let unknown = Card::deck()
+            .into_iter()
+            .filter(|c| !knowledge.played.contains_key(c) && !knowledge.hand.contains(c))
+            .collect::<Vec<_>>();
+        let mut capacities = [0; 6];
+        for p in (0..5).filter(|&p| p != seat) {
+            capacities[p] = knowledge.hand_sizes[p];
+        }
+        capacities[5] = 3;
+        let mut weights = [0.0; 6];
+        let weight = inference.declarer_weight;
+        enumerate(
+            knowledge,
+            &unknown,
+            &mut capacities,
+            card,
+            6,
+            weight,
+            &mut weights,
+        );
+
+        let total = weights.iter().sum::<f64>();
+        for (p, &probability) in inference.probabilities().iter().enumerate() {
+            let expected = weights[p] / total;
+            assert!(
+                (probability - expected).abs() < 1e-9,
+                "seed {} seat {}",
+                seed,
+                p
+            );
+        }
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
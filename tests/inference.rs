@@ -0,0 +1,81 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::{Card, Suit};
+use mighty_rs::common::{Contract, PartnerCondition, PlayAction};
+use mighty_rs::extra_phase::ExtraPhase;
+use mighty_rs::inference::{CardLocation, Knowledge};
+use mighty_rs::play_phase::PlayPhase;
+use mighty_rs::player::{Player, RandomPlayer};
+
+/// A seeded deal declared by seat 0, ready to play.
+fn new_game(seed: u64) -> PlayPhase {
+    let deal = PledgePhase::seeded(0, 13, seed);
+    let mut hands = deal.hands.clone();
+    hands[0].extend(deal.bottom.iter().copied());
+    let mut cards = hands[0].iter().copied().collect::<Vec<_>>();
+    cards.sort_by_key(|c| c.index());
+    let contract = Contract {
+        suit: [Some(Suit::S), Some(Suit::H), None][seed as usize % 3],
+        count: 13,
+    };
+    let mut extra = ExtraPhase {
+        hands,
+        contract,
+        declarer: 0,
+    };
+    let condition = PartnerCondition::CardCondition(Card::Joker);
+    extra.submit_plan(contract, condition, cards[..3].iter().copied().collect())
+}
+
+#[test]
+fn failing_to_follow_suit_shows_a_void() {
+    let mut voids = 0;
+    for seed in 0..20 {
+        let mut game = new_game(seed);
+        let mut knowledge = Knowledge::new(4, &game.play_state(4));
+        while let Some(player) = game.turn_player() {
+            let action = RandomPlayer {}.play_action(game.play_state(player));
+            let lead = game.round_suit;
+            game.player_acts(player, action);
+            knowledge.update(&game.play_state(4));
+
+            let mighty = Card::mighty(game.contract.suit);
+            if let (Some(suit), PlayAction::Hand(card @ Card::Shaped(s, _))) = (lead, action) {
+                if s != suit && card != mighty {
+                    voids += 1;
+                    assert!(knowledge.voids[player].contains(&suit));
+                    assert!((2..=14).all(|n| !knowledge.can_hold(player, Card::Shaped(suit, n))));
+                }
+            }
+        }
+    }
+    assert!(voids > 0);
+}
+
+#[test]
+fn cards_are_never_ruled_out_for_their_holder() {
+    for seed in 0..20 {
+        let mut game = new_game(seed);
+        let mut seats = (0..5)
+            .map(|seat| Knowledge::new(seat, &game.play_state(seat)))
+            .collect::<Vec<_>>();
+        while let Some(player) = game.turn_player() {
+            let action = RandomPlayer {}.play_action(game.play_state(player));
+            game.player_acts(player, action);
+            for (seat, knowledge) in seats.iter_mut().enumerate() {
+                knowledge.update(&game.play_state(seat));
+                for (holder, hand) in game.hands.iter().enumerate() {
+                    for &card in hand {
+                        assert!(knowledge.can_hold(holder, card), "seed {}", seed);
+                        let location = knowledge.location(card);
+                        assert!(
+                            matches!(location, CardLocation::Unknown)
+                                || location == CardLocation::Hand(holder),
+                            "seed {}",
+                            seed
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use mighty_rs::client::Client;
+use mighty_rs::event::Event;
+use mighty_rs::json::Json;
+use mighty_rs::lobby::Lobby;
+use mighty_rs::player::RandomPlayer;
+use mighty_rs::protocol::{rules_from_json, ServerMessage};
+use mighty_rs::rules::RuleSet;
+use std::net::SocketAddr;
+use std::thread;
+
+fn start_lobby() -> SocketAddr {
+    let lobby = Lobby::bind("127.0.0.1:0").unwrap();
+    let addr = lobby.local_addr().unwrap();
+    thread::spawn(move || lobby.run());
+    addr
+}
+
+#[test]
+fn rooms_play_matches_concurrently() {
+    let addr = start_lobby();
+    let rules = RuleSet {
+        min_pledge: 14,
+        ..RuleSet::default()
+    };
+
+    let (mut alice, rooms) = Client::enter_lobby(addr, "alice").unwrap();
+    assert!(rooms.is_empty());
+    assert_eq!(
+        alice
+            .create_room("first", rules.clone(), 3, "random", Some(2))
+            .unwrap(),
+        2
+    );
+
+    let (mut bob, rooms) = Client::enter_lobby(addr, "bob").unwrap();
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0].name, "first");
+    assert_eq!(rooms[0].rules, rules);
+    assert_eq!(rooms[0].seats[2].as_deref(), Some("alice"));
+    let first = rooms[0].id;
+    assert!(bob.join_room(first, Some(2)).is_err());
+    assert_eq!(bob.join_room(first, None).unwrap(), 0);
+    bob.leave_room().unwrap();
+    assert_eq!(bob.join_room(first, Some(4)).unwrap(), 4);
+
+    let (mut carol, _) = Client::enter_lobby(addr, "carol").unwrap();
+    carol
+        .create_room("second", RuleSet::default(), 2, "random", None)
+        .unwrap();
+
+    alice.start().unwrap();
+    carol.start().unwrap();
+    let players = [alice, bob, carol]
+        .into_iter()
+        .map(|mut client| {
+            thread::spawn(move || {
+                let mut results = 0;
+                let room = client
+                    .play_match(&RandomPlayer {}, |e| {
+                        if matches!(e, Event::Result { .. } | Event::Cancelled) {
+                            results += 1;
+                        }
+                    })
+                    .unwrap();
+                (room, results)
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = players
+        .into_iter()
+        .map(|p| p.join().unwrap())
+        .collect::<Vec<_>>();
+
+    let (alice, bob, carol) = (&results[0], &results[1], &results[2]);
+    assert_eq!(alice.0, bob.0);
+    assert_eq!(alice.0.name, "first");
+    assert_eq!(alice.0.hands_played, 3);
+    assert_eq!(alice.1, 3);
+    assert_eq!(alice.0.scores.iter().sum::<i32>(), 0);
+    assert_eq!(carol.0.name, "second");
+    assert_eq!(carol.0.hands_played, 2);
+    assert_eq!(carol.1, 2);
+    assert!(!carol.0.playing);
+}
+
+#[test]
+fn lobby_rejects_bad_requests() {
+    let addr = start_lobby();
+    let (mut client, _) = Client::enter_lobby(addr, "dave").unwrap();
+    assert!(client.join_room(7, None).is_err());
+    assert!(client
+        .create_room("bad", RuleSet::default(), 1, "nobody", None)
+        .is_err());
+    assert!(client
+        .create_room("bad", RuleSet::default(), 0, "random", None)
+        .is_err());
+    // Clients cannot make the server read files or start programs.
+    assert!(client
+        .create_room("bad", RuleSet::default(), 1, "heuristic:/etc/passwd", None)
+        .is_err());
+    assert!(client
+        .create_room("bad", RuleSet::default(), 1, "exec:touch /tmp/x", None)
+        .is_err());
+    client.start().unwrap();
+    assert!(matches!(client.receive().unwrap(), ServerMessage::Error(_)));
+    assert!(client.list_rooms().unwrap().is_empty());
+}
+
+#[test]
+fn room_rules_are_kept_in_range() {
+    let rules = |text: &str| rules_from_json(&text.parse::<Json>().unwrap());
+    let parsed = rules(r#"{"min_pledge":15,"surrender_payment":0}"#).unwrap();
+    assert_eq!(parsed.min_pledge, 15);
+    assert_eq!(parsed.surrender_payment, 0);
+    for bad in [
+        r#"{"min_pledge":269}"#,
+        r#"{"min_pledge":0}"#,
+        r#"{"back_run_max_score":21}"#,
+        r#"{"run_multiplier":-2}"#,
+        r#"{"no_friend_multiplier":4294967298}"#,
+        r#"{"surrender_payment":-1}"#,
+    ] {
+        assert!(rules(bad).is_err(), "{}", bad);
+    }
+}
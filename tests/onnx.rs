@@ -0,0 +1,183 @@
+#![cfg(feature = "onnx")]
+
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::common::{Contract, PartnerCondition};
+use mighty_rs::encoding::Layout;
+use mighty_rs::game::{play_timed_game, HandEnd};
+use mighty_rs::onnx::{OnnxModel, OnnxPlayer, Selection};
+use mighty_rs::player::Player;
+use prost::Message;
+use std::path::PathBuf;
+use std::rc::Rc;
+use tract_onnx::pb::tensor_shape_proto::{dimension, Dimension};
+use tract_onnx::pb::{
+    type_proto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TensorProto,
+    TensorShapeProto, TypeProto, ValueInfoProto,
+};
+
+fn tensor(name: &str, dims: &[i64], values: Vec<f32>) -> TensorProto {
+    TensorProto {
+        name: name.to_string(),
+        dims: dims.to_vec(),
+        data_type: 1,
+        float_data: values,
+        ..Default::default()
+    }
+}
+
+fn node(op: &str, inputs: &[&str], output: &str) -> NodeProto {
+    NodeProto {
+        op_type: op.to_string(),
+        input: inputs.iter().map(|i| i.to_string()).collect(),
+        output: vec![output.to_string()],
+        ..Default::default()
+    }
+}
+
+/// A float tensor of the shape `dims`.
+fn value_info(name: &str, dims: &[i64]) -> ValueInfoProto {
+    let dim = dims
+        .iter()
+        .map(|&d| Dimension {
+            value: Some(dimension::Value::DimValue(d)),
+            ..Default::default()
+        })
+        .collect();
+    let tensor = type_proto::Tensor {
+        elem_type: 1,
+        shape: Some(TensorShapeProto { dim }),
+    };
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            value: Some(type_proto::Value::TensorType(tensor)),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Writes a model whose policy ignores the view and gives `logits`, with a
+/// value of 0.5.
+fn write_model(name: &str, logits: Vec<f32>) -> PathBuf {
+    let inputs = Layout::LATEST.observation_size();
+    let outputs = logits.len();
+    let graph = GraphProto {
+        name: name.to_string(),
+        node: vec![
+            node("MatMul", &["features", "weights"], "product"),
+            node("Add", &["product", "bias"], "policy"),
+            node("Identity", &["half"], "value"),
+        ],
+        initializer: vec![
+            tensor(
+                "weights",
+                &[inputs as i64, outputs as i64],
+                vec![0.0; inputs * outputs],
+            ),
+            tensor("bias", &[1, outputs as i64], logits),
+            tensor("half", &[1, 1], vec![0.5]),
+        ],
+        input: vec![value_info("features", &[1, inputs as i64])],
+        output: vec![
+            value_info("policy", &[1, outputs as i64]),
+            value_info("value", &[1, 1]),
+        ],
+        ..Default::default()
+    };
+    let model = ModelProto {
+        ir_version: 7,
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: 13,
+        }],
+        graph: Some(graph),
+        ..Default::default()
+    };
+    let path = std::env::temp_dir().join(format!("mighty-{}-{}.onnx", name, std::process::id()));
+    std::fs::write(&path, model.encode_to_vec()).unwrap();
+    path
+}
+
+/// A model preferring higher action indices, or lower when `reversed`.
+fn ordered_model(name: &str, reversed: bool) -> Rc<OnnxModel> {
+    let count = Layout::LATEST.action_count();
+    let logits = (0..count)
+        .map(|i| if reversed { -(i as f32) } else { i as f32 })
+        .collect();
+    let path = write_model(name, logits);
+    let model = OnnxModel::load(&path, Layout::LATEST).unwrap();
+    std::fs::remove_file(path).unwrap();
+    Rc::new(model)
+}
+
+fn players(make: impl Fn() -> OnnxPlayer) -> Vec<Box<dyn Player>> {
+    (0..5)
+        .map(|_| Box::new(make()) as Box<dyn Player>)
+        .collect()
+}
+
+#[test]
+fn models_choose_their_best_legal_actions() {
+    let model = ordered_model("highest", false);
+    let (policy, value) = model
+        .evaluate(&vec![0.0; Layout::LATEST.observation_size()])
+        .unwrap();
+    assert_eq!(policy[7], 7.0);
+    assert_eq!(value, Some(0.5));
+
+    let players = players(|| OnnxPlayer::with_models(model.clone(), model.clone(), model.clone()));
+    let deal = PledgePhase::seeded(0, 13, 4);
+    let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+    // The highest bid, then passes as nothing is left to outbid it.
+    let no_trump_20 = Contract {
+        suit: None,
+        count: 20,
+    };
+    assert_eq!(record.bids[0], (0, Some(no_trump_20)));
+    let (contract, friend, _) = record.plan.unwrap();
+    assert_eq!(contract, no_trump_20);
+    assert_eq!(friend, PartnerCondition::None);
+    assert!(end.played().unwrap().is_done());
+    assert!(record.timeouts.is_empty());
+}
+
+#[test]
+fn each_phase_has_its_own_model() {
+    let passing = ordered_model("lowest", true);
+    let playing = ordered_model("playing", false);
+    let players =
+        players(|| OnnxPlayer::with_models(passing.clone(), playing.clone(), playing.clone()));
+    let deal = PledgePhase::seeded(0, 13, 4);
+    let (end, record) = play_timed_game(&players, deal, TimeControl::default());
+    assert!(matches!(end, HandEnd::Cancelled));
+    assert!(record.bids.iter().all(|(_, bid)| bid.is_none()));
+}
+
+#[test]
+fn sampling_is_repeatable_by_seed() {
+    let model = ordered_model("sampled", false);
+    let play = |seed: u64| {
+        let players = players(|| {
+            let mut player = OnnxPlayer::with_models(model.clone(), model.clone(), model.clone());
+            player.selection = Selection::Sample { temperature: 50.0 };
+            player.with_seed(seed)
+        });
+        let deal = PledgePhase::seeded(0, 13, 4);
+        play_timed_game(&players, deal, TimeControl::default()).1
+    };
+    let first = play(1);
+    assert_eq!(first.actions.len(), 50);
+    assert_eq!(first.actions, play(1).actions);
+    assert_eq!(first.bids, play(1).bids);
+}
+
+#[test]
+fn unfit_models_are_refused() {
+    assert!(OnnxModel::load("/no/such/model.onnx", Layout::LATEST).is_err());
+    let path = write_model("small", vec![0.0; 10]);
+    let error = OnnxModel::load(&path, Layout::LATEST).err().unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(error.contains("10 logits"));
+}
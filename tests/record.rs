@@ -0,0 +1,46 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::common::Contract;
+use mighty_rs::game::play_hand;
+use mighty_rs::play_phase::EarlyEnd;
+use mighty_rs::player::{create_player, Player};
+use mighty_rs::record::GameRecord;
+
+#[test]
+fn records_are_read_back() {
+    let players = (0..5)
+        .map(|_| create_player("heuristic").unwrap())
+        .collect::<Vec<Box<dyn Player>>>();
+    for seed in 0..10 {
+        let deal = PledgePhase::seeded(seed as usize % 5, 13, seed);
+        let (_, mut record) = play_hand(&players, deal, TimeControl::default(), EarlyEnd::Never);
+        record.notes.push((record.moves(), "a note".to_string()));
+        assert_eq!(record.to_string().parse(), Ok(record));
+    }
+}
+
+#[test]
+fn minimum_pledges_out_of_range_are_rejected() {
+    let deal = PledgePhase::seeded(0, 13, 1);
+    let text = GameRecord::new(&deal).to_string();
+    for min_pledge in ["0", "21", "-1"] {
+        let bad = text.replace("min_pledge 13", &format!("min_pledge {}", min_pledge));
+        assert!(bad.parse::<GameRecord>().is_err(), "{}", min_pledge);
+    }
+    let good = text.replace("min_pledge 13", "min_pledge 1");
+    assert_eq!(good.parse::<GameRecord>().map(|r| r.min_pledge), Ok(1));
+}
+
+#[test]
+fn contracts_with_non_ascii_text_are_rejected() {
+    for text in ["♠13", "N♠13", "S♠", "NT", "", "ＮＴ13"] {
+        assert!(text.parse::<Contract>().is_err(), "{}", text);
+    }
+    assert_eq!(
+        "nt12".parse(),
+        Ok(Contract {
+            suit: None,
+            count: 12
+        })
+    );
+}
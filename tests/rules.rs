@@ -0,0 +1,68 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::Suit;
+use mighty_rs::common::{Contract, PartnerCondition};
+use mighty_rs::extra_phase::ExtraPhase;
+use mighty_rs::play_phase::PlayPhase;
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::rules::RuleSet;
+use mighty_rs::solver::Solver;
+
+/// A hand in which seat 0 declares with one of its own cards as the friend
+/// card, played out at random from `round`.
+fn own_friend_game(seed: u64, round: u8) -> PlayPhase {
+    let deal = PledgePhase::seeded(0, 13, seed);
+    let mut hands = deal.hands.clone();
+    hands[0].extend(deal.bottom.iter().copied());
+    let mut cards = hands[0].iter().copied().collect::<Vec<_>>();
+    cards.sort_by_key(|c| c.index());
+    let contract = Contract {
+        suit: Some(Suit::S),
+        count: 13,
+    };
+    let mut extra = ExtraPhase {
+        hands,
+        contract,
+        declarer: 0,
+    };
+    let condition = PartnerCondition::CardCondition(cards[12]);
+    let mut game = extra.submit_plan(contract, condition, cards[..3].iter().copied().collect());
+    while game.round < round {
+        let player = game.turn_player().unwrap();
+        let action = RandomPlayer {}.play_action(game.play_state(player));
+        game.player_acts(player, action);
+    }
+    game
+}
+
+#[test]
+fn declarers_who_are_their_own_friend_count_once() {
+    let rules = RuleSet::default();
+    for seed in 0..20 {
+        let game = own_friend_game(seed, 10);
+        assert_eq!(game.partner_revealed, Some(0));
+        assert_eq!(game.declarer_team_score(), game.scores()[0]);
+        // Paid the same as a declarer playing alone with the same points.
+        let alone = PlayPhase {
+            partner_revealed: None,
+            ..game.clone()
+        };
+        let settlement = rules.settle(&game);
+        assert_eq!(settlement, rules.settle(&alone));
+        assert_eq!(settlement.payouts[0] > 0, game.scores()[0] >= 13);
+        assert_eq!(
+            settlement.payouts[1..].iter().sum::<i32>(),
+            -settlement.payouts[0]
+        );
+    }
+}
+
+#[test]
+fn solver_counts_a_declarer_who_is_their_own_friend_once() {
+    for seed in 0..5 {
+        let game = own_friend_game(seed, 7);
+        let points = Solver::new().solve(&game).points;
+        let left = game.hands.iter().flatten().map(|c| c.score()).sum::<u8>();
+        assert!(points >= game.scores()[0]);
+        assert!(points <= game.scores()[0] + left);
+    }
+}
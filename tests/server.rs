@@ -0,0 +1,343 @@
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::Card;
+use mighty_rs::client::Client;
+use mighty_rs::common::{Claim, Contract, PartnerCondition, PlayAction};
+use mighty_rs::event::Event;
+use mighty_rs::extra_phase::ExtraExposedState;
+use mighty_rs::json::Json;
+use mighty_rs::play_phase::ExposedGameState;
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::protocol::{ClientMessage, ServerMessage};
+use mighty_rs::rules::RuleSet;
+use mighty_rs::server::{Connection, Server, MAX_LINE};
+use mighty_rs::web;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+#[test]
+fn five_clients_play_a_hand() {
+    let server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        let game = server
+            .host_game(&mut seats, game)
+            .unwrap()
+            .played()
+            .unwrap();
+        server.rules.settle(&game).payouts
+    });
+
+    let clients = (0..5)
+        .map(|i| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, &format!("bot{}", i)).unwrap();
+                let events = client.play_hand(&RandomPlayer {}, |_| {}).unwrap();
+                (client.seat, events)
+            })
+        })
+        .collect::<Vec<_>>();
+    let payouts = host.join().unwrap();
+    let results = clients
+        .into_iter()
+        .map(|c| c.join().unwrap())
+        .collect::<Vec<_>>();
+
+    let mut seats = results.iter().map(|(seat, _)| *seat).collect::<Vec<_>>();
+    seats.sort();
+    assert_eq!(seats, vec![0, 1, 2, 3, 4]);
+
+    let events = &results[0].1;
+    assert!(results.iter().all(|(_, e)| e == events));
+    let plays = events
+        .iter()
+        .filter(|e| matches!(e, Event::Play { .. }))
+        .count();
+    assert_eq!(plays, 50);
+    match events.last() {
+        Some(Event::Result { payouts: p, .. }) => assert_eq!(p, &payouts),
+        last => panic!("hand ended with {:?}", last),
+    }
+}
+
+#[test]
+fn endless_lines_are_cut_off() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // The server hangs up partway, so the write may fail.
+        let _ = stream.write_all("x".repeat(MAX_LINE * 4).as_bytes());
+        let mut rest = vec![];
+        let _ = stream.read_to_end(&mut rest);
+    });
+    let (stream, _) = listener.accept().unwrap();
+    assert!(Connection::accept(stream).is_err());
+    client.join().unwrap();
+}
+
+#[test]
+fn rejects_invalid_messages() {
+    let mut server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    server.takeover.grace = Duration::ZERO;
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        // Seat 0 bids first.
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        server.host_game(&mut seats, game)
+    });
+
+    // Seat 0 talks the protocol by hand; the others are bots.
+    let stream = TcpStream::connect(addr).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    let mut receive = || {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        ServerMessage::from_json(&line.parse::<Json>().unwrap()).unwrap()
+    };
+    let join = ClientMessage::Join {
+        name: "manual".to_string(),
+        token: None,
+    };
+    writeln!(writer, "{}", join.to_json()).unwrap();
+    assert!(matches!(receive(), ServerMessage::Welcome { seat: 0, .. }));
+
+    let bots = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, "bot").unwrap();
+                client.play_hand(&RandomPlayer {}, |_| {}).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    assert!(matches!(receive(), ServerMessage::Bidding(_)));
+    writeln!(writer, "not json").unwrap();
+    assert!(matches!(receive(), ServerMessage::Error(_)));
+    assert!(matches!(receive(), ServerMessage::Bidding(_)));
+    writeln!(writer, "{}{}", "[".repeat(30_000), "]".repeat(30_000)).unwrap();
+    assert!(matches!(receive(), ServerMessage::Error(_)));
+    assert!(matches!(receive(), ServerMessage::Bidding(_)));
+    writeln!(writer, r#"{{"type":"bid","contract":"S5"}}"#).unwrap();
+    assert!(matches!(receive(), ServerMessage::Error(_)));
+    assert!(matches!(receive(), ServerMessage::Bidding(_)));
+    writeln!(writer, r#"{{"type":"play","action":"SA"}}"#).unwrap();
+    assert!(matches!(receive(), ServerMessage::Error(_)));
+    assert!(matches!(receive(), ServerMessage::Bidding(_)));
+    writeln!(writer, r#"{{"type":"bid","contract":null}}"#).unwrap();
+    assert!(matches!(
+        receive(),
+        ServerMessage::Event(Event::Bid {
+            player: 0,
+            contract: None
+        })
+    ));
+
+    // A bot plays out the hand once seat 0 leaves.
+    writer.shutdown(Shutdown::Both).unwrap();
+    assert!(host.join().unwrap().is_ok());
+    for bot in bots {
+        let events = bot.join().unwrap();
+        assert!(events.contains(&Event::Away { player: 0 }));
+    }
+}
+
+/// Plays at random, claiming the rest of the hand on the first and last
+/// rounds: too early to be checked, then sure to hold.
+struct Claimer;
+
+impl Player for Claimer {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        RandomPlayer {}.bidding(state)
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        RandomPlayer {}.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        RandomPlayer {}.play_action(state)
+    }
+
+    fn claim(&self, state: &ExposedGameState) -> Option<Claim> {
+        [0, 9].contains(&state.round).then_some(Claim::Tricks(0))
+    }
+}
+
+#[test]
+fn clients_claim_the_rest_of_a_hand() {
+    let server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        server.host_game(&mut seats, game).unwrap()
+    });
+    let clients = (0..5)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, "claimer").unwrap();
+                client.play_hand(&Claimer, |_| {}).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    assert!(host.join().unwrap().played().is_some());
+    for client in clients {
+        let events = client.join().unwrap();
+        let claims = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Claim { accepted, .. } => Some(*accepted),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // Every seat is turned down on the first round, and each plays on
+        // after that; the first claim on the last round ends the hand.
+        assert_eq!(claims, [vec![false; 5], vec![true]].concat());
+        let plays = events
+            .iter()
+            .filter(|e| matches!(e, Event::Play { .. }))
+            .count();
+        assert_eq!(plays, 50);
+    }
+}
+
+#[test]
+fn client_takes_its_seat_back() {
+    let server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        server.host_game(&mut seats, game).unwrap()
+    });
+
+    let mut client = Client::connect(addr, "human").unwrap();
+    let bots = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, "bot").unwrap();
+                client.play_hand(&RandomPlayer {}, |_| {}).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Drop out when first asked to play a card.
+    let player = RandomPlayer {};
+    loop {
+        match client.receive().unwrap() {
+            ServerMessage::Turn { .. } => break,
+            message => {
+                if let Some(answer) = message.answer(&player) {
+                    client.send(&answer).unwrap();
+                }
+            }
+        }
+    }
+    let (seat, token) = (client.seat, client.token.clone());
+    drop(client);
+
+    let mut client = Client::rejoin(addr, "human", &token).unwrap();
+    assert_eq!(client.seat, seat);
+    let events = client.play_hand(&player, |_| {}).unwrap();
+    assert!(host.join().unwrap().played().is_some());
+    for bot in bots {
+        assert_eq!(bot.join().unwrap(), events);
+    }
+    assert!(!events.contains(&Event::Away { player: seat }));
+}
+
+#[test]
+fn browser_plays_over_a_websocket() {
+    let server = Server::bind("127.0.0.1:0", RuleSet::default()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || {
+        let mut seats = server.accept_table().unwrap();
+        let game = PledgePhase::seeded(0, server.rules.min_pledge, 7);
+        server.host_game(&mut seats, game).unwrap()
+    });
+
+    // Opening the address gives the page and takes no seat.
+    let mut page = TcpStream::connect(addr).unwrap();
+    write!(page, "GET / HTTP/1.1\r\nHost: {}\r\n\r\n", addr).unwrap();
+    let mut response = String::new();
+    page.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with(web::PAGE));
+
+    let stream = TcpStream::connect(addr).unwrap();
+    let (mut socket, _) = tungstenite::client(format!("ws://{}/", addr), stream).unwrap();
+    let send = |socket: &mut WebSocket<TcpStream>, message: ClientMessage| {
+        let text = message.to_json().to_string();
+        socket.send(Message::text(text)).unwrap();
+    };
+    let receive = |socket: &mut WebSocket<TcpStream>| {
+        let message = socket.read().unwrap();
+        let json = message.to_text().unwrap().parse::<Json>().unwrap();
+        ServerMessage::from_json(&json).unwrap()
+    };
+    let join = ClientMessage::Join {
+        name: "browser".to_string(),
+        token: None,
+    };
+    send(&mut socket, join);
+    assert!(matches!(
+        receive(&mut socket),
+        ServerMessage::Welcome { seat: 0, .. }
+    ));
+
+    let bots = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::connect(addr, "bot").unwrap();
+                client.play_hand(&RandomPlayer {}, |_| {}).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let player = RandomPlayer {};
+    let mut events = vec![];
+    loop {
+        match receive(&mut socket) {
+            ServerMessage::Bidding(state) => {
+                send(&mut socket, ClientMessage::Bid(player.bidding(&state)))
+            }
+            ServerMessage::Declare(state) => {
+                let (contract, partner_condition, discards) = player.declare_plan(state);
+                let plan = ClientMessage::Plan {
+                    contract,
+                    partner_condition,
+                    discards,
+                };
+                send(&mut socket, plan);
+            }
+            ServerMessage::Turn { state, .. } => {
+                send(&mut socket, ClientMessage::Play(player.play_action(state)))
+            }
+            ServerMessage::Event(event) => {
+                let ends_hand = event.ends_hand();
+                events.push(event);
+                if ends_hand {
+                    break;
+                }
+            }
+            ServerMessage::State(_) => {}
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+    assert!(host.join().unwrap().played().is_some());
+    for bot in bots {
+        assert_eq!(bot.join().unwrap(), events);
+    }
+}
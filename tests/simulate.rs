@@ -0,0 +1,51 @@
+use mighty_rs::clock::TimeControl;
+use mighty_rs::play_phase::EarlyEnd;
+use mighty_rs::player::check_player;
+use mighty_rs::rules::RuleSet;
+use mighty_rs::simulate::Simulation;
+
+fn rows(threads: usize) -> Vec<String> {
+    let simulation = Simulation {
+        games: 24,
+        seed: 5,
+        lineup: ["heuristic"; 5].map(str::to_string).to_vec(),
+        rules: RuleSet::default(),
+        threads,
+        time_control: TimeControl::default(),
+        early_end: EarlyEnd::Never,
+    };
+    let summaries = simulation.run().unwrap();
+    summaries.iter().map(|s| s.to_csv()).collect()
+}
+
+#[test]
+fn thread_count_does_not_change_the_games() {
+    let single = rows(1);
+    assert_eq!(single.len(), 24);
+    assert_eq!(rows(4), single);
+}
+
+#[test]
+fn lineups_are_checked_without_making_the_players() {
+    let marker = std::env::temp_dir().join(format!("mighty-lineup-{}", std::process::id()));
+    let bot = format!("exec:touch {}", marker.display());
+    assert_eq!(check_player(&bot), Ok(()));
+    assert!(!marker.exists());
+
+    assert!(check_player("exec:").is_err());
+    assert!(check_player("mlp:/no/such/model").is_err());
+    assert!(check_player("minimax").is_err());
+
+    let simulation = Simulation {
+        games: 0,
+        seed: 0,
+        lineup: ["random", "random", "random", "random", "cfr:/no/such/table"]
+            .map(str::to_string)
+            .to_vec(),
+        rules: RuleSet::default(),
+        threads: 1,
+        time_control: TimeControl::default(),
+        early_end: EarlyEnd::Never,
+    };
+    assert!(simulation.run().is_err());
+}
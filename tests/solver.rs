@@ -0,0 +1,116 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::card::Suit;
+use mighty_rs::common::{Contract, PartnerCondition, PlayerIndex};
+use mighty_rs::extra_phase::ExtraPhase;
+use mighty_rs::play_phase::PlayPhase;
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::solver::Solver;
+
+/// A seeded deal with seat 0 declaring `trump` and calling the first card
+/// of seat 1 as friend, played out at random up to `round`.
+fn endgame(seed: u64, trump: Option<Suit>, round: u8) -> PlayPhase {
+    let deal = PledgePhase::seeded(0, 13, seed);
+    let mut hands = deal.hands.clone();
+    hands[0].extend(deal.bottom.iter().copied());
+    let mut cards = hands[0].iter().copied().collect::<Vec<_>>();
+    cards.sort_by_key(|c| c.index());
+    let friend = *hands[1].iter().min_by_key(|c| c.index()).unwrap();
+    let contract = Contract {
+        suit: trump,
+        count: 13,
+    };
+    let mut extra = ExtraPhase {
+        hands,
+        contract,
+        declarer: 0,
+    };
+    let condition = PartnerCondition::CardCondition(friend);
+    let mut game = extra.submit_plan(contract, condition, cards[..3].iter().copied().collect());
+    while game.round < round {
+        let player = game.turn_player().unwrap();
+        let action = RandomPlayer {}.play_action(game.play_state(player));
+        game.player_acts(player, action);
+    }
+    game
+}
+
+fn is_leading(game: &PlayPhase, player: PlayerIndex) -> bool {
+    let friend = game.partner_revealed.or(match game.partner_condition {
+        PartnerCondition::CardCondition(card) => (0..5).find(|&p| game.hands[p].contains(&card)),
+        _ => None,
+    });
+    player == game.declarer || Some(player) == friend
+}
+
+/// Plain minimax over every legal action, without any pruning.
+fn minimax(game: &PlayPhase) -> u8 {
+    let Some(player) = game.turn_player() else {
+        return game.declarer_team_score();
+    };
+    let values = game.legal_actions(player).into_iter().map(|action| {
+        let mut next = game.clone();
+        next.player_acts(player, action);
+        minimax(&next)
+    });
+    if is_leading(game, player) {
+        values.max().unwrap()
+    } else {
+        values.min().unwrap()
+    }
+}
+
+const TRUMPS: [Option<Suit>; 3] = [Some(Suit::S), Some(Suit::H), None];
+
+#[test]
+fn solutions_match_minimax() {
+    for seed in 0..12 {
+        let game = endgame(seed, TRUMPS[seed as usize % 3], 7);
+        let solution = Solver::new().solve(&game);
+        assert_eq!(solution.points, minimax(&game), "seed {}", seed);
+
+        let player = game.turn_player().unwrap();
+        let mut next = game.clone();
+        next.player_acts(player, solution.action.unwrap());
+        assert_eq!(minimax(&next), solution.points, "seed {}", seed);
+    }
+}
+
+#[test]
+fn action_values_match_minimax() {
+    for seed in 0..6 {
+        let mut game = endgame(seed, Some(Suit::D), 7);
+        // Start from the middle of a round as well.
+        let player = game.turn_player().unwrap();
+        let first = game.legal_actions(player)[0];
+        game.player_acts(player, first);
+        let player = game.turn_player().unwrap();
+        for (action, value) in Solver::new().action_values(&game) {
+            let mut next = game.clone();
+            next.player_acts(player, action);
+            assert_eq!(value, minimax(&next), "seed {} action {:?}", seed, action);
+        }
+    }
+}
+
+#[test]
+fn one_solver_serves_many_hands() {
+    // The same cards left under another trump or declarer make a different
+    // game, which the reused solver must not mistake for one seen before.
+    let mut shared = Solver::new();
+    for seed in 0..4 {
+        for trump in TRUMPS {
+            let game = endgame(seed, trump, 6);
+            let fresh = Solver::new().solve(&game).points;
+            assert_eq!(shared.solve(&game).points, fresh);
+            let switched = PlayPhase {
+                contract: Contract {
+                    suit: TRUMPS.into_iter().find(|&t| t != trump).unwrap(),
+                    ..game.contract
+                },
+                ..game.clone()
+            };
+            let fresh = Solver::new().solve(&switched).points;
+            assert_eq!(shared.solve(&switched).points, fresh);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use mighty_rs::bidding_phase::{BiddingState, PledgePhase};
+use mighty_rs::card::Card;
+use mighty_rs::clock::TimeControl;
+use mighty_rs::common::{Contract, PartnerCondition, PlayAction};
+use mighty_rs::dataset::{DatasetFormat, DatasetWriter, GameData, View};
+use mighty_rs::encoding::{Action, Layout};
+use mighty_rs::event::Event;
+use mighty_rs::extra_phase::ExtraExposedState;
+use mighty_rs::game::{play_game, play_hand, HandEnd};
+use mighty_rs::heuristic::{HeuristicPlayer, Weights};
+use mighty_rs::json::Json;
+use mighty_rs::play_phase::{EarlyEnd, ExposedGameState};
+use mighty_rs::player::{Player, RandomPlayer};
+use mighty_rs::protocol::{accept_plan, ClientMessage, ServerMessage};
+use mighty_rs::record::GameRecord;
+use mighty_rs::rules::RuleSet;
+use std::collections::HashSet;
+use std::fs;
+
+/// Bids the lowest contract first in the pledge and gives up every hand.
+struct Quitter;
+
+impl Player for Quitter {
+    fn bidding(&self, state: &BiddingState) -> Option<Contract> {
+        (state.players_remaining == 5).then_some(Contract {
+            suit: None,
+            count: state.min_effective_count,
+        })
+    }
+
+    fn declare_plan(
+        &self,
+        state: ExtraExposedState,
+    ) -> (Contract, PartnerCondition, HashSet<Card>) {
+        RandomPlayer {}.declare_plan(state)
+    }
+
+    fn play_action(&self, state: ExposedGameState) -> PlayAction {
+        RandomPlayer {}.play_action(state)
+    }
+
+    fn surrender(&self, _state: &ExtraExposedState) -> bool {
+        true
+    }
+}
+
+fn surrendered_hand() -> (HandEnd, GameRecord) {
+    let players = (0..5)
+        .map(|_| Box::new(Quitter) as Box<dyn Player>)
+        .collect::<Vec<_>>();
+    let deal = PledgePhase::seeded(0, 13, 3);
+    play_hand(&players, deal, TimeControl::default(), EarlyEnd::Never)
+}
+
+#[test]
+fn surrendering_pays_a_fixed_loss() {
+    let rules = RuleSet {
+        surrender_payment: 3,
+        ..RuleSet::default()
+    };
+    let (end, record) = surrendered_hand();
+    let HandEnd::Surrendered(surrender) = &end else {
+        panic!("the hand ended as {:?}", end);
+    };
+    let payouts = end.payouts(&rules);
+    assert_eq!(payouts.iter().sum::<i32>(), 0);
+    for (seat, payout) in payouts.into_iter().enumerate() {
+        let expected = if seat == surrender.declarer { -12 } else { 3 };
+        assert_eq!(payout, expected);
+    }
+    assert_eq!(
+        rules.settle_surrender(surrender).payouts,
+        end.payouts(&rules)
+    );
+    assert!(record.actions.is_empty() && record.plan.is_none());
+}
+
+#[test]
+fn surrendered_records_are_read_back() {
+    let (end, record) = surrendered_hand();
+    let text = record.to_string();
+    assert!(text.ends_with("surrender\n"));
+    let parsed = text.parse::<GameRecord>().unwrap();
+    assert_eq!(parsed, record);
+    assert_eq!(parsed.moves(), record.bids.len() + 1);
+    assert!(parsed.play_phase(0).is_err());
+    let surrender = parsed.extra_phase().unwrap().surrender();
+    assert!(matches!(end, HandEnd::Surrendered(s) if s == surrender));
+}
+
+#[test]
+fn declarers_surrender_over_the_protocol() {
+    let (_, record) = surrendered_hand();
+    let state = record.extra_phase().unwrap().extra_state();
+    let answer = ServerMessage::Declare(state.clone())
+        .answer(&Quitter)
+        .unwrap();
+    assert!(matches!(answer, ClientMessage::Surrender));
+    let json = answer.to_json();
+    assert_eq!(json.to_string(), r#"{"type":"surrender"}"#);
+    let parsed = ClientMessage::from_json(&json).unwrap();
+    assert_eq!(accept_plan(&state, parsed), Ok(None));
+
+    let event = Event::Surrender { declarer: 2 };
+    assert_eq!(Event::from_json(&event.to_json()), Ok(event));
+}
+
+#[test]
+fn heuristic_bots_give_up_weak_hands() {
+    let play = |surrender_value| {
+        let weights = Weights {
+            surrender_value,
+            ..Weights::default()
+        };
+        let players = (0..5)
+            .map(|_| Box::new(HeuristicPlayer::new(weights.clone())) as Box<dyn Player>)
+            .collect::<Vec<_>>();
+        (0..10)
+            .map(|seed| play_game(&players, PledgePhase::seeded(0, 13, seed)))
+            .filter(|end| !matches!(end, HandEnd::Cancelled))
+            .collect::<Vec<_>>()
+    };
+    let ends = play(100.0);
+    assert!(!ends.is_empty());
+    assert!(ends
+        .iter()
+        .all(|end| matches!(end, HandEnd::Surrendered(_))));
+    let ends = play(Weights::default().surrender_value);
+    assert!(ends.into_iter().all(|end| end.played().is_some()));
+}
+
+#[test]
+fn surrenders_are_kept_as_decisions() {
+    let (_, record) = surrendered_hand();
+    let rules = RuleSet::default();
+    let game = GameData::from_record(0, &record, &rules).unwrap();
+    assert_eq!(game.decisions.len(), record.bids.len() + 1);
+    let last = game.decisions.last().unwrap();
+    assert_eq!(last.action, Action::Surrender);
+    assert!(matches!(last.view, View::Declaring(..)));
+    assert!(last.legal.contains(&Action::Surrender));
+
+    // The first layout has no row for the surrender.
+    let dir = std::env::temp_dir().join(format!("mighty-surrender-{}", std::process::id()));
+    let mut writer = DatasetWriter::new(&dir, DatasetFormat::Columns).unwrap();
+    writer.layout = Layout::V1;
+    let shard = writer.write_shard(&[game]).unwrap();
+    let meta: Json = fs::read_to_string(shard.join("meta.json"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let rows = record.bids.len() as u64;
+    assert_eq!(meta.field("rows").and_then(Json::as_u64), Ok(rows));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,33 @@
+use mighty_rs::clock::TimeControl;
+use mighty_rs::player::RandomPlayer;
+use mighty_rs::rules::RuleSet;
+use mighty_rs::tournament::{Entrant, Tournament};
+
+fn tournament(entrants: usize, games: usize) -> Tournament {
+    Tournament {
+        entrants: (0..entrants)
+            .map(|i| Entrant::new(&format!("random {}", i), || Box::new(RandomPlayer {})))
+            .collect(),
+        rules: RuleSet::default(),
+        games,
+        seed: 1,
+        time_control: TimeControl::default(),
+    }
+}
+
+#[test]
+fn every_entrant_sits_in_every_seat_equally_often() {
+    // Entrants times the distinct seatings of each lineup.
+    for (entrants, cycle) in [(1, 1), (2, 20), (3, 90), (4, 240), (5, 600)] {
+        let tournament = tournament(entrants, cycle);
+        let mut counts = vec![[0; 5]; entrants];
+        for game in 0..cycle {
+            let seating = tournament.seating(game);
+            for (seat, &entrant) in seating.iter().enumerate() {
+                counts[entrant][seat] += 1;
+            }
+        }
+        let each = cycle / entrants;
+        assert!(counts.iter().flatten().all(|&c| c == each), "{:?}", counts);
+    }
+}
@@ -0,0 +1,139 @@
+use mighty_rs::bidding_phase::PledgePhase;
+use mighty_rs::encoding::Layout;
+use mighty_rs::game::play_game;
+use mighty_rs::mlp::{softmax, Mlp};
+use mighty_rs::player::create_player;
+use mighty_rs::train::{TrainConfig, Trainer};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn small_config() -> TrainConfig {
+    TrainConfig {
+        hidden: vec![8],
+        batch: 2,
+        iterations: 2,
+        eval_every: 1,
+        eval_hands: 2,
+        ..TrainConfig::default()
+    }
+}
+
+#[test]
+fn gradient_steps_favour_rewarded_actions() {
+    let layout = Layout::LATEST;
+    let mut network = Mlp::new(layout, &[16, 8], &mut StdRng::seed_from_u64(1));
+    let features = (0..layout.observation_size())
+        .map(|i| (i % 3) as f32)
+        .collect::<Vec<_>>();
+    let legal = [3, 40, 250];
+    let before = softmax(&network.forward(&features), &legal)[1];
+    assert!((before - 1.0 / 3.0).abs() < 1e-6);
+    for _ in 0..5 {
+        let mut gradient = network.zero_gradient();
+        let chosen = network.accumulate(&mut gradient, &features, &legal, 40, 1.0);
+        assert!(chosen > 0.0);
+        network.apply(&gradient, 0.1);
+    }
+    let after = softmax(&network.forward(&features), &legal)[1];
+    assert!(after > before);
+}
+
+#[test]
+fn checkpoints_load_back() {
+    let network = Mlp::new(Layout::LATEST, &[4], &mut StdRng::seed_from_u64(2));
+    let path = std::env::temp_dir().join(format!("mighty-mlp-{}.bin", std::process::id()));
+    network.save(&path).unwrap();
+    let loaded = Mlp::load(&path).unwrap();
+    assert_eq!(loaded.hidden(), &[4]);
+    let features = vec![1.0; Layout::LATEST.observation_size()];
+    assert_eq!(loaded.forward(&features), network.forward(&features));
+
+    // A saved network plays as a bot.
+    let name = format!("mlp:{}", path.display());
+    let players = (0..5)
+        .map(|_| create_player(&name).unwrap())
+        .collect::<Vec<_>>();
+    let game = play_game(&players, PledgePhase::seeded(0, 13, 3))
+        .played()
+        .unwrap();
+    assert!(game.is_done());
+
+    std::fs::write(&path, b"not a network").unwrap();
+    assert!(Mlp::load(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+    assert!(Mlp::load("/no/such/network.bin").is_err());
+}
+
+#[test]
+fn training_reports_and_saves_checkpoints() {
+    let path = std::env::temp_dir().join(format!("mighty-train-{}.bin", std::process::id()));
+    let mut trainer = Trainer::new(TrainConfig {
+        checkpoint: Some(path.clone()),
+        ..small_config()
+    });
+    let mut reports = vec![];
+    trainer
+        .run(|progress| reports.push(progress.clone()))
+        .unwrap();
+    assert_eq!(reports.len(), 2);
+    for progress in &reports {
+        // Two hands of ten rounds.
+        assert_eq!(progress.decisions, 100);
+        assert!(progress.entropy > 0.0);
+        assert_eq!(progress.evaluation.unwrap().hands, 10);
+    }
+    assert!(Mlp::load(&path).is_ok());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn training_is_repeatable_by_seed() {
+    let features = vec![1.0; Layout::LATEST.observation_size()];
+    let train = || {
+        let mut trainer = Trainer::new(small_config());
+        trainer.iteration();
+        trainer.iteration();
+        trainer.network.forward(&features)
+    };
+    assert_eq!(train(), train());
+
+    let trainer = Trainer::new(TrainConfig {
+        opponent: "nobody".to_string(),
+        ..small_config()
+    });
+    assert!(trainer.evaluate().is_err());
+}
+
+#[test]
+fn loaded_checkpoints_go_on_where_training_stopped() {
+    let dir = std::env::temp_dir();
+    let whole = dir.join(format!("mighty-whole-{}.bin", std::process::id()));
+    let halves = dir.join(format!("mighty-halves-{}.bin", std::process::id()));
+    let config = |iterations, path: &std::path::PathBuf| TrainConfig {
+        iterations,
+        eval_every: 0,
+        checkpoint: Some(path.clone()),
+        ..small_config()
+    };
+
+    let mut trainer = Trainer::new(config(2, &whole));
+    trainer.run(|_| {}).unwrap();
+    Trainer::new(config(1, &halves)).run(|_| {}).unwrap();
+    let mut resumed = Trainer::load(config(2, &halves), &halves).unwrap();
+    assert_eq!(resumed.iteration, 1);
+    let mut reported = vec![];
+    resumed.run(|p| reported.push(p.iteration)).unwrap();
+    assert_eq!(reported, [2]);
+
+    let features = vec![1.0; Layout::LATEST.observation_size()];
+    assert_eq!(
+        resumed.network.forward(&features),
+        trainer.network.forward(&features)
+    );
+    for path in [whole, halves] {
+        let mut state = path.clone().into_os_string();
+        state.push(".state");
+        std::fs::remove_file(state).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}